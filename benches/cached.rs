@@ -0,0 +1,21 @@
+/*!
+Compares deobfuscating a string on every use against the per thread `#[cached]` form.
+
+```
+cargo bench --bench cached --features std
+```
+*/
+
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, Criterion};
+use obfstr::obfstr;
+
+fn benches(c: &mut Criterion) {
+	let mut group = c.benchmark_group("query");
+	group.bench_function("obfstr", |b| b.iter(|| black_box(obfstr!("SELECT id, name, email FROM users WHERE id = ? AND active = 1")).len()));
+	group.bench_function("cached", |b| b.iter(|| black_box(obfstr!(#[cached] "SELECT id, name, email FROM users WHERE id = ? AND active = 1")).len()));
+	group.finish();
+}
+
+criterion_group!(cached, benches);
+criterion_main!(cached);
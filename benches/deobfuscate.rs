@@ -0,0 +1,35 @@
+/*!
+Compares the vectorized `deobfuscate` and `equals` against the word-at-a-time `deobfuscate64`.
+
+```
+cargo bench --bench deobfuscate
+```
+*/
+
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, Criterion};
+use obfstr::bytes;
+
+const STRING: &[u8] = &[b'x'; 512];
+
+fn bench<const LEN: usize, const WORDS: usize>(c: &mut Criterion) {
+	let keys = bytes::keystream::<LEN>(0x1234);
+	let keys64 = bytes::keystream64::<WORDS>(0x1234);
+	let data = bytes::obfuscate::<LEN>(&STRING[..LEN], &keys);
+	let data64 = bytes::obfuscate64::<LEN, WORDS>(&STRING[..LEN], &keys64);
+
+	let mut group = c.benchmark_group(format!("{}", LEN));
+	group.bench_function("deobfuscate", |b| b.iter(|| bytes::deobfuscate::<LEN>(black_box(&data), &keys)));
+	group.bench_function("deobfuscate64", |b| b.iter(|| bytes::deobfuscate64::<LEN, WORDS>(black_box(&data64), &keys64)));
+	group.bench_function("equals", |b| b.iter(|| bytes::equals::<LEN>(black_box(&data), &keys, black_box(&STRING[..LEN]))));
+	group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+	bench::<64, 8>(c);
+	bench::<256, 32>(c);
+	bench::<512, 64>(c);
+}
+
+criterion_group!(deobfuscate, benches);
+criterion_main!(deobfuscate);
@@ -31,6 +31,19 @@ fn obfstmt() -> i32 {
 	i
 }
 
+#[inline(never)]
+fn obfstmt_junk() -> i32 {
+	let mut i = 0;
+	obfstr::obfstmt!(junk = 3; {
+		i = 5;
+		i *= 24;
+		i -= 10;
+		i += 8;
+	});
+	assert_eq!(i, 118);
+	i
+}
+
 #[inline(never)]
 fn obfstr() {
 	print(obfstr::obfstr!("Hello world!"));
@@ -38,16 +51,79 @@ fn obfstr() {
 	print(obfstr::obfstr!("This literal is very very very long to see if it correctly handles long strings"));
 }
 
+#[inline(never)]
+fn immediate() {
+	print(obfstr::obfstr!(#[immediate] "Hello world!"));
+	print(obfstr::obfstr!(#[immediate] "Immediates in the code"));
+}
+
 #[inline(never)]
 fn xref() -> &'static i32 {
 	static FOO: i32 = 3141592;
 	obfstr::xref!(&FOO)
 }
 
+#[inline(never)]
+fn xref_slice() -> &'static [u16] {
+	static TABLE: [u16; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+	obfstr::xref_slice!(&TABLE[..])
+}
+
+#[inline(never)]
+fn xref_chain() -> &'static i32 {
+	static BAR: i32 = 2718281;
+	obfstr::xref!(3; &BAR)
+}
+
+#[inline(never)]
+fn xref_fn() -> u64 {
+	let f: fn(u64) -> u64 = obfstr::xref_fn!(fibonacci);
+	f(42)
+}
+
+#[inline(never)]
+fn obfindex() -> u16 {
+	static TABLE: [u16; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+	obfstr::obfindex!(TABLE, 7)
+}
+
+#[inline(never)]
+fn obfconst() -> (u32, f64) {
+	(obfstr::obfconst!(0xDEADBEEFu32), obfstr::obfconst!(1.0e-9f64))
+}
+
+#[inline(never)]
+fn opaque(x: u32) -> u32 {
+	if obfstr::obftrue!() { x.wrapping_mul(3) } else { x ^ 0x5a5a5a5a }
+}
+
+#[inline(never)]
+fn obfbranch(x: i32) -> &'static str {
+	obfstr::obfbranch!(x < 0, { "negative" }, { "positive" })
+}
+
+#[inline(never)]
+fn fibonacci(n: u64) -> u64 {
+	let (mut a, mut b) = (0u64, 1u64);
+	for _ in 0..n {
+		(a, b) = (b, a.wrapping_add(b));
+	}
+	a
+}
+
 fn main() {
 	println!("obfstmt: {}", obfstmt());
+	println!("obfstmt_junk: {}", obfstmt_junk());
 	obfstr();
+	immediate();
 	println!("xref: {}", xref());
+	println!("xref_slice: {:?}", xref_slice());
+	println!("xref_chain: {}", xref_chain());
+	println!("xref_fn: {}", xref_fn());
+	println!("obfindex: {}", obfindex());
+	println!("obfconst: {:?}", obfconst());
+	println!("opaque: {}", opaque(14));
+	println!("obfbranch: {}", obfbranch(-3));
 }
 
 #[inline(never)]
@@ -4,7 +4,7 @@ The example presented here uses an enum because it can be challenging to return
  */
 
 use std::fmt;
-use obfstr::{obfstr, obfstring, position};
+use obfstr::{obfpool, obfstr, obfstring, pool_get, position_in_segments};
 
 // Let's try to obfuscate the string representation of this enum.
 pub enum Example {
@@ -42,18 +42,35 @@ impl Example {
 	}
 
 	// Allocate the string literals via concatenation.
+	// If a string also appears inside an earlier string, eg. "Bar" in concat!("FooBar", "Bar"),
+	// position! finds the earlier one, use rposition! to find the standalone string at the end.
 	pub const POOL: &'static str = concat!("Foo", "Bar", "Baz");
+	// The strings of the pool, position_in_segments! never matches a string spanning two of them.
+	pub const SEGMENTS: &'static [&'static str] = &["Foo", "Bar", "Baz"];
 
 	// Deobfuscate the POOL constant and pass it here as the pool argument.
 	// This to string implementation will slice the right substring.
 	pub fn to_str4<'a>(&self, pool: &'a str) -> &'a str {
 		match self {
-			Example::Foo => &pool[position!(Example::POOL, "Foo")],
-			Example::Bar => &pool[position!(Example::POOL, "Bar")],
-			Example::Baz => &pool[position!(Example::POOL, "Baz")],
+			Example::Foo => &pool[position_in_segments!(Example::SEGMENTS, "Foo")],
+			Example::Bar => &pool[position_in_segments!(Example::SEGMENTS, "Bar")],
+			Example::Baz => &pool[position_in_segments!(Example::SEGMENTS, "Baz")],
+		}
+	}
+
+	// Deobfuscate the POOL constant defined by obfpool! below and pass it here as the pool argument.
+	pub fn to_str5<'a>(&self, pool: &'a str) -> &'a str {
+		match self {
+			Example::Foo => pool_get(pool, FOO),
+			Example::Bar => pool_get(pool, BAR),
+			Example::Baz => pool_get(pool, BAZ),
 		}
 	}
 }
+
+// Pool the string literals and compute their ranges from the offsets of the entries in the pool.
+obfpool! { pub POOL: FOO = "Foo", BAR = "Bar", BAZ = "Baz"; }
+
 impl fmt::Display for Example {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		self.to_str2(|s| f.write_str(s))
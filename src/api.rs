@@ -0,0 +1,95 @@
+/*!
+Export-by-hash lookup
+=====================
+
+Resolves names such as exported functions by their [`murmur3`](crate::murmur3()) hash so the name itself never exists in the binary.
+*/
+
+use crate::{murmur3, murmur3_nocase, murmur3_utf16, murmur3_utf16_nocase};
+
+/// Hash seed used by [`api_hash!`] when no explicit seed is given.
+///
+/// Derived from [`SEED`](crate::SEED) so every build uses different hashes.
+pub const SEED: u32 = crate::entropy("api_hash") as u32;
+
+/// Compiletime name hash.
+///
+/// Produces only the [`murmur3`](crate::murmur3()) hash of the name, the name itself is not embedded.
+/// The hash is seeded with [`api::SEED`](SEED) unless an explicit seed is given.
+/// The `nocase` form ignores ASCII case.
+///
+/// ```
+/// use obfstr::api;
+///
+/// let exports: [&[u8]; 3] = [b"CloseHandle", b"CreateFileW", b"ReadFile"];
+/// let index = api::find_by_hash(exports.iter().copied(), obfstr::api_hash!("CreateFileW"), api::SEED);
+/// assert_eq!(index, Some(1));
+///
+/// let modules: [&[u16]; 2] = [obfstr::wide!("NTDLL.DLL"), obfstr::wide!("KERNEL32.DLL")];
+/// let index = api::find_by_hash_wide_nocase(modules.iter().copied(), obfstr::api_hash!(nocase "kernel32.dll"), api::SEED);
+/// assert_eq!(index, Some(1));
+/// ```
+#[macro_export]
+macro_rules! api_hash {
+	(nocase $s:expr $(, $seed:expr)?) => {
+		$crate::__api_hash!(murmur3_nocase, $s $(, $seed)?)
+	};
+	($s:expr $(, $seed:expr)?) => {
+		$crate::__api_hash!(murmur3, $s $(, $seed)?)
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __api_hash {
+	($f:ident, $s:expr) => {
+		$crate::__api_hash!($f, $s, $crate::api::SEED)
+	};
+	($f:ident, $s:expr, $seed:expr) => {{ const _API_HASH: u32 = $crate::$f(::core::primitive::str::as_bytes($s), $seed); _API_HASH }};
+}
+
+/// Finds the index of the name matching the hash.
+#[inline]
+pub fn find_by_hash<'a>(names: impl Iterator<Item = &'a [u8]>, hash: u32, seed: u32) -> Option<usize> {
+	names.enumerate().find(|&(_, name)| murmur3(name, seed) == hash).map(|(index, _)| index)
+}
+
+/// Finds the index of the name matching the hash ignoring ASCII case.
+#[inline]
+pub fn find_by_hash_nocase<'a>(names: impl Iterator<Item = &'a [u8]>, hash: u32, seed: u32) -> Option<usize> {
+	names.enumerate().find(|&(_, name)| murmur3_nocase(name, seed) == hash).map(|(index, _)| index)
+}
+
+/// Finds the index of the wide name matching the hash.
+///
+/// The wide names are hashed as if they were UTF-8 encoded, the hash is compatible with [`api_hash!`].
+#[inline]
+pub fn find_by_hash_wide<'a>(names: impl Iterator<Item = &'a [u16]>, hash: u32, seed: u32) -> Option<usize> {
+	names.enumerate().find(|&(_, name)| murmur3_utf16(name, seed) == hash).map(|(index, _)| index)
+}
+
+/// Finds the index of the wide name matching the hash ignoring ASCII case.
+#[inline]
+pub fn find_by_hash_wide_nocase<'a>(names: impl Iterator<Item = &'a [u16]>, hash: u32, seed: u32) -> Option<usize> {
+	names.enumerate().find(|&(_, name)| murmur3_utf16_nocase(name, seed) == hash).map(|(index, _)| index)
+}
+
+#[test]
+fn test_find_by_hash() {
+	static EXPORTS: [&str; 5] = ["CloseHandle", "CreateFileA", "CreateFileW", "ReadFile", "WriteFile"];
+	let names = || EXPORTS.iter().map(|name| name.as_bytes());
+	const HASH: u32 = api_hash!("CreateFileW");
+	assert_eq!(find_by_hash(names(), HASH, SEED), Some(2));
+	assert_eq!(find_by_hash(names(), api_hash!("WriteFile"), SEED), Some(4));
+	assert_eq!(find_by_hash(names(), api_hash!("DeleteFileW"), SEED), None);
+	assert_eq!(find_by_hash(names(), api_hash!("CreateFileW", 42), 42), Some(2));
+	assert_eq!(find_by_hash(names(), api_hash!("createfilew"), SEED), None);
+	assert_eq!(find_by_hash_nocase(names(), api_hash!(nocase "createfilew"), SEED), Some(2));
+
+	let wide: Vec<Vec<u16>> = EXPORTS.iter().map(|name| name.encode_utf16().collect()).collect();
+	assert_eq!(find_by_hash_wide(wide.iter().map(|name| &name[..]), HASH, SEED), Some(2));
+	assert_eq!(find_by_hash_wide_nocase(wide.iter().map(|name| &name[..]), api_hash!(nocase "READFILE"), SEED), Some(3));
+
+	// Only the hash is produced
+	assert_eq!(HASH, murmur3(b"CreateFileW", SEED));
+}
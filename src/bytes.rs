@@ -3,7 +3,11 @@ Byte string obfuscation
 =======================
 */
 
-use core::ptr::{read_volatile, write};
+use core::{fmt, hint, ops, str};
+use core::mem::MaybeUninit;
+#[cfg(target_has_atomic = "8")]
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::ptr::{read_unaligned, read_volatile, write, write_unaligned, write_volatile};
 
 /// Compiletime string constant obfuscation.
 ///
@@ -49,6 +53,101 @@ use core::ptr::{read_volatile, write};
 /// }
 /// let mut buf = [0u8; 16];
 /// assert_eq!(helper(&mut buf), "hello");
+///
+/// // The buffer may be uninitialized
+/// let mut buf = [std::mem::MaybeUninit::uninit(); 16];
+/// assert_eq!(s!(buf <- "uninit"), "uninit");
+/// ```
+///
+/// The keyed form additionally encrypts the string under a runtime key, see [`RUNTIME_KEY`](crate::RUNTIME_KEY).
+/// The string constant in the binary is useless without the key provided at runtime.
+/// Since a wrong key decodes to garbage bytes the UTF-8 is always validated and the result is returned as `Result<&str, Utf8Error>`:
+///
+/// ```
+/// use obfstr::obfstr as s;
+///
+/// let rk = obfstr::RUNTIME_KEY;
+/// assert_eq!(s!(rk => "Hello world"), Ok("Hello world"));
+/// assert_ne!(s!(rk ^ 1 => "Hello world"), Ok("Hello world"));
+/// ```
+///
+/// The `#[selfkey]` form derives part of the key from the address of the obfuscated data, see [`bytes::deobfuscate_selfkeyed`](crate::bytes::deobfuscate_selfkeyed):
+///
+/// ```
+/// use obfstr::obfstr as s;
+///
+/// assert_eq!(s!(#[selfkey] "Hello world"), "Hello world");
+/// ```
+///
+/// The `#[padded]` form pads the obfuscated data to hide the length of the string, the true length is stored obfuscated alongside:
+///
+/// ```
+/// use obfstr::obfstr as s;
+///
+/// assert_eq!(s!(#[padded] "Hello world"), "Hello world");
+///
+/// s! { #[padded] let a = "abc"; let b = "defdef"; }
+/// assert_eq!((a, b), ("abc", "defdef"));
+///
+/// let mut buf = [0u8; 32];
+/// assert_eq!(s!(#[padded] buf <- "Hello world"), "Hello world");
+///
+/// // Only the true length is compared, the padding is never decoded
+/// assert!(obfstr::obfeq!(#[padded] "Hello world", "Hello world"));
+/// ```
+///
+/// There is no `name =` form since the type of the padded buffer cannot be named, use the `buf <-` form instead.
+///
+/// The `#[split]` form splits the obfuscated data in 2 to 4 pieces stored as separate statics:
+///
+/// ```
+/// use obfstr::obfstr as s;
+///
+/// assert_eq!(s!(#[split] "Hello world"), "Hello world");
+/// assert!(obfstr::obfeq!(#[split] "Hello world", "Hello world"));
+/// ```
+///
+/// The `#[immediate]` form encodes strings up to [`IMMEDIATE_MAX`](crate::bytes::IMMEDIATE_MAX) bytes as immediates in the code instead of static data.
+/// Longer strings fall back to static data:
+///
+/// ```
+/// use obfstr::obfstr as s;
+///
+/// assert_eq!(s!(#[immediate] "Hello world"), "Hello world");
+/// ```
+///
+/// The `#[link_section]` form places the obfuscated data, and optionally the key stream, in the given link sections.
+/// The key stream is otherwise embedded in the code. Custom sections are ignored on wasm targets:
+///
+/// ```
+/// use obfstr::obfstr as s;
+///
+/// # #[cfg(target_os = "linux")]
+/// assert_eq!(s!(#[link_section = ".data.obfstr"] "Hello world"), "Hello world");
+/// # #[cfg(target_os = "linux")]
+/// assert_eq!(s!(#[link_section(data = ".data.obfstr", keys = ".text.obfstr")] "Hello world"), "Hello world");
+/// ```
+///
+/// The `#[cached]` form requires the `std` feature, each call site deobfuscates the string once per thread into a thread local buffer, see [`Cache`](crate::bytes::Cache).
+/// Use it for strings in hot loops, the plaintext stays in memory until the thread exits:
+///
+/// ```
+/// use obfstr::obfstr as s;
+///
+/// # #[cfg(feature = "std")]
+/// for _ in 0..3 {
+/// 	assert_eq!(s!(#[cached] "SELECT name FROM users"), "SELECT name FROM users");
+/// }
+/// ```
+///
+/// The `#[verified]` form stores an obfuscated murmur3 checksum of the plaintext and verifies the deobfuscated string against it, see [`verify`](crate::bytes::verify).
+/// On mismatch the hook is called and the string is wiped. The default hook [`verify_failed`](crate::bytes::verify_failed) panics in debug builds and does nothing in release builds:
+///
+/// ```
+/// use obfstr::obfstr as s;
+///
+/// assert_eq!(s!(#[verified] "Hello world"), "Hello world");
+/// assert_eq!(s!(#[verified(|| std::process::abort())] "Hello world"), "Hello world");
 /// ```
 #[macro_export]
 macro_rules! obfstr {
@@ -62,6 +161,43 @@ macro_rules! obfstr {
 	($buf:ident <- $s:expr) => {
 		$crate::unsafe_as_str($crate::obfbytes!($buf <- ::core::primitive::str::as_bytes($s)))
 	};
+	(#[selfkey] $s:expr) => {
+		$crate::unsafe_as_str($crate::obfbytes!(#[selfkey] ::core::primitive::str::as_bytes($s)))
+	};
+	(#[padded] $(let $name:ident = $s:expr;)*) => {$(
+		$crate::obfbytes! { #[padded] let $name = ::core::primitive::str::as_bytes($s); }
+		let $name = $crate::unsafe_as_str($name);
+	)*};
+	(#[padded] $buf:ident <- $s:expr) => {
+		$crate::unsafe_as_str($crate::obfbytes!(#[padded] $buf <- ::core::primitive::str::as_bytes($s)))
+	};
+	(#[padded] $s:expr) => {
+		$crate::unsafe_as_str($crate::obfbytes!(#[padded] ::core::primitive::str::as_bytes($s)))
+	};
+	(#[split] $s:expr) => {
+		$crate::unsafe_as_str($crate::obfbytes!(#[split] ::core::primitive::str::as_bytes($s)))
+	};
+	(#[immediate] $s:expr) => {
+		$crate::unsafe_as_str($crate::obfbytes!(#[immediate] ::core::primitive::str::as_bytes($s)))
+	};
+	(#[cached] $s:expr) => {
+		$crate::unsafe_as_str($crate::obfbytes!(#[cached] ::core::primitive::str::as_bytes($s)))
+	};
+	(#[verified] $s:expr) => {
+		$crate::unsafe_as_str($crate::obfbytes!(#[verified] ::core::primitive::str::as_bytes($s)))
+	};
+	(#[verified($hook:expr)] $s:expr) => {
+		$crate::unsafe_as_str($crate::obfbytes!(#[verified($hook)] ::core::primitive::str::as_bytes($s)))
+	};
+	(#[link_section = $data:literal] $s:expr) => {
+		$crate::unsafe_as_str($crate::obfbytes!(#[link_section = $data] ::core::primitive::str::as_bytes($s)))
+	};
+	(#[link_section(data = $data:literal, keys = $keys:literal)] $s:expr) => {
+		$crate::unsafe_as_str($crate::obfbytes!(#[link_section(data = $data, keys = $keys)] ::core::primitive::str::as_bytes($s)))
+	};
+	($rk:expr => $s:expr) => {
+		::core::str::from_utf8($crate::obfbytes!($rk => ::core::primitive::str::as_bytes($s)))
+	};
 	($s:expr) => {
 		$crate::unsafe_as_str($crate::obfbytes!(::core::primitive::str::as_bytes($s)))
 	};
@@ -108,6 +244,33 @@ macro_rules! obfstring {
 }
 
 /// Compiletime byte string obfuscation.
+///
+/// The keyed form `obfbytes!(rk => s)` decodes to garbage bytes unless `rk` equals [`RUNTIME_KEY`](crate::RUNTIME_KEY).
+///
+/// The `pad` form fills a fixed size slot with the string followed by zero bytes, see [`Slot`](crate::bytes::Slot):
+///
+/// ```
+/// let slot = obfstr::obfbytes!(pad 16, b"short");
+/// assert_eq!(slot.len(), 16);
+/// assert_eq!(slot.as_bytes(), b"short");
+/// assert_eq!(&slot[5..], &[0; 11]);
+///
+/// // Compares against the string only, the padding is never decoded
+/// assert!(obfstr::obfeq_bytes!(pad 16, b"short", b"short"));
+/// ```
+///
+/// The `#[in_place]` form decodes the obfuscated static in place and returns a guard which re-obfuscates it on drop, see [`InPlace`](crate::bytes::InPlace):
+///
+/// ```
+/// let guard = obfstr::obfbytes!(#[in_place] b"long-lived");
+/// assert_eq!(&*guard, b"long-lived");
+/// ```
+///
+/// Strings longer than the slot fail to compile:
+///
+/// ```compile_fail
+/// let slot = obfstr::obfbytes!(pad 4, b"too long");
+/// ```
 #[macro_export]
 macro_rules! obfbytes {
 	($(let $name:ident = $s:expr;)*) => {
@@ -119,52 +282,906 @@ macro_rules! obfbytes {
 	}};
 	($buf:ident <- $s:expr) => {{
 		let buf = &mut $buf[..$s.len()];
-		buf.copy_from_slice(&$crate::__obfbytes!($s));
-		buf
+		$crate::__obfbytes!(buf <- $s)
 	}};
+	(#[selfkey] $s:expr) => {
+		&$crate::__obfbytes_selfkeyed!($s)
+	};
+	(#[padded] $(let $name:ident = $s:expr;)*) => {$(
+		let $name = $crate::__obfbytes_padded!($s);
+		let $name: &[u8] = &*$name;
+	)*};
+	(#[padded] $buf:ident <- $s:expr) => {
+		$crate::__obfbytes_padded!($buf <- $s)
+	};
+	(#[padded] $s:expr) => {
+		&*$crate::__obfbytes_padded!($s)
+	};
+	(#[split] $s:expr) => {
+		&$crate::__obfbytes_split!($s)
+	};
+	(#[immediate] $s:expr) => {
+		&$crate::__obfbytes_immediate!($s)
+	};
+	(#[in_place] $s:expr) => {
+		$crate::__obfbytes_in_place!($s)
+	};
+	(#[cached] $s:expr) => {
+		&*$crate::__obfbytes_cached!($s)
+	};
+	(#[verified] $s:expr) => {
+		&$crate::__obfbytes_verified!($crate::bytes::verify_failed; $s)
+	};
+	(#[verified($hook:expr)] $s:expr) => {
+		&$crate::__obfbytes_verified!($hook; $s)
+	};
+	(#[link_section = $data:literal] $s:expr) => {
+		&$crate::__obfbytes_section!($data; $s)
+	};
+	(#[link_section(data = $data:literal, keys = $keys:literal)] $s:expr) => {
+		&$crate::__obfbytes_section!($data, $keys; $s)
+	};
+	(pad $pad:expr, $s:expr) => {
+		&$crate::__obfbytes_slot!($pad, $s)
+	};
+	($rk:expr => $s:expr) => {
+		&$crate::__obfbytes_keyed!($rk, $s)
+	};
 	($s:expr) => {
 		&$crate::__obfbytes!($s)
 	};
 }
 
+/// Compares a string against an obfuscated string constant.
+///
+/// The string constant is never deobfuscated, instead the keystream is folded into the comparison.
+///
+/// ```
+/// let input = "hello";
+/// assert!(obfstr::obfeq!(input, "hello"));
+/// assert!(!obfstr::obfeq!(input, "world"));
+/// ```
+///
+/// The `wide` form compares against an obfuscated wide string constant.
+/// The other string may be a `&[u16]`, a `&str` encoded on the fly or with the `std` feature on Windows an `&OsStr`.
+/// No plaintext wide string constant is ever constructed.
+///
+/// ```
+/// let module: &[u16] = obfstr::wide!("ntdll.dll");
+/// assert!(obfstr::obfeq!(wide module, "ntdll.dll"));
+/// assert!(obfstr::obfeq!(wide "ntdll.dll", "ntdll.dll"));
+/// assert!(!obfstr::obfeq!(wide "kernel32.dll", "ntdll.dll"));
+/// ```
+#[macro_export]
+macro_rules! obfeq {
+	(wide $e:expr, $s:expr) => {
+		$crate::__obfwide_cmp!(equals_str, $e, $s)
+	};
+	(#[padded] $e:expr, $s:expr) => {
+		$crate::__obfbytes_padded!(equals, ::core::primitive::str::as_bytes($e), ::core::primitive::str::as_bytes($s))
+	};
+	(#[split] $e:expr, $s:expr) => {
+		$crate::__obfbytes_split!(equals, ::core::primitive::str::as_bytes($e), ::core::primitive::str::as_bytes($s))
+	};
+	(pad $pad:expr, $e:expr, $s:expr) => {
+		$crate::__obfbytes_slot!(equals, $pad, ::core::primitive::str::as_bytes($e), ::core::primitive::str::as_bytes($s))
+	};
+	($e:expr, $s:expr) => {
+		$crate::__obfbytes_cmp!(equals, ::core::primitive::str::as_bytes($e), ::core::primitive::str::as_bytes($s))
+	};
+}
+
+/// Compares a byte string against an obfuscated byte string constant.
+///
+/// Unlike [`obfeq!`] no UTF-8 is involved, the obfuscated side may be a byte string literal or a `&[u8]` constant.
+///
+/// ```
+/// const MAGIC: &[u8] = b"MAGIC\x00\xff";
+/// let packet: &[u8] = &[b'M', b'A', b'G', b'I', b'C', 0x00, 0xff];
+/// assert!(obfstr::obfeq_bytes!(packet, MAGIC));
+/// assert!(obfstr::obfeq_bytes!(&packet[..5], b"MAGIC"));
+/// assert!(!obfstr::obfeq_bytes!(&packet[..5], b"MAGIC\0"));
+/// ```
+#[macro_export]
+macro_rules! obfeq_bytes {
+	(#[padded] $e:expr, $s:expr) => {
+		$crate::__obfbytes_padded!(equals, $e, $s)
+	};
+	(#[split] $e:expr, $s:expr) => {
+		$crate::__obfbytes_split!(equals, $e, $s)
+	};
+	(pad $pad:expr, $e:expr, $s:expr) => {
+		$crate::__obfbytes_slot!(equals, $pad, $e, $s)
+	};
+	($e:expr, $s:expr) => {
+		$crate::__obfbytes_cmp!(equals, $e, $s)
+	};
+}
+
+/// Checks if a string starts with an obfuscated string constant.
+///
+/// The prefix is never deobfuscated, see [`obfeq!`] for more information.
+///
+/// ```
+/// let path = "/api/v1/users";
+/// assert!(obfstr::obf_starts_with!(path, "/api/v1/"));
+/// assert!(!obfstr::obf_starts_with!(path, "/api/v2/"));
+/// ```
+#[macro_export]
+macro_rules! obf_starts_with {
+	($e:expr, $s:expr) => {
+		$crate::__obfbytes_cmp!(starts_with, ::core::primitive::str::as_bytes($e), ::core::primitive::str::as_bytes($s))
+	};
+}
+
+/// Checks if a string ends with an obfuscated string constant.
+///
+/// The suffix is never deobfuscated, see [`obfeq!`] for more information.
+///
+/// ```
+/// let file = "kernel32.dll";
+/// assert!(obfstr::obf_ends_with!(file, ".dll"));
+/// assert!(!obfstr::obf_ends_with!(file, ".exe"));
+/// ```
+#[macro_export]
+macro_rules! obf_ends_with {
+	($e:expr, $s:expr) => {
+		$crate::__obfbytes_cmp!(ends_with, ::core::primitive::str::as_bytes($e), ::core::primitive::str::as_bytes($s))
+	};
+}
+
+/// Checks if a string contains an obfuscated string constant.
+///
+/// The haystack may be anything that implements `AsRef<[u8]>` such as `&str` or `&[u8]`.
+/// The needle is decoded one byte at a time inside the search loop, see [`bytes::contains`](crate::bytes::contains).
+///
+/// ```
+/// let cmdline = "program.exe --debug --verbose";
+/// assert!(obfstr::obfcontains!(cmdline, "--debug"));
+/// assert!(!obfstr::obfcontains!(cmdline, "--quiet"));
+/// ```
+#[macro_export]
+macro_rules! obfcontains {
+	($e:expr, $s:expr) => {
+		$crate::__obfbytes_cmp!(contains, ::core::convert::AsRef::<[u8]>::as_ref($e), ::core::primitive::str::as_bytes($s))
+	};
+}
+
+/// Writes an obfuscated string constant to a `fmt::Write` sink.
+///
+/// The string is decoded in small chunks, no buffer for the whole plaintext is ever constructed.
+/// See [`bytes::deobfuscate_to_writer`](crate::bytes::deobfuscate_to_writer).
+///
+/// ```
+/// let mut s = String::new();
+/// obfstr::obfwrite_str!(&mut s, "Hello world").unwrap();
+/// assert_eq!(s, "Hello world");
+/// ```
+#[macro_export]
+macro_rules! obfwrite_str {
+	($w:expr, $s:expr) => {
+		$crate::__obfbytes_cmp!(deobfuscate_to_writer, $w, ::core::primitive::str::as_bytes($s))
+	};
+}
+
+/// Matches a string against obfuscated string constants.
+///
+/// Every arm's string constant is obfuscated independently and compared with [`obfeq!`], no deobfuscated copy of any arm is created.
+/// Arms may list multiple string constants separated by `|` and have an `if` guard, the `_` wildcard arm is required and must come last.
+/// Every arm must be followed by a comma.
+///
+/// To avoid comparing against every arm the input is first hashed with [`murmur3`](crate::murmur3()) using a per call site key.
+/// Only the lowest 8 bits of the hash are compared, storing the full hash of short string constants would allow them to be brute forced.
+/// On a hash match the full comparison decides.
+///
+/// ```
+/// fn dispatch(cmd: &str, force: bool) -> i32 {
+/// 	obfstr::strswitch!(cmd => {
+/// 		"start" | "run" => 1,
+/// 		"stop" if force => 2,
+/// 		"stop" => 3,
+/// 		_ => 0,
+/// 	})
+/// }
+/// assert_eq!(dispatch("start", false), 1);
+/// assert_eq!(dispatch("run", false), 1);
+/// assert_eq!(dispatch("stop", true), 2);
+/// assert_eq!(dispatch("stop", false), 3);
+/// assert_eq!(dispatch("restart", false), 0);
+/// ```
+#[macro_export]
+macro_rules! strswitch {
+	($e:expr => { $($($s:literal)|+ $(if $guard:expr)? => $body:expr,)* _ => $default:expr $(,)? }) => {
+		$crate::__strswitch!($crate::random!(u32, "strswitch"); $e => { $($($s)|+ $(if $guard)? => $body,)* _ => $default })
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __strswitch {
+	($key:expr; $e:expr => { $($($s:literal)|+ $(if $guard:expr)? => $body:expr,)* _ => $default:expr $(,)? }) => {{
+		const _STRSWITCH_KEY: u32 = $key;
+		let _strswitch_input: &[u8] = ::core::primitive::str::as_bytes($e);
+		let _strswitch_hash = $crate::murmur3(_strswitch_input, _STRSWITCH_KEY) as u8;
+		$(
+			if ($(
+				_strswitch_hash == { const _STRSWITCH_HASH: u8 = $crate::murmur3(::core::primitive::str::as_bytes($s), _STRSWITCH_KEY) as u8; _STRSWITCH_HASH } &&
+				$crate::__obfbytes_cmp!(equals, _strswitch_input, ::core::primitive::str::as_bytes($s))
+			)||+) $(&& $guard)? {
+				$body
+			}
+			else
+		)*
+		{
+			$default
+		}
+	}};
+}
+
+/// Compares two obfuscated string constants.
+///
+/// Neither string constant is deobfuscated, both keystreams are folded into the comparison.
+///
+/// ```
+/// const LHS: &str = "duplicated";
+/// const RHS: &str = "duplicated";
+/// assert!(obfstr::obfeq_obf!(LHS, RHS));
+/// assert!(!obfstr::obfeq_obf!(LHS, "different"));
+/// ```
+#[macro_export]
+macro_rules! obfeq_obf {
+	($a:expr, $b:expr) => {{
+		use ::core::primitive::*;
+		const _OBFEQ_A_STRING: &[u8] = ::core::primitive::str::as_bytes($a);
+		const _OBFEQ_A_LEN: usize = _OBFEQ_A_STRING.len();
+		const _OBFEQ_A_KEYSTREAM: [u8; _OBFEQ_A_LEN] = $crate::bytes::keys::<_OBFEQ_A_LEN>($crate::random!(u64, "key", "lhs", stringify!($a)));
+		static _OBFEQ_A_SDATA: [u8; _OBFEQ_A_LEN] = $crate::bytes::obfuscate::<_OBFEQ_A_LEN>(_OBFEQ_A_STRING, &_OBFEQ_A_KEYSTREAM);
+		const _OBFEQ_B_STRING: &[u8] = ::core::primitive::str::as_bytes($b);
+		const _OBFEQ_B_LEN: usize = _OBFEQ_B_STRING.len();
+		const _OBFEQ_B_KEYSTREAM: [u8; _OBFEQ_B_LEN] = $crate::bytes::keys::<_OBFEQ_B_LEN>($crate::random!(u64, "key", "rhs", stringify!($b)));
+		static _OBFEQ_B_SDATA: [u8; _OBFEQ_B_LEN] = $crate::bytes::obfuscate::<_OBFEQ_B_LEN>(_OBFEQ_B_STRING, &_OBFEQ_B_KEYSTREAM);
+		$crate::bytes::equals_obf::<_OBFEQ_A_LEN>(
+			$crate::xref::xref::<_,
+				{$crate::random!(u32, "offset", "lhs", stringify!($a))},
+				{$crate::random!(u64, "xref", "lhs", stringify!($a))}>
+				(&_OBFEQ_A_SDATA),
+			&_OBFEQ_A_KEYSTREAM,
+			$crate::xref::xref::<_,
+				{$crate::random!(u32, "offset", "rhs", stringify!($b))},
+				{$crate::random!(u64, "xref", "rhs", stringify!($b))}>
+				(&_OBFEQ_B_SDATA),
+			&_OBFEQ_B_KEYSTREAM)
+	}};
+}
+
+/// Compares a string against an obfuscated string constant in constant time.
+///
+/// See [`obfeq!`] for more information and [`bytes::ct_equals`](crate::bytes::ct_equals) for the timing guarantees provided.
+///
+/// ```
+/// let token = "0123-4567-89AB";
+/// assert!(obfstr::obfeq_ct!(token, "0123-4567-89AB"));
+/// assert!(!obfstr::obfeq_ct!(token, "0123-4567-89AC"));
+/// ```
+#[macro_export]
+macro_rules! obfeq_ct {
+	($e:expr, $s:expr) => {
+		$crate::__obfbytes_cmp!(ct_equals, ::core::primitive::str::as_bytes($e), ::core::primitive::str::as_bytes($s))
+	};
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __obfbytes {
+	($buf:ident <- $s:expr) => {{
+		use ::core::primitive::*;
+		$crate::__obfbytes_data!($s);
+		let sdata = $crate::xref::xref::<_,
+			{$crate::random!(u32, "offset", stringify!($s))},
+			{$crate::random!(u64, "xref", stringify!($s))}>
+			(&_OBFBYTES_SDATA);
+		if $crate::bytes::SMALL_CODE {
+			let keys: &'static [u8; _OBFBYTES_LEN] = &_OBFBYTES_KEYSTREAM2;
+			$crate::bytes::deobfuscate_small_into::<_OBFBYTES_LEN>(&sdata.0, keys, _OBFBYTES_MASK_PARTS, $buf)
+		}
+		else if $crate::bytes::OUTLINE {
+			// Every call site gets its own stub, the key stream is passed in by the caller
+			#[inline(never)]
+			fn _obfbytes_outline<'a>(
+				sdata: &$crate::bytes::Aligned<[u8; _OBFBYTES_LEN]>,
+				keys2: &[u8; _OBFBYTES_LEN],
+				parts: [u16; 4],
+				dst: &'a mut (impl $crate::bytes::Buffer<u8> + ?Sized)) -> &'a mut [u8]
+			{
+				let keys = $crate::bytes::unmask_keys::<_OBFBYTES_LEN>(keys2, parts);
+				if _OBFBYTES_CIPHER2 {
+					$crate::bytes::deobfuscate2_into::<_OBFBYTES_LEN>(&sdata.0, &keys, dst)
+				}
+				else {
+					$crate::bytes::deobfuscate_into::<_OBFBYTES_LEN>(&sdata.0, &keys, dst)
+				}
+			}
+			_obfbytes_outline(sdata,
+				::core::hint::black_box(&_OBFBYTES_KEYSTREAM2),
+				::core::hint::black_box(_OBFBYTES_MASK_PARTS),
+				$buf)
+		}
+		else if _OBFBYTES_CIPHER2 {
+			let keys = $crate::bytes::unmask_keys::<_OBFBYTES_LEN>(&_OBFBYTES_KEYSTREAM2, _OBFBYTES_MASK_PARTS);
+			$crate::bytes::deobfuscate2_into::<_OBFBYTES_LEN>(&sdata.0, &keys, $buf)
+		}
+		else {
+			let keys = $crate::bytes::unmask_keys::<_OBFBYTES_LEN>(&_OBFBYTES_KEYSTREAM2, _OBFBYTES_MASK_PARTS);
+			$crate::bytes::deobfuscate_into::<_OBFBYTES_LEN>(&sdata.0, &keys, $buf)
+		}
+	}};
 	($s:expr) => {{
+		use ::core::primitive::*;
+		$crate::__obfbytes_data!($s);
+		let sdata = $crate::xref::xref::<_,
+			{$crate::random!(u32, "offset", stringify!($s))},
+			{$crate::random!(u64, "xref", stringify!($s))}>
+			(&_OBFBYTES_SDATA);
+		if $crate::bytes::SMALL_CODE {
+			let keys: &'static [u8; _OBFBYTES_LEN] = &_OBFBYTES_KEYSTREAM2;
+			$crate::bytes::deobfuscate_small::<_OBFBYTES_LEN>(&sdata.0, keys, _OBFBYTES_MASK_PARTS)
+		}
+		else if $crate::bytes::OUTLINE {
+			// Every call site gets its own stub, the key stream is passed in by the caller
+			#[inline(never)]
+			fn _obfbytes_outline(
+				sdata: &$crate::bytes::Aligned<[u8; _OBFBYTES_LEN]>,
+				keys: &[u64; _OBFBYTES_WORDS],
+				keys2: &[u8; _OBFBYTES_LEN],
+				parts: [u16; 4],
+				dst: &mut ::core::mem::MaybeUninit<[u8; _OBFBYTES_LEN]>)
+			{
+				dst.write(if _OBFBYTES_CIPHER2 {
+					$crate::bytes::deobfuscate2::<_OBFBYTES_LEN>(&sdata.0, &$crate::bytes::unmask_keys::<_OBFBYTES_LEN>(keys2, parts))
+				}
+				else if $crate::bytes::MASKED {
+					$crate::bytes::deobfuscate64_masked::<_OBFBYTES_LEN, _OBFBYTES_WORDS>(sdata, keys, parts)
+				}
+				else {
+					$crate::bytes::deobfuscate64::<_OBFBYTES_LEN, _OBFBYTES_WORDS>(sdata, keys)
+				});
+			}
+			let mut buf = ::core::mem::MaybeUninit::uninit();
+			_obfbytes_outline(sdata,
+				::core::hint::black_box(&_OBFBYTES_KEYSTREAM),
+				::core::hint::black_box(&_OBFBYTES_KEYSTREAM2),
+				::core::hint::black_box(_OBFBYTES_MASK_PARTS),
+				&mut buf);
+			unsafe { buf.assume_init() }
+		}
+		else if _OBFBYTES_CIPHER2 {
+			$crate::bytes::deobfuscate2::<_OBFBYTES_LEN>(&sdata.0, &$crate::bytes::unmask_keys::<_OBFBYTES_LEN>(&_OBFBYTES_KEYSTREAM2, _OBFBYTES_MASK_PARTS))
+		}
+		else if $crate::bytes::MASKED {
+			$crate::bytes::deobfuscate64_masked::<_OBFBYTES_LEN, _OBFBYTES_WORDS>(sdata, &_OBFBYTES_KEYSTREAM, _OBFBYTES_MASK_PARTS)
+		}
+		else {
+			$crate::bytes::deobfuscate64::<_OBFBYTES_LEN, _OBFBYTES_WORDS>(sdata, &_OBFBYTES_KEYSTREAM)
+		}
+	}};
+}
+
+// Key stream constants and the obfuscated static shared by the __obfbytes! arms.
+// The 64-bit key stream and the byte key stream derive from the same key and encode the same ciphertext.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbytes_data {
+	($s:expr) => {
+		const _OBFBYTES_STRING: &[u8] = $s;
+		const _OBFBYTES_LEN: usize = _OBFBYTES_STRING.len();
+		const _OBFBYTES_WORDS: usize = _OBFBYTES_LEN.div_ceil(8);
+		const _OBFBYTES_CIPHER2: bool = !$crate::bytes::SMALL_CODE && $crate::random!(bool, "cipher", stringify!($s));
+		const _OBFBYTES_GEN: u8 = $crate::random!(u8, "gen", stringify!($s)) % $crate::bytes::GENERATORS;
+		const _OBFBYTES_MASK: u64 = if $crate::bytes::MASKED { $crate::random!(u64, "mask", stringify!($s)) } else { 0 };
+		const _OBFBYTES_MASK_PARTS: [u16; 4] = $crate::bytes::mask_parts(_OBFBYTES_MASK);
+		const _OBFBYTES_KEY: u64 = $crate::bytes::pick_key::<_OBFBYTES_LEN, _OBFBYTES_WORDS, _OBFBYTES_GEN>(_OBFBYTES_STRING, $crate::random!(u64, "key", stringify!($s)), _OBFBYTES_CIPHER2);
+		const _OBFBYTES_KEYS: [u64; _OBFBYTES_WORDS] = $crate::bytes::keys64_with::<_OBFBYTES_WORDS, _OBFBYTES_GEN>(_OBFBYTES_KEY);
+		const _OBFBYTES_KEYSTREAM: [u64; _OBFBYTES_WORDS] = $crate::bytes::mask64::<_OBFBYTES_WORDS>(&_OBFBYTES_KEYS, _OBFBYTES_MASK);
+		const _OBFBYTES_KEYS2: [u8; _OBFBYTES_LEN] = $crate::bytes::keys_with::<_OBFBYTES_LEN, _OBFBYTES_GEN>(_OBFBYTES_KEY);
+		const _OBFBYTES_KEYSTREAM2: [u8; _OBFBYTES_LEN] = $crate::bytes::fold_key::<_OBFBYTES_LEN>(&_OBFBYTES_KEYS2, _OBFBYTES_MASK);
+		static _OBFBYTES_SDATA: $crate::bytes::Aligned<[u8; _OBFBYTES_LEN]> = if _OBFBYTES_CIPHER2 {
+			$crate::bytes::Aligned($crate::bytes::obfuscate2::<_OBFBYTES_LEN>(_OBFBYTES_STRING, &_OBFBYTES_KEYS2))
+		}
+		else {
+			$crate::bytes::obfuscate64::<_OBFBYTES_LEN, _OBFBYTES_WORDS>(_OBFBYTES_STRING, &_OBFBYTES_KEYS)
+		};
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbytes_keyed {
+	($rk:expr, $s:expr) => {{
 		use ::core::primitive::*;
 		const _OBFBYTES_STRING: &[u8] = $s;
 		const _OBFBYTES_LEN: usize = _OBFBYTES_STRING.len();
-		const _OBFBYTES_KEYSTREAM: [u8; _OBFBYTES_LEN] = $crate::bytes::keystream::<_OBFBYTES_LEN>($crate::random!(u32, "key", stringify!($s)));
-		static _OBFBYTES_SDATA: [u8; _OBFBYTES_LEN] = $crate::bytes::obfuscate::<_OBFBYTES_LEN>(_OBFBYTES_STRING, &_OBFBYTES_KEYSTREAM);
-		$crate::bytes::deobfuscate::<_OBFBYTES_LEN>(
+		const _OBFBYTES_GEN: u8 = $crate::random!(u8, "gen", stringify!($s)) % $crate::bytes::GENERATORS;
+		const _OBFBYTES_KEYSTREAM: [u8; _OBFBYTES_LEN] = $crate::bytes::keys_with::<_OBFBYTES_LEN, _OBFBYTES_GEN>($crate::random!(u64, "key", stringify!($s)));
+		static _OBFBYTES_SDATA: [u8; _OBFBYTES_LEN] = $crate::bytes::obfuscate::<_OBFBYTES_LEN>(_OBFBYTES_STRING,
+			&$crate::bytes::fold_key::<_OBFBYTES_LEN>(&_OBFBYTES_KEYSTREAM, $crate::RUNTIME_KEY));
+		$crate::bytes::deobfuscate_keyed::<_OBFBYTES_LEN>(
+			$crate::xref::xref::<_,
+				{$crate::random!(u32, "offset", stringify!($s))},
+				{$crate::random!(u64, "xref", stringify!($s))}>
+				(&_OBFBYTES_SDATA),
+			&_OBFBYTES_KEYSTREAM,
+			$rk)
+	}};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbytes_selfkeyed {
+	($s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFBYTES_STRING: &[u8] = $s;
+		const _OBFBYTES_LEN: usize = _OBFBYTES_STRING.len();
+		const _OBFBYTES_GEN: u8 = $crate::random!(u8, "gen", stringify!($s)) % $crate::bytes::GENERATORS;
+		const _OBFBYTES_KEYSTREAM: [u8; _OBFBYTES_LEN] = $crate::bytes::keys_with::<_OBFBYTES_LEN, _OBFBYTES_GEN>($crate::random!(u64, "key", stringify!($s)));
+		const _OBFBYTES_BIAS: usize = $crate::random!(u32, "bias", stringify!($s)) as usize;
+		static _OBFBYTES_SDATA: [u8; _OBFBYTES_LEN] = $crate::bytes::obfuscate::<_OBFBYTES_LEN>(_OBFBYTES_STRING,
+			&$crate::bytes::fold_key::<_OBFBYTES_LEN>(&_OBFBYTES_KEYSTREAM, $crate::bytes::selfkey(_OBFBYTES_BIAS)));
+		static _OBFBYTES_ANCHOR: $crate::bytes::Anchor = $crate::bytes::Anchor(_OBFBYTES_SDATA.as_ptr().wrapping_add(_OBFBYTES_BIAS));
+		$crate::bytes::deobfuscate_selfkeyed::<_OBFBYTES_LEN>(
 			$crate::xref::xref::<_,
 				{$crate::random!(u32, "offset", stringify!($s))},
 				{$crate::random!(u64, "xref", stringify!($s))}>
 				(&_OBFBYTES_SDATA),
+			&_OBFBYTES_ANCHOR,
 			&_OBFBYTES_KEYSTREAM)
 	}};
 }
 
-// Simple XorShift to generate the key stream.
-// Security doesn't matter, we just want a number of random-looking bytes.
-#[inline(always)]
-const fn next_round(mut x: u32) -> u32 {
-	x ^= x << 13;
-	x ^= x >> 17;
-	x ^= x << 5;
-	return x;
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbytes_padded {
+	(equals, $other:expr, $s:expr) => {{
+		use ::core::primitive::*;
+		$crate::__obfbytes_padded!(@data $s);
+		$crate::bytes::equals_padded::<_OBFBYTES_PADDED>($crate::__obfbytes_padded!(@xref $s), &_OBFBYTES_KEYSTREAM, _OBFBYTES_LEN_KEY, $other)
+	}};
+	($buf:ident <- $s:expr) => {{
+		use ::core::primitive::*;
+		$crate::__obfbytes_padded!(@data $s);
+		$crate::bytes::deobfuscate_padded_into::<_OBFBYTES_PADDED>($crate::__obfbytes_padded!(@xref $s), &_OBFBYTES_KEYSTREAM, _OBFBYTES_LEN_KEY, &mut $buf[..])
+	}};
+	(@data $s:expr) => {
+		const _OBFBYTES_STRING: &[u8] = $s;
+		const _OBFBYTES_PADDED: usize = $crate::bytes::padded_len(_OBFBYTES_STRING.len(), $crate::random!(u64, "padding", stringify!($s)));
+		const _OBFBYTES_LEN_KEY: u64 = $crate::random!(u64, "len", stringify!($s));
+		const _OBFBYTES_GEN: u8 = $crate::random!(u8, "gen", stringify!($s)) % $crate::bytes::GENERATORS;
+		const _OBFBYTES_KEYSTREAM: [u8; _OBFBYTES_PADDED] = $crate::bytes::keys_with::<_OBFBYTES_PADDED, _OBFBYTES_GEN>($crate::random!(u64, "key", stringify!($s)));
+		static _OBFBYTES_SDATA: $crate::bytes::Padded<[u8; _OBFBYTES_PADDED]> = $crate::bytes::Padded {
+			data: $crate::bytes::obfuscate::<_OBFBYTES_PADDED>(
+				&$crate::bytes::pad::<_OBFBYTES_PADDED>(_OBFBYTES_STRING, $crate::random!(u64, "filler", stringify!($s))),
+				&_OBFBYTES_KEYSTREAM),
+			len: _OBFBYTES_STRING.len() as u64 ^ _OBFBYTES_LEN_KEY,
+		};
+	};
+	(@xref $s:expr) => {
+		$crate::xref::xref::<_,
+			{$crate::random!(u32, "offset", stringify!($s))},
+			{$crate::random!(u64, "xref", stringify!($s))}>
+			(&_OBFBYTES_SDATA)
+	};
+	($s:expr) => {{
+		use ::core::primitive::*;
+		$crate::__obfbytes_padded!(@data $s);
+		$crate::bytes::deobfuscate_padded::<_OBFBYTES_PADDED>($crate::__obfbytes_padded!(@xref $s), &_OBFBYTES_KEYSTREAM, _OBFBYTES_LEN_KEY)
+	}};
 }
 
-/// Generate the key stream for array of given length.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbytes_in_place {
+	($s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFBYTES_STRING: &[u8] = $s;
+		const _OBFBYTES_LEN: usize = _OBFBYTES_STRING.len();
+		const _OBFBYTES_KEYSTREAM: [u8; _OBFBYTES_LEN] = $crate::bytes::keys::<_OBFBYTES_LEN>($crate::random!(u64, "key", stringify!($s)));
+		static _OBFBYTES_SDATA: $crate::bytes::InPlace<_OBFBYTES_LEN> = $crate::bytes::InPlace::new($crate::bytes::obfuscate::<_OBFBYTES_LEN>(_OBFBYTES_STRING, &_OBFBYTES_KEYSTREAM));
+		$crate::xref::xref::<_,
+			{$crate::random!(u32, "offset", stringify!($s))},
+			{$crate::random!(u64, "xref", stringify!($s))}>
+			(&_OBFBYTES_SDATA)
+			.lock(_OBFBYTES_KEYSTREAM)
+	}};
+}
+
+#[cfg(feature = "std")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbytes_cached {
+	($s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFBYTES_STRING: &[u8] = $s;
+		const _OBFBYTES_LEN: usize = _OBFBYTES_STRING.len();
+		static _OBFBYTES_KEYSTREAM: [u8; _OBFBYTES_LEN] = $crate::bytes::keys::<_OBFBYTES_LEN>($crate::random!(u64, "key", stringify!($s)));
+		static _OBFBYTES_SDATA: [u8; _OBFBYTES_LEN] = $crate::bytes::obfuscate::<_OBFBYTES_LEN>(_OBFBYTES_STRING, &_OBFBYTES_KEYSTREAM);
+		$crate::bytes::thread_local! {
+			static _OBFBYTES_CACHE: $crate::bytes::Cache<_OBFBYTES_LEN> = const { $crate::bytes::Cache::new() };
+		}
+		$crate::bytes::Cache::get(&_OBFBYTES_CACHE,
+			$crate::xref::xref::<_,
+				{$crate::random!(u32, "offset", stringify!($s))},
+				{$crate::random!(u64, "xref", stringify!($s))}>
+				(&_OBFBYTES_SDATA),
+			&_OBFBYTES_KEYSTREAM)
+	}};
+}
+
+#[cfg(not(feature = "std"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbytes_cached {
+	($s:expr) => {
+		compile_error!("the #[cached] form requires the std feature")
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbytes_verified {
+	($hook:expr; $s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFBYTES_STRING: &[u8] = $s;
+		const _OBFBYTES_SEED: u32 = $crate::random!(u32, "check seed", stringify!($s));
+		const _OBFBYTES_MASK: u32 = $crate::random!(u32, "check mask", stringify!($s));
+		static _OBFBYTES_CHECK: u32 = $crate::murmur3(_OBFBYTES_STRING, _OBFBYTES_SEED) ^ _OBFBYTES_MASK;
+		let mut data = $crate::__obfbytes!($s);
+		$crate::bytes::verify(&mut data, &_OBFBYTES_CHECK, _OBFBYTES_SEED, _OBFBYTES_MASK, $hook);
+		data
+	}};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbytes_slot {
+	(equals, $pad:expr, $other:expr, $s:expr) => {{
+		use ::core::primitive::*;
+		$crate::__obfbytes_slot!(@data $pad, $s);
+		// Only the string is compared, the padding is never decoded
+		$crate::bytes::equals_prefix::<_OBFBYTES_PAD>($crate::__obfbytes_slot!(@xref $s), &_OBFBYTES_KEYSTREAM, _OBFBYTES_LEN, $other)
+	}};
+	(@data $pad:expr, $s:expr) => {
+		const _OBFBYTES_STRING: &[u8] = $s;
+		const _OBFBYTES_LEN: usize = _OBFBYTES_STRING.len();
+		const _OBFBYTES_PAD: usize = $pad;
+		const _OBFBYTES_KEYSTREAM: [u8; _OBFBYTES_PAD] = $crate::bytes::keys::<_OBFBYTES_PAD>($crate::random!(u64, "key", stringify!($s)));
+		static _OBFBYTES_SDATA: [u8; _OBFBYTES_PAD] = $crate::bytes::obfuscate_padded::<_OBFBYTES_PAD>(_OBFBYTES_STRING, &_OBFBYTES_KEYSTREAM, 0);
+	};
+	(@xref $s:expr) => {
+		$crate::xref::xref::<_,
+			{$crate::random!(u32, "offset", stringify!($s))},
+			{$crate::random!(u64, "xref", stringify!($s))}>
+			(&_OBFBYTES_SDATA)
+	};
+	($pad:expr, $s:expr) => {{
+		use ::core::primitive::*;
+		$crate::__obfbytes_slot!(@data $pad, $s);
+		$crate::bytes::Slot::<_OBFBYTES_PAD, _OBFBYTES_LEN> {
+			data: $crate::bytes::deobfuscate::<_OBFBYTES_PAD>($crate::__obfbytes_slot!(@xref $s), &_OBFBYTES_KEYSTREAM),
+		}
+	}};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbytes_split {
+	(equals, $other:expr, $s:expr) => {{
+		use ::core::primitive::*;
+		$crate::__obfbytes_split!(@data $s);
+		let (a, b, c, d) = $crate::__obfbytes_split!(@xref $s);
+		$crate::bytes::equals_split::<_OBFBYTES_LEN, {_OBFBYTES_PIECES[0]}, {_OBFBYTES_PIECES[1]}, {_OBFBYTES_PIECES[2]}, {_OBFBYTES_PIECES[3]}>(a, b, c, d, &_OBFBYTES_KEYSTREAM, $other)
+	}};
+	(@data $s:expr) => {
+		const _OBFBYTES_STRING: &[u8] = $s;
+		const _OBFBYTES_LEN: usize = _OBFBYTES_STRING.len();
+		const _OBFBYTES_PIECES: [usize; 4] = $crate::bytes::split_lens(_OBFBYTES_LEN, $crate::random!(u64, "split", stringify!($s)));
+		const _OBFBYTES_KEYSTREAM: [u8; _OBFBYTES_LEN] = $crate::bytes::keys::<_OBFBYTES_LEN>($crate::random!(u64, "key", stringify!($s)));
+		const _OBFBYTES_DATA: [u8; _OBFBYTES_LEN] = $crate::bytes::obfuscate::<_OBFBYTES_LEN>(_OBFBYTES_STRING, &_OBFBYTES_KEYSTREAM);
+		static _OBFBYTES_SDATA0: [u8; _OBFBYTES_PIECES[0]] = $crate::bytes::piece(&_OBFBYTES_DATA, 0);
+		static _OBFBYTES_SDATA1: [u8; _OBFBYTES_PIECES[1]] = $crate::bytes::piece(&_OBFBYTES_DATA, _OBFBYTES_PIECES[0]);
+		static _OBFBYTES_SDATA2: [u8; _OBFBYTES_PIECES[2]] = $crate::bytes::piece(&_OBFBYTES_DATA, _OBFBYTES_PIECES[0] + _OBFBYTES_PIECES[1]);
+		static _OBFBYTES_SDATA3: [u8; _OBFBYTES_PIECES[3]] = $crate::bytes::piece(&_OBFBYTES_DATA, _OBFBYTES_PIECES[0] + _OBFBYTES_PIECES[1] + _OBFBYTES_PIECES[2]);
+	};
+	(@xref $s:expr) => {(
+		$crate::xref::xref::<_, {$crate::random!(u32, "offset", "0", stringify!($s))}, {$crate::random!(u64, "xref", "0", stringify!($s))}>(&_OBFBYTES_SDATA0),
+		$crate::xref::xref::<_, {$crate::random!(u32, "offset", "1", stringify!($s))}, {$crate::random!(u64, "xref", "1", stringify!($s))}>(&_OBFBYTES_SDATA1),
+		$crate::xref::xref::<_, {$crate::random!(u32, "offset", "2", stringify!($s))}, {$crate::random!(u64, "xref", "2", stringify!($s))}>(&_OBFBYTES_SDATA2),
+		$crate::xref::xref::<_, {$crate::random!(u32, "offset", "3", stringify!($s))}, {$crate::random!(u64, "xref", "3", stringify!($s))}>(&_OBFBYTES_SDATA3),
+	)};
+	($s:expr) => {{
+		use ::core::primitive::*;
+		$crate::__obfbytes_split!(@data $s);
+		let (a, b, c, d) = $crate::__obfbytes_split!(@xref $s);
+		$crate::bytes::deobfuscate_split::<_OBFBYTES_LEN, {_OBFBYTES_PIECES[0]}, {_OBFBYTES_PIECES[1]}, {_OBFBYTES_PIECES[2]}, {_OBFBYTES_PIECES[3]}>(a, b, c, d, &_OBFBYTES_KEYSTREAM)
+	}};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbytes_immediate {
+	($s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFBYTES_STRING: &[u8] = $s;
+		const _OBFBYTES_LEN: usize = _OBFBYTES_STRING.len();
+		const _OBFBYTES_IMMEDIATE: bool = _OBFBYTES_LEN <= $crate::bytes::IMMEDIATE_MAX;
+		const _OBFBYTES_WORDS: usize = if _OBFBYTES_IMMEDIATE { _OBFBYTES_LEN.div_ceil(8) } else { 0 };
+		const _OBFBYTES_SLEN: usize = if _OBFBYTES_IMMEDIATE { 0 } else { _OBFBYTES_LEN };
+		const _OBFBYTES_KEYS: [u64; _OBFBYTES_WORDS] = $crate::bytes::keys64::<_OBFBYTES_WORDS>($crate::random!(u64, "key", stringify!($s)));
+		const _OBFBYTES_WDATA: [u64; _OBFBYTES_WORDS] = $crate::bytes::immediate_words::<_OBFBYTES_WORDS>(_OBFBYTES_STRING, &_OBFBYTES_KEYS);
+		const _OBFBYTES_KEYSTREAM: [u8; _OBFBYTES_SLEN] = $crate::bytes::keys::<_OBFBYTES_SLEN>($crate::random!(u64, "key", stringify!($s)));
+		static _OBFBYTES_SDATA: [u8; _OBFBYTES_SLEN] = $crate::bytes::obfuscate::<_OBFBYTES_SLEN>(
+			if _OBFBYTES_IMMEDIATE { &[] } else { _OBFBYTES_STRING }, &_OBFBYTES_KEYSTREAM);
+		$crate::bytes::deobfuscate_immediate::<_OBFBYTES_LEN, _OBFBYTES_WORDS, _OBFBYTES_SLEN>(
+			_OBFBYTES_WDATA,
+			_OBFBYTES_KEYS,
+			$crate::xref::xref::<_,
+				{$crate::random!(u32, "offset", stringify!($s))},
+				{$crate::random!(u64, "xref", stringify!($s))}>
+				(&_OBFBYTES_SDATA),
+			&_OBFBYTES_KEYSTREAM)
+	}};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbytes_section {
+	($data:literal; $s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFBYTES_STRING: &[u8] = $s;
+		const _OBFBYTES_LEN: usize = _OBFBYTES_STRING.len();
+		const _OBFBYTES_KEYSTREAM: [u8; _OBFBYTES_LEN] = $crate::bytes::keys::<_OBFBYTES_LEN>($crate::random!(u64, "key", stringify!($s)));
+		// Wasm has no named data sections, the attribute is dropped there and the data stays in the default section
+		// `#[used]` keeps the section from being discarded as unreferenced by the compiler
+		#[used]
+		#[cfg_attr(not(target_family = "wasm"), link_section = $data)]
+		static _OBFBYTES_SDATA: [u8; _OBFBYTES_LEN] = $crate::bytes::obfuscate::<_OBFBYTES_LEN>(_OBFBYTES_STRING, &_OBFBYTES_KEYSTREAM);
+		$crate::bytes::deobfuscate::<_OBFBYTES_LEN>(
+			$crate::xref::xref::<_,
+				{$crate::random!(u32, "offset", stringify!($s))},
+				{$crate::random!(u64, "xref", stringify!($s))}>
+				(&_OBFBYTES_SDATA),
+			&_OBFBYTES_KEYSTREAM)
+	}};
+	($data:literal, $keys:literal; $s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFBYTES_STRING: &[u8] = $s;
+		const _OBFBYTES_LEN: usize = _OBFBYTES_STRING.len();
+		const _OBFBYTES_KEYS: [u8; _OBFBYTES_LEN] = $crate::bytes::keys::<_OBFBYTES_LEN>($crate::random!(u64, "key", stringify!($s)));
+		// See above for the wasm exclusion and `#[used]`
+		#[used]
+		#[cfg_attr(not(target_family = "wasm"), link_section = $keys)]
+		static _OBFBYTES_KEYSTREAM: [u8; _OBFBYTES_LEN] = _OBFBYTES_KEYS;
+		#[used]
+		#[cfg_attr(not(target_family = "wasm"), link_section = $data)]
+		static _OBFBYTES_SDATA: [u8; _OBFBYTES_LEN] = $crate::bytes::obfuscate::<_OBFBYTES_LEN>(_OBFBYTES_STRING, &_OBFBYTES_KEYS);
+		// Read the key stream through an xref to keep it from being folded into the code
+		$crate::bytes::deobfuscate::<_OBFBYTES_LEN>(
+			$crate::xref::xref::<_,
+				{$crate::random!(u32, "offset", stringify!($s))},
+				{$crate::random!(u64, "xref", stringify!($s))}>
+				(&_OBFBYTES_SDATA),
+			$crate::xref::xref::<_,
+				{$crate::random!(u32, "offset", "keys", stringify!($s))},
+				{$crate::random!(u64, "xref", "keys", stringify!($s))}>
+				(&_OBFBYTES_KEYSTREAM))
+	}};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbytes_cmp {
+	(equals, $other:expr, $s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFBYTES_STRING: &[u8] = $s;
+		const _OBFBYTES_LEN: usize = _OBFBYTES_STRING.len();
+		const _OBFBYTES_CIPHER2: bool = !$crate::bytes::SMALL_CODE && $crate::random!(bool, "cipher", stringify!($s));
+		const _OBFBYTES_GEN: u8 = $crate::random!(u8, "gen", stringify!($s)) % $crate::bytes::GENERATORS;
+		const _OBFBYTES_MASK: u64 = if $crate::bytes::MASKED { $crate::random!(u64, "mask", stringify!($s)) } else { 0 };
+		const _OBFBYTES_MASK_PARTS: [u16; 4] = $crate::bytes::mask_parts(_OBFBYTES_MASK);
+		const _OBFBYTES_KEYS: [u8; _OBFBYTES_LEN] = $crate::bytes::keys_with::<_OBFBYTES_LEN, _OBFBYTES_GEN>($crate::random!(u64, "key", stringify!($s)));
+		const _OBFBYTES_KEYSTREAM: [u8; _OBFBYTES_LEN] = $crate::bytes::fold_key::<_OBFBYTES_LEN>(&_OBFBYTES_KEYS, _OBFBYTES_MASK);
+		static _OBFBYTES_SDATA: [u8; _OBFBYTES_LEN] = if _OBFBYTES_CIPHER2 {
+			$crate::bytes::obfuscate2::<_OBFBYTES_LEN>(_OBFBYTES_STRING, &_OBFBYTES_KEYS)
+		}
+		else {
+			$crate::bytes::obfuscate::<_OBFBYTES_LEN>(_OBFBYTES_STRING, &_OBFBYTES_KEYS)
+		};
+		let sdata = $crate::xref::xref::<_,
+			{$crate::random!(u32, "offset", stringify!($s))},
+			{$crate::random!(u64, "xref", stringify!($s))}>
+			(&_OBFBYTES_SDATA);
+		if $crate::bytes::SMALL_CODE {
+			let keys: &'static [u8; _OBFBYTES_LEN] = &_OBFBYTES_KEYSTREAM;
+			$crate::bytes::equals_small::<_OBFBYTES_LEN>(sdata, keys, _OBFBYTES_MASK_PARTS, $other)
+		}
+		else if _OBFBYTES_CIPHER2 {
+			$crate::bytes::equals2::<_OBFBYTES_LEN>(sdata, &$crate::bytes::unmask_keys::<_OBFBYTES_LEN>(&_OBFBYTES_KEYSTREAM, _OBFBYTES_MASK_PARTS), $other)
+		}
+		else if $crate::bytes::MASKED {
+			$crate::bytes::equals_masked::<_OBFBYTES_LEN>(sdata, &_OBFBYTES_KEYSTREAM, _OBFBYTES_MASK_PARTS, $other)
+		}
+		else {
+			$crate::bytes::equals::<_OBFBYTES_LEN>(sdata, &_OBFBYTES_KEYSTREAM, $other)
+		}
+	}};
+	($f:ident, $other:expr, $s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFBYTES_STRING: &[u8] = $s;
+		const _OBFBYTES_LEN: usize = _OBFBYTES_STRING.len();
+		const _OBFBYTES_GEN: u8 = $crate::random!(u8, "gen", stringify!($s)) % $crate::bytes::GENERATORS;
+		const _OBFBYTES_MASK: u64 = if $crate::bytes::MASKED { $crate::random!(u64, "mask", stringify!($s)) } else { 0 };
+		const _OBFBYTES_KEYS: [u8; _OBFBYTES_LEN] = $crate::bytes::keys_with::<_OBFBYTES_LEN, _OBFBYTES_GEN>($crate::random!(u64, "key", stringify!($s)));
+		const _OBFBYTES_KEYSTREAM: [u8; _OBFBYTES_LEN] = $crate::bytes::fold_key::<_OBFBYTES_LEN>(&_OBFBYTES_KEYS, _OBFBYTES_MASK);
+		static _OBFBYTES_SDATA: [u8; _OBFBYTES_LEN] = $crate::bytes::obfuscate::<_OBFBYTES_LEN>(_OBFBYTES_STRING, &_OBFBYTES_KEYS);
+		$crate::bytes::$f::<_OBFBYTES_LEN>(
+			$crate::xref::xref::<_,
+				{$crate::random!(u32, "offset", stringify!($s))},
+				{$crate::random!(u64, "xref", stringify!($s))}>
+				(&_OBFBYTES_SDATA),
+			&$crate::bytes::unmask_keys::<_OBFBYTES_LEN>(&_OBFBYTES_KEYSTREAM, $crate::bytes::mask_parts(_OBFBYTES_MASK)),
+			$other)
+	}};
+}
+
+// Simple XorShift to generate the key stream.
+// Security doesn't matter, we just want a number of random-looking bytes.
+#[inline(always)]
+const fn next_round(mut x: u32) -> u32 {
+	x ^= x << 13;
+	x ^= x >> 17;
+	x ^= x << 5;
+	return x;
+}
+
+// Splitmix chain to generate the key stream from a 64-bit key.
+// Unlike XorShift a zero key does not produce a zero key stream.
+#[inline(always)]
+pub(crate) const fn next_round64(x: u64) -> u64 {
+	return crate::splitmix(x);
+}
+
+// Evaluating long strings bytewise is slow in const eval, the const fns process them in blocks of 64-bit words.
+// Function calls are expensive in const eval as well, large blocks amortize the calls to load and store them.
+const BLOCK: usize = 256;
+const BLOCK_WORDS: usize = BLOCK / 8;
+
+// Loads the block at the given offset as words in native byte order.
+#[inline(always)]
+const fn load_block(s: &[u8], i: usize) -> [u64; BLOCK_WORDS] {
+	assert!(i + BLOCK <= s.len());
+	return unsafe { read_unaligned(s.as_ptr().add(i) as *const [u64; BLOCK_WORDS]) };
+}
+#[inline(always)]
+const fn store_block(s: &mut [u8], i: usize, words: [u64; BLOCK_WORDS]) {
+	assert!(i + BLOCK <= s.len());
+	unsafe { write_unaligned(s.as_mut_ptr().add(i) as *mut [u64; BLOCK_WORDS], words) };
+}
+
+// Reinterprets the bytes as words in native byte order, the bytes past the end of the string are zero.
+#[repr(C)]
+union Words<const LEN: usize, const WORDS: usize> {
+	bytes: [u8; LEN],
+	words: [u64; WORDS],
+}
+
+#[inline(always)]
+const fn to_words<const LEN: usize, const WORDS: usize>(s: &[u8; LEN]) -> [u64; WORDS] {
+	if WORDS != LEN.div_ceil(8) {
+		panic!("key stream words do not match the input string len");
+	}
+	let mut w = Words::<LEN, WORDS> { words: [0u64; WORDS] };
+	w.bytes = *s;
+	return unsafe { w.words };
+}
+
+#[inline(always)]
+const fn from_words<const LEN: usize, const WORDS: usize>(words: [u64; WORDS]) -> [u8; LEN] {
+	if WORDS != LEN.div_ceil(8) {
+		panic!("key stream words do not match the input string len");
+	}
+	return unsafe { Words::<LEN, WORDS> { words }.bytes };
+}
+
+/// Number of key stream generators to choose from, see [`keystream_with`].
+pub const GENERATORS: u8 = 4;
+
+// The key stream generators, returns the next state and the output for this round.
+// Each generator produces a differently shaped key stream so recovering one does not generalize to the others.
+#[inline(always)]
+const fn next_round_with<const GEN: u8>(state: u64) -> (u64, u64) {
+	match GEN {
+		// Splitmix chain
+		0 => {
+			let x = next_round64(state);
+			return (x, x);
+		},
+		// Multiply-xorshift (xorshift64*), the zero state is a fixed point so it is replaced
+		1 => {
+			let mut x = if state == 0 { 0x9e3779b97f4a7c15 } else { state };
+			x ^= x >> 12;
+			x ^= x << 25;
+			x ^= x >> 27;
+			return (x, x.wrapping_mul(0x2545f4914f6cdd1d));
+		},
+		// Knuth's MMIX LCG with an xorshift-multiply output function to hide the weak low bits
+		2 => {
+			let x = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+			let z = (x ^ (x >> 32)).wrapping_mul(0xd6e8feb86659fd93);
+			return (x, z ^ (z >> 32));
+		},
+		// Wyrand
+		3 => {
+			let x = state.wrapping_add(0xa0761d6478bd642f);
+			let t = (x as u128).wrapping_mul((x ^ 0xe7037ed1a0b428db) as u128);
+			return (x, (t >> 64) as u64 ^ t as u64);
+		},
+		_ => panic!("unknown key stream generator"),
+	}
+}
+
+/// Generate the key stream for array of given length.
+#[inline(always)]
+pub const fn keystream<const LEN: usize>(key: u64) -> [u8; LEN] {
+	return keystream_with::<LEN, 0>(key);
+}
+
+/// Generate the key stream for array of given length with the given generator.
+///
+/// The generator `GEN` must be less than [`GENERATORS`].
+#[inline(always)]
+pub const fn keystream_with<const LEN: usize, const GEN: u8>(key: u64) -> [u8; LEN] {
+	let mut keys = [0u8; LEN];
+	let mut state = key;
+	let mut i = 0;
+	// Calculate the key stream in blocks of words
+	while i + BLOCK <= LEN {
+		let mut words = [0u64; BLOCK_WORDS];
+		let mut j = 0;
+		while j < BLOCK_WORDS {
+			let (next, round_key) = next_round_with::<GEN>(state);
+			state = next;
+			words[j] = round_key.to_le();
+			j += 1;
+		}
+		store_block(&mut keys, i, words);
+		i += BLOCK;
+	}
+	// Calculate the remaining bytes of the key stream in chunks of 8 bytes
+	while i < LEN {
+		let (next, round_key) = next_round_with::<GEN>(state);
+		state = next;
+		let kb = round_key.to_le_bytes();
+		let mut j = 0;
+		while j < 8 && i + j < LEN {
+			keys[i + j] = kb[j];
+			j += 1;
+		}
+		i += 8;
+	}
+	return keys;
+}
+
+/// Generate the key stream for array of given length from a 32-bit key.
+///
+/// This is the key stream used before the keys were widened to 64 bits.
+#[deprecated(note = "use keystream with a 64-bit key")]
 #[inline(always)]
-pub const fn keystream<const LEN: usize>(key: u32) -> [u8; LEN] {
+pub const fn keystream_u32<const LEN: usize>(key: u32) -> [u8; LEN] {
 	let mut keys = [0u8; LEN];
 	let mut round_key = key;
 	let mut i = 0;
 	// Calculate the key stream in chunks of 4 bytes
 	while i < LEN & !3 {
 		round_key = next_round(round_key);
-		let kb = round_key.to_ne_bytes();
+		let kb = round_key.to_le_bytes();
 		keys[i + 0] = kb[0];
 		keys[i + 1] = kb[1];
 		keys[i + 2] = kb[2];
@@ -173,7 +1190,7 @@ pub const fn keystream<const LEN: usize>(key: u32) -> [u8; LEN] {
 	}
 	// Calculate the remaining bytes of the key stream
 	round_key = next_round(round_key);
-	let kb = round_key.to_ne_bytes();
+	let kb = round_key.to_le_bytes();
 	match LEN % 4 {
 		1 => {
 			keys[i + 0] = kb[0];
@@ -192,87 +1209,1834 @@ pub const fn keystream<const LEN: usize>(key: u32) -> [u8; LEN] {
 	return keys;
 }
 
-/// Obfuscates the input string and given key stream.
+/// Generate the mixed key stream for array of given length.
+///
+/// Every 8 bytes of the key stream are produced by running `rounds` rounds of keyed [`splitmix`](crate::splitmix) over a 64-bit counter.
+/// Unlike [`keystream`] recovering a part of the key stream does not reveal the state needed to produce the rest of the key stream.
+/// At least two rounds are needed for this to hold, a single round can be inverted to recover the key.
 #[inline(always)]
-pub const fn obfuscate<const LEN: usize>(s: &[u8], k: &[u8; LEN]) -> [u8; LEN] {
+pub const fn keystream_mixed<const LEN: usize>(key: u64, rounds: u32) -> [u8; LEN] {
+	let mut keys = [0u8; LEN];
+	let mut i = 0;
+	while i + BLOCK <= LEN {
+		let mut words = [0u64; BLOCK_WORDS];
+		let mut j = 0;
+		while j < BLOCK_WORDS {
+			words[j] = mix_word(key, (i / 8 + j) as u64, rounds).to_le();
+			j += 1;
+		}
+		store_block(&mut keys, i, words);
+		i += BLOCK;
+	}
+	while i < LEN {
+		let kb = mix_word(key, (i / 8) as u64, rounds).to_le_bytes();
+		let mut j = 0;
+		while j < 8 && i + j < LEN {
+			keys[i + j] = kb[j];
+			j += 1;
+		}
+		i += 8;
+	}
+	return keys;
+}
+
+/// Produces the word of the mixed key stream at the given index.
+#[inline(always)]
+pub(crate) const fn mix_word(key: u64, index: u64, rounds: u32) -> u64 {
+	let mut z = index;
+	let mut round = 0;
+	while round < rounds {
+		z = crate::splitmix(z ^ key.rotate_left(round * 17));
+		round += 1;
+	}
+	return z;
+}
+
+/// Generate the key stream used by the obfuscation macros.
+///
+/// With the `mixed-keystream` feature enabled this is [`keystream_mixed`] with [`ROUNDS`](crate::ROUNDS) rounds.
+#[doc(hidden)]
+#[inline(always)]
+pub const fn keys<const LEN: usize>(key: u64) -> [u8; LEN] {
+	return keys_with::<LEN, 0>(key);
+}
+
+/// Generate the key stream used by the obfuscation macros with the given generator.
+///
+/// The mixed key stream has no generators to choose from and ignores `GEN`.
+#[doc(hidden)]
+#[inline(always)]
+pub const fn keys_with<const LEN: usize, const GEN: u8>(key: u64) -> [u8; LEN] {
+	#[cfg(feature = "mixed-keystream")]
+	return keystream_mixed::<LEN>(key, crate::ROUNDS);
+	#[cfg(not(feature = "mixed-keystream"))]
+	return keystream_with::<LEN, GEN>(key);
+}
+
+/// Generate the key stream as 64-bit words.
+///
+/// Produces the same key stream as [`keystream`], every word holds 8 bytes of the key stream in native byte order.
+/// The words are byte swapped on big endian targets so their bytes in memory match the little endian key stream.
+#[inline(always)]
+pub const fn keystream64<const WORDS: usize>(key: u64) -> [u64; WORDS] {
+	return keystream64_with::<WORDS, 0>(key);
+}
+
+/// Generate the key stream as 64-bit words with the given generator.
+///
+/// Produces the same key stream as [`keystream_with`].
+#[inline(always)]
+pub const fn keystream64_with<const WORDS: usize, const GEN: u8>(key: u64) -> [u64; WORDS] {
+	let mut keys = [0u64; WORDS];
+	let mut state = key;
+	let mut i = 0;
+	while i < WORDS {
+		let (next, round_key) = next_round_with::<GEN>(state);
+		state = next;
+		keys[i] = round_key.to_le();
+		i += 1;
+	}
+	return keys;
+}
+
+/// Generate the key stream as 64-bit words used by the obfuscation macros.
+///
+/// Produces the same key stream as [`keys`].
+#[doc(hidden)]
+#[inline(always)]
+pub const fn keys64<const WORDS: usize>(key: u64) -> [u64; WORDS] {
+	return keys64_with::<WORDS, 0>(key);
+}
+
+/// Generate the key stream as 64-bit words used by the obfuscation macros with the given generator.
+///
+/// Produces the same key stream as [`keys_with`].
+#[doc(hidden)]
+#[inline(always)]
+pub const fn keys64_with<const WORDS: usize, const GEN: u8>(key: u64) -> [u64; WORDS] {
+	#[cfg(feature = "mixed-keystream")]
+	{
+		let mut keys = [0u64; WORDS];
+		let mut i = 0;
+		while i < WORDS {
+			keys[i] = mix_word(key, i as u64, crate::ROUNDS).to_le();
+			i += 1;
+		}
+		return keys;
+	}
+	#[cfg(not(feature = "mixed-keystream"))]
+	return keystream64_with::<WORDS, GEN>(key);
+}
+
+/// Aligns the obfuscated data to allow aligned 8-byte reads.
+#[doc(hidden)]
+#[repr(C, align(8))]
+pub struct Aligned<T>(pub T);
+
+/// Obfuscates the input string and given key stream of 64-bit words.
+#[inline(always)]
+pub const fn obfuscate64<const LEN: usize, const WORDS: usize>(s: &[u8], k: &[u64; WORDS]) -> Aligned<[u8; LEN]> {
 	if s.len() != LEN {
 		panic!("input string len not equal to key stream len");
 	}
-	let mut data = [0u8; LEN];
-	let mut i = 0usize;
-	while i < LEN {
-		data[i] = s[i] ^ k[i];
+	let Some(s) = s.first_chunk::<LEN>() else { unreachable!() };
+	let mut data = to_words::<LEN, WORDS>(s);
+	let mut prev = 0;
+	let mut i = 0;
+	while i < WORDS {
+		prev ^= data[i] ^ k[i];
+		data[i] = prev;
 		i += 1;
 	}
-	return data;
+	return Aligned(from_words::<LEN, WORDS>(data));
 }
 
-/// Deobfuscates the obfuscated input string and given key stream.
+/// Deobfuscates the obfuscated input string and given key stream of 64-bit words.
 #[inline(always)]
-pub fn deobfuscate<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN]) -> [u8; LEN] {
+pub fn deobfuscate64<const LEN: usize, const WORDS: usize>(s: &Aligned<[u8; LEN]>, k: &[u64; WORDS]) -> [u8; LEN] {
 	let mut buf = [0u8; LEN];
+	if SIMPLE_DECODE {
+		let mut j = 0;
+		while j < LEN {
+			let kb = k[j / 8].to_ne_bytes();
+			buf[j] = s.0[j] ^ kb[j % 8] ^ if j < 8 { 0 } else { s.0[j - 8] };
+			j += 1;
+		}
+		return buf;
+	}
 	let mut i = 0;
-	// Try to tickle the LLVM optimizer in _just_ the right way
 	// Use `read_volatile` to avoid constant folding a specific read and optimize the rest
-	// Volatile reads of any size larger than 8 bytes appears to cause a bunch of one byte reads
-	// Hand optimize in chunks of 8 and 4 bytes to avoid this
+	// The data is aligned which allows reading whole words without reassembling them from bytes
 	unsafe {
-		let src = s.as_ptr();
+		let src = s.0.as_ptr();
 		let dest = buf.as_mut_ptr();
-		// Process in chunks of 8 bytes on 64-bit targets
-		#[cfg(target_pointer_width = "64")]
-		while i < LEN & !7 {
-			let ct = read_volatile(src.offset(i as isize) as *const [u8; 8]);
-			let tmp = u64::from_ne_bytes([ct[0], ct[1], ct[2], ct[3], ct[4], ct[5], ct[6], ct[7]]) ^
-				u64::from_ne_bytes([k[i + 0], k[i + 1], k[i + 2], k[i + 3], k[i + 4], k[i + 5], k[i + 6], k[i + 7]]);
-			write(dest.offset(i as isize) as *mut [u8; 8], tmp.to_ne_bytes());
-			i += 8;
+		let mut prev = 0u64;
+		while i < LEN / 8 {
+			let ct = read_volatile(src.offset((i * 8) as isize) as *const u64);
+			write_unaligned(dest.offset((i * 8) as isize) as *mut u64, ct ^ k[i] ^ prev);
+			prev = ct;
+			i += 1;
 		}
-		// Process in chunks of 4 bytes
-		while i < LEN & !3 {
-			let ct = read_volatile(src.offset(i as isize) as *const [u8; 4]);
+		// Process the remaining bytes
+		if LEN % 8 != 0 {
+			let kb = k[i].to_ne_bytes();
+			let cb = prev.to_ne_bytes();
+			let mut j = 0;
+			while i * 8 + j < LEN {
+				let ct = read_volatile(src.offset((i * 8 + j) as isize));
+				write(dest.offset((i * 8 + j) as isize), ct ^ kb[j] ^ cb[j]);
+				j += 1;
+			}
+		}
+	}
+	return buf;
+}
+
+// Size of the chunks before the remaining bytes, 16-bit targets process 2 bytes at a time.
+const TAIL_CHUNK: usize = if cfg!(target_pointer_width = "16") { 2 } else { 4 };
+
+// Chains the ciphertext into the key stream: every byte is additionally masked with the ciphertext 8 bytes before it.
+// Chaining per 8-byte block keeps the chunked loops intact while the same plaintext encrypts differently in every block.
+// Returns the chained ciphertext for the `N` bytes starting at `i`, the first block is chained with zeroes.
+#[inline(always)]
+unsafe fn chain<const N: usize>(src: *const u8, i: usize) -> [u8; N] {
+	if i < 8 {
+		return [0u8; N];
+	}
+	return read_volatile(src.offset((i - 8) as isize) as *const [u8; N]);
+}
+
+// Loads the ciphertext a vector at a time, reading `[u8; N]` keeps the volatile loads unaligned like in `chain`.
+// Volatile loads cannot be merged, elided or folded with the constant ciphertext by the optimizer.
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+unsafe fn load128(p: *const u8) -> core::arch::x86_64::__m128i {
+	return core::mem::transmute::<[u8; 16], core::arch::x86_64::__m128i>(read_volatile(p as *const [u8; 16]));
+}
+#[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
+#[inline(always)]
+unsafe fn load256(p: *const u8) -> core::arch::x86_64::__m256i {
+	return core::mem::transmute::<[u8; 32], core::arch::x86_64::__m256i>(read_volatile(p as *const [u8; 32]));
+}
+
+// Deobfuscates 16 bytes per iteration with SSE2 and 32 bytes with AVX2 when enabled at compiletime.
+// The source pointer is laundered through `black_box` and every ciphertext vector is a volatile load.
+// Returns the offset where the scalar code continues.
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+unsafe fn simd_deobfuscate<const LEN: usize>(src: *const u8, k: &[u8; LEN], dest: *mut u8) -> usize {
+	use core::arch::x86_64::*;
+	let src = hint::black_box(src);
+	let kp = k.as_ptr();
+	if LEN < 16 {
+		return 0;
+	}
+	// The first block is chained with zeroes
+	let ct = load128(src);
+	let tmp = _mm_xor_si128(_mm_xor_si128(ct, _mm_loadu_si128(kp as *const __m128i)), _mm_slli_si128::<8>(ct));
+	_mm_storeu_si128(dest as *mut __m128i, tmp);
+	let mut i = 16;
+	#[cfg(target_feature = "avx2")]
+	while i + 32 <= LEN {
+		let ct = load256(src.add(i));
+		let cb = load256(src.add(i - 8));
+		let tmp = _mm256_xor_si256(_mm256_xor_si256(ct, _mm256_loadu_si256(kp.add(i) as *const __m256i)), cb);
+		_mm256_storeu_si256(dest.add(i) as *mut __m256i, tmp);
+		i += 32;
+	}
+	while i + 16 <= LEN {
+		let ct = load128(src.add(i));
+		let cb = load128(src.add(i - 8));
+		let tmp = _mm_xor_si128(_mm_xor_si128(ct, _mm_loadu_si128(kp.add(i) as *const __m128i)), cb);
+		_mm_storeu_si128(dest.add(i) as *mut __m128i, tmp);
+		i += 16;
+	}
+	return i;
+}
+
+// Compares 16 bytes per iteration with SSE2 and 32 bytes with AVX2 when enabled at compiletime.
+// Returns the offset where the scalar code continues or `None` on the first mismatch.
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+unsafe fn simd_equals<const LEN: usize>(src: *const u8, k: &[u8; LEN], other: *const u8) -> Option<usize> {
+	use core::arch::x86_64::*;
+	let src = hint::black_box(src);
+	let kp = k.as_ptr();
+	if LEN < 16 {
+		return Some(0);
+	}
+	let ct = load128(src);
+	let tmp = _mm_xor_si128(_mm_xor_si128(ct, _mm_loadu_si128(kp as *const __m128i)), _mm_slli_si128::<8>(ct));
+	if _mm_movemask_epi8(_mm_cmpeq_epi8(tmp, _mm_loadu_si128(other as *const __m128i))) != 0xffff {
+		return None;
+	}
+	let mut i = 16;
+	#[cfg(target_feature = "avx2")]
+	while i + 32 <= LEN {
+		let ct = load256(src.add(i));
+		let cb = load256(src.add(i - 8));
+		let tmp = _mm256_xor_si256(_mm256_xor_si256(ct, _mm256_loadu_si256(kp.add(i) as *const __m256i)), cb);
+		if _mm256_movemask_epi8(_mm256_cmpeq_epi8(tmp, _mm256_loadu_si256(other.add(i) as *const __m256i))) != -1 {
+			return None;
+		}
+		i += 32;
+	}
+	while i + 16 <= LEN {
+		let ct = load128(src.add(i));
+		let cb = load128(src.add(i - 8));
+		let tmp = _mm_xor_si128(_mm_xor_si128(ct, _mm_loadu_si128(kp.add(i) as *const __m128i)), cb);
+		if _mm_movemask_epi8(_mm_cmpeq_epi8(tmp, _mm_loadu_si128(other.add(i) as *const __m128i))) != 0xffff {
+			return None;
+		}
+		i += 16;
+	}
+	return Some(i);
+}
+
+/// Returns `true` if the bytes contain a run of at least `n` printable ASCII characters.
+///
+/// Tools such as `strings` flag these runs as candidate strings.
+pub const fn has_printable_run(s: &[u8], n: usize) -> bool {
+	let mut run = 0;
+	let mut i = 0;
+	while i < s.len() {
+		if s[i] == b'\t' || s[i] >= 0x20 && s[i] < 0x7f {
+			run += 1;
+			if run >= n {
+				return true;
+			}
+		}
+		else {
+			run = 0;
+		}
+		i += 1;
+	}
+	return false;
+}
+
+/// Length of printable runs in the ciphertext which cause the key to be re-rolled, matches the default of `strings`.
+pub const PRINTABLE_RUN: usize = 4;
+
+/// Number of attempts to find a key which avoids printable runs in the ciphertext.
+pub const REROLLS: u32 = 8;
+
+// Only the first bytes of the ciphertext of long strings are scanned for printable runs.
+// These only depend on the first bytes of the string, scanning the prefix avoids obfuscating the whole string for every rejected key.
+const PREFIX: usize = 1024;
+const fn prefix_has_printable_run<const GEN: u8>(s: &[u8], key: u64, cipher2: bool) -> bool {
+	let s = s.split_at(PREFIX).0;
+	let data = if cipher2 {
+		obfuscate2::<PREFIX>(s, &keys_with::<PREFIX, GEN>(key))
+	}
+	else {
+		obfuscate64::<PREFIX, { PREFIX / 8 }>(s, &keys64_with::<{ PREFIX / 8 }, GEN>(key)).0
+	};
+	return has_printable_run(&data, PRINTABLE_RUN);
+}
+
+/// Picks the key for the string, re-rolling it while the ciphertext contains a printable run.
+///
+/// Only the first 1024 bytes of the ciphertext are scanned, long strings are never obfuscated in full per attempt.
+/// Gives up after [`REROLLS`] attempts and returns the last key tried.
+#[doc(hidden)]
+pub const fn pick_key<const LEN: usize, const WORDS: usize, const GEN: u8>(s: &[u8], key: u64, cipher2: bool) -> u64 {
+	let mut key = key;
+	let mut attempt = 1;
+	while attempt < REROLLS {
+		let run = if LEN > PREFIX {
+			prefix_has_printable_run::<GEN>(s, key, cipher2)
+		}
+		else if cipher2 {
+			has_printable_run(&obfuscate2::<LEN>(s, &keys_with::<LEN, GEN>(key)), PRINTABLE_RUN)
+		}
+		else {
+			has_printable_run(&obfuscate64::<LEN, WORDS>(s, &keys64_with::<WORDS, GEN>(key)).0, PRINTABLE_RUN)
+		};
+		if !run {
+			break;
+		}
+		key = crate::splitmix(key);
+		attempt += 1;
+	}
+	return key;
+}
+
+/// Whether the key stream constants are stored masked, see the `masked-keystream` feature.
+///
+/// Only the plain forms of `obfstr!`, `obfbytes!`, `obfwide!` and `obfeq!` store their key stream masked.
+#[doc(hidden)]
+pub const MASKED: bool = cfg!(feature = "masked-keystream");
+
+/// Splits the key stream mask in smaller constants.
+#[doc(hidden)]
+#[inline(always)]
+pub const fn mask_parts(mask: u64) -> [u16; 4] {
+	return [mask as u16, (mask >> 16) as u16, (mask >> 32) as u16, (mask >> 48) as u16];
+}
+
+// Reconstructs the key stream mask at runtime.
+// Each part is passed through `black_box` to avoid folding them back into a single constant.
+#[inline(always)]
+pub(crate) fn unmask(parts: [u16; 4]) -> u64 {
+	let a = hint::black_box(parts[0]) as u64;
+	let b = hint::black_box(parts[1]) as u64;
+	let c = hint::black_box(parts[2]) as u64;
+	let d = hint::black_box(parts[3]) as u64;
+	return a | b << 16 | c << 32 | d << 48;
+}
+
+/// Masks the key stream of 64-bit words.
+#[inline(always)]
+pub const fn mask64<const WORDS: usize>(k: &[u64; WORDS], mask: u64) -> [u64; WORDS] {
+	let mut keys = *k;
+	let mut i = 0;
+	while i < WORDS {
+		keys[i] ^= mask.to_le();
+		i += 1;
+	}
+	return keys;
+}
+
+/// Unmasks the key stream masked with [`fold_key`].
+///
+/// Returns the key stream unchanged without the `masked-keystream` feature.
+#[doc(hidden)]
+#[inline(always)]
+pub fn unmask_keys<const LEN: usize>(k: &[u8; LEN], parts: [u16; 4]) -> [u8; LEN] {
+	if !MASKED {
+		return *k;
+	}
+	return fold_key::<LEN>(k, unmask(parts));
+}
+
+/// Deobfuscates the obfuscated input string with the key stream masked with [`mask64`].
+///
+/// The mask is reconstructed from its parts and removed on the fly.
+#[inline(always)]
+pub fn deobfuscate64_masked<const LEN: usize, const WORDS: usize>(s: &Aligned<[u8; LEN]>, k: &[u64; WORDS], parts: [u16; 4]) -> [u8; LEN] {
+	let mask = unmask(parts).to_le();
+	let mut buf = [0u8; LEN];
+	let mut i = 0;
+	unsafe {
+		let src = s.0.as_ptr();
+		let dest = buf.as_mut_ptr();
+		let mut prev = 0u64;
+		while i < LEN / 8 {
+			let ct = read_volatile(src.offset((i * 8) as isize) as *const u64);
+			write_unaligned(dest.offset((i * 8) as isize) as *mut u64, ct ^ k[i] ^ mask ^ prev);
+			prev = ct;
+			i += 1;
+		}
+		// Process the remaining bytes
+		if LEN % 8 != 0 {
+			let kb = (k[i] ^ mask).to_ne_bytes();
+			let cb = prev.to_ne_bytes();
+			let mut j = 0;
+			while i * 8 + j < LEN {
+				let ct = read_volatile(src.offset((i * 8 + j) as isize));
+				write(dest.offset((i * 8 + j) as isize), ct ^ kb[j] ^ cb[j]);
+				j += 1;
+			}
+		}
+	}
+	return buf;
+}
+
+/// Compares the obfuscated string with the other string with the key stream masked with [`fold_key`].
+#[inline(always)]
+pub fn equals_masked<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], parts: [u16; 4], other: &[u8]) -> bool {
+	if other.len() != LEN {
+		return false;
+	}
+	let mask = unmask(parts).to_le();
+	let mb = mask.to_ne_bytes();
+	let mut i = 0;
+	unsafe {
+		let src = s.as_ptr();
+		// Process in chunks of 8 bytes
+		while i < LEN & !7 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u8; 8]);
+			let tmp = u64::from_ne_bytes(ct) ^
+				u64::from_ne_bytes([k[i + 0], k[i + 1], k[i + 2], k[i + 3], k[i + 4], k[i + 5], k[i + 6], k[i + 7]]) ^
+				u64::from_ne_bytes(chain::<8>(src, i)) ^ mask;
+			let other = u64::from_ne_bytes([other[i + 0], other[i + 1], other[i + 2], other[i + 3], other[i + 4], other[i + 5], other[i + 6], other[i + 7]]);
+			if tmp != other {
+				return false;
+			}
+			i += 8;
+		}
+		// Process the remaining bytes
+		while i < LEN {
+			let ct = read_volatile(src.offset(i as isize));
+			if ct ^ k[i] ^ chain::<1>(src, i)[0] ^ mb[i % 8] != other[i] {
+				return false;
+			}
+			i += 1;
+		}
+	}
+	return true;
+}
+
+/// Obfuscates the input string and given key stream.
+///
+/// Each byte is masked with its key byte and the ciphertext byte 8 positions before it.
+#[inline(always)]
+pub const fn obfuscate<const LEN: usize>(s: &[u8], k: &[u8; LEN]) -> [u8; LEN] {
+	if s.len() != LEN {
+		panic!("input string len not equal to key stream len");
+	}
+	let mut data = [0u8; LEN];
+	let mut prev = 0u64;
+	let mut i = 0;
+	// Obfuscate in blocks of words, every word is chained to the previous word of ciphertext
+	while i + BLOCK <= LEN {
+		let mut words = load_block(s, i);
+		let keys = load_block(k, i);
+		let mut j = 0;
+		while j < BLOCK_WORDS {
+			prev ^= words[j] ^ keys[j];
+			words[j] = prev;
+			j += 1;
+		}
+		store_block(&mut data, i, words);
+		i += BLOCK;
+	}
+	// Obfuscate the remaining bytes
+	let prev = prev.to_ne_bytes();
+	let start = i;
+	while i < LEN {
+		data[i] = s[i] ^ k[i] ^ if i < start + 8 { prev[i - start] } else { data[i - 8] };
+		i += 1;
+	}
+	return data;
+}
+
+/// Deobfuscates the obfuscated input string and given key stream.
+#[inline(always)]
+pub fn deobfuscate<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN]) -> [u8; LEN] {
+	let mut buf = [0u8; LEN];
+	deobfuscate_into::<LEN>(s, k, &mut buf);
+	return buf;
+}
+
+/// Destination of the `*_into` deobfuscation functions, either initialized or uninitialized.
+///
+/// This trait is sealed, it is implemented for slices and arrays of `T` and `MaybeUninit<T>`.
+pub trait Buffer<T>: sealed::Sealed {
+	/// Returns the first `len` elements as uninitialized memory.
+	///
+	/// Panics if the buffer is shorter than `len`.
+	///
+	/// # Safety
+	///
+	/// The buffer may be initialized memory viewed as uninitialized, only initialized values may be written through the result.
+	unsafe fn uninit_prefix(&mut self, len: usize) -> &mut [MaybeUninit<T>];
+}
+mod sealed {
+	pub trait Sealed {}
+	impl<T> Sealed for [T] {}
+	impl<T, const N: usize> Sealed for [T; N] {}
+}
+impl<T: Copy> Buffer<T> for [T] {
+	#[inline(always)]
+	unsafe fn uninit_prefix(&mut self, len: usize) -> &mut [MaybeUninit<T>] {
+		let buf = &mut self[..len];
+		&mut *(buf as *mut [T] as *mut [MaybeUninit<T>])
+	}
+}
+impl<T: Copy> Buffer<T> for [MaybeUninit<T>] {
+	#[inline(always)]
+	unsafe fn uninit_prefix(&mut self, len: usize) -> &mut [MaybeUninit<T>] {
+		&mut self[..len]
+	}
+}
+impl<T: Copy, const N: usize> Buffer<T> for [T; N] {
+	#[inline(always)]
+	unsafe fn uninit_prefix(&mut self, len: usize) -> &mut [MaybeUninit<T>] {
+		self[..].uninit_prefix(len)
+	}
+}
+impl<T: Copy, const N: usize> Buffer<T> for [MaybeUninit<T>; N] {
+	#[inline(always)]
+	unsafe fn uninit_prefix(&mut self, len: usize) -> &mut [MaybeUninit<T>] {
+		&mut self[..len]
+	}
+}
+
+/// Asserts the buffer was fully initialized by one of the `*_into` deobfuscation functions.
+///
+/// # Safety
+///
+/// Every element of the buffer must be initialized.
+#[inline(always)]
+pub unsafe fn assume_init<T>(buf: &mut [MaybeUninit<T>]) -> &mut [T] {
+	&mut *(buf as *mut [MaybeUninit<T>] as *mut [T])
+}
+
+/// Deobfuscates the obfuscated input string and given key stream directly into the destination.
+///
+/// The destination may be uninitialized, returns the deobfuscated string.
+/// Panics if the destination is shorter than the string before anything is written.
+#[inline(always)]
+pub fn deobfuscate_into<'a, const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], dst: &'a mut (impl Buffer<u8> + ?Sized)) -> &'a mut [u8] {
+	// Safety: only initialized values are written to the destination
+	let dst = unsafe { dst.uninit_prefix(LEN) };
+	if SIMPLE_DECODE {
+		for j in 0..LEN {
+			dst[j] = MaybeUninit::new(s[j] ^ k[j] ^ if j < 8 { 0 } else { s[j - 8] });
+		}
+		return unsafe { assume_init(dst) };
+	}
+	let mut i = 0;
+	// Try to tickle the LLVM optimizer in _just_ the right way
+	// Use `read_volatile` to avoid constant folding a specific read and optimize the rest
+	// Volatile reads of any size larger than 8 bytes appears to cause a bunch of one byte reads
+	// Hand optimize in chunks of 8 and 4 bytes to avoid this
+	unsafe {
+		let src = s.as_ptr();
+		let dest = dst.as_mut_ptr() as *mut u8;
+		// Process in chunks of 16 or 32 bytes on x86_64
+		#[cfg(target_arch = "x86_64")]
+		{
+			i += simd_deobfuscate::<LEN>(src, k, dest);
+		}
+		// Process in chunks of 8 bytes on 64-bit targets
+		#[cfg(target_pointer_width = "64")]
+		while i < LEN & !7 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u8; 8]);
+			let tmp = u64::from_ne_bytes([ct[0], ct[1], ct[2], ct[3], ct[4], ct[5], ct[6], ct[7]]) ^
+				u64::from_ne_bytes([k[i + 0], k[i + 1], k[i + 2], k[i + 3], k[i + 4], k[i + 5], k[i + 6], k[i + 7]]) ^
+				u64::from_ne_bytes(chain::<8>(src, i));
+			write(dest.offset(i as isize) as *mut [u8; 8], tmp.to_ne_bytes());
+			i += 8;
+		}
+		// Process in chunks of 4 bytes on 32-bit and 64-bit targets
+		#[cfg(not(target_pointer_width = "16"))]
+		while i < LEN & !3 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u8; 4]);
 			let tmp = u32::from_ne_bytes([ct[0], ct[1], ct[2], ct[3]]) ^
-				u32::from_ne_bytes([k[i + 0], k[i + 1], k[i + 2], k[i + 3]]);
+				u32::from_ne_bytes([k[i + 0], k[i + 1], k[i + 2], k[i + 3]]) ^
+				u32::from_ne_bytes(chain::<4>(src, i));
 			write(dest.offset(i as isize) as *mut [u8; 4], tmp.to_ne_bytes());
 			i += 4;
 		}
+		// Process in chunks of 2 bytes on 16-bit targets
+		#[cfg(target_pointer_width = "16")]
+		while i < LEN & !1 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u8; 2]);
+			let cb = chain::<2>(src, i);
+			write(dest.offset(i as isize) as *mut [u8; 2], [
+				ct[0] ^ k[i + 0] ^ cb[0],
+				ct[1] ^ k[i + 1] ^ cb[1],
+			]);
+			i += 2;
+		}
+		// Process the remaining bytes
+		match LEN % TAIL_CHUNK {
+			1 => {
+				let ct = read_volatile(src.offset(i as isize));
+				write(dest.offset(i as isize), ct ^ k[i] ^ chain::<1>(src, i)[0]);
+			},
+			2 => {
+				let ct = read_volatile(src.offset(i as isize) as *const [u8; 2]);
+				let cb = chain::<2>(src, i);
+				write(dest.offset(i as isize) as *mut [u8; 2], [
+					ct[0] ^ k[i + 0] ^ cb[0],
+					ct[1] ^ k[i + 1] ^ cb[1],
+				]);
+			},
+			3 => {
+				let ct = read_volatile(src.offset(i as isize) as *const [u8; 3]);
+				let cb = chain::<3>(src, i);
+				write(dest.offset(i as isize) as *mut [u8; 2], [
+					ct[0] ^ k[i + 0] ^ cb[0],
+					ct[1] ^ k[i + 1] ^ cb[1],
+				]);
+				write(dest.offset(i as isize + 2), ct[2] ^ k[i + 2] ^ cb[2]);
+			},
+			_ => (),
+		}
+		return assume_init(dst);
+	}
+}
+
+// Whether to decode with plain loops instead of chunked volatile reads, see the `simple-decode` feature.
+// The plain loops are smaller but give the optimizer the chance to constant fold the deobfuscation.
+const SIMPLE_DECODE: bool = cfg!(feature = "simple-decode");
+
+/// Whether the macros share a single deobfuscation routine, see the `small-code` feature.
+#[doc(hidden)]
+pub const SMALL_CODE: bool = cfg!(feature = "small-code");
+
+/// Whether the macros deobfuscate in a stub per call site, see the `outline-decode` feature.
+#[doc(hidden)]
+pub const OUTLINE: bool = cfg!(feature = "outline-decode");
+
+/// Deobfuscates `len` bytes of obfuscated data into the destination.
+///
+/// Shared by all strings with the `small-code` feature instead of monomorphizing [`deobfuscate`] for every length.
+///
+/// # Safety
+///
+/// The source and key stream must be readable and the destination writable for `len` bytes.
+#[inline(never)]
+pub unsafe fn deobfuscate_dyn(src: *const u8, keys: *const u8, dst: *mut u8, len: usize) {
+	let mut i = 0;
+	while i < len {
+		let ct = read_volatile(src.add(i)) ^ if i < 8 { 0 } else { read_volatile(src.add(i - 8)) };
+		write(dst.add(i), ct ^ *keys.add(i));
+		i += 1;
+	}
+}
+
+/// Compares `len` bytes of obfuscated data with the other string.
+///
+/// The key stream is additionally masked with `mask`, see [`fold_key`].
+///
+/// # Safety
+///
+/// The source and key stream must be readable for `len` bytes.
+#[inline(never)]
+pub unsafe fn equals_dyn(src: *const u8, keys: *const u8, mask: u64, len: usize, other: &[u8]) -> bool {
+	if other.len() != len {
+		return false;
+	}
+	let mb = mask.to_le_bytes();
+	let mut i = 0;
+	while i < len {
+		let ct = read_volatile(src.add(i)) ^ if i < 8 { 0 } else { read_volatile(src.add(i - 8)) };
+		if ct ^ *keys.add(i) ^ mb[i % 8] != other[i] {
+			return false;
+		}
+		i += 1;
+	}
+	return true;
+}
+
+// Removes the key stream mask from the deobfuscated data.
+#[inline(never)]
+fn unmask_dyn(buf: &mut [u8], mask: u64) {
+	let mb = mask.to_le_bytes();
+	for i in 0..buf.len() {
+		buf[i] ^= mb[i % 8];
+	}
+}
+
+/// Deobfuscates the obfuscated input string with [`deobfuscate_dyn`].
+#[inline(always)]
+pub fn deobfuscate_small<const LEN: usize>(s: &[u8; LEN], k: &'static [u8; LEN], parts: [u16; 4]) -> [u8; LEN] {
+	let mut buf = [0u8; LEN];
+	deobfuscate_small_into::<LEN>(s, k, parts, &mut buf);
+	return buf;
+}
+
+/// Deobfuscates the obfuscated input string with [`deobfuscate_dyn`] directly into the destination.
+///
+/// The destination may be uninitialized, returns the deobfuscated string.
+/// Panics if the destination is shorter than the string before anything is written.
+#[inline(always)]
+pub fn deobfuscate_small_into<'a, const LEN: usize>(s: &[u8; LEN], k: &'static [u8; LEN], parts: [u16; 4], dst: &'a mut (impl Buffer<u8> + ?Sized)) -> &'a mut [u8] {
+	// Safety: only initialized values are written to the destination
+	let dst = unsafe { dst.uninit_prefix(LEN) };
+	let dst = unsafe {
+		deobfuscate_dyn(s.as_ptr(), k.as_ptr(), dst.as_mut_ptr() as *mut u8, LEN);
+		assume_init(dst)
+	};
+	if MASKED {
+		unmask_dyn(dst, unmask(parts));
+	}
+	return dst;
+}
+
+/// Compares the obfuscated string with the other string with [`equals_dyn`].
+#[inline(always)]
+pub fn equals_small<const LEN: usize>(s: &[u8; LEN], k: &'static [u8; LEN], parts: [u16; 4], other: &[u8]) -> bool {
+	let mask = if MASKED { unmask(parts) } else { 0 };
+	return unsafe { equals_dyn(s.as_ptr(), k.as_ptr(), mask, LEN, other) };
+}
+
+/// Chunk size of [`deobfuscate_to_writer`].
+pub const WRITER_CHUNK: usize = 64;
+
+/// Deobfuscates the obfuscated UTF-8 string into the writer.
+///
+/// The string is decoded [`WRITER_CHUNK`] bytes at a time and the chunk buffer is wiped after every write.
+/// A chunk never splits a multi-byte character, the incomplete tail is carried over to the next chunk.
+/// Returns an error if the decoded string is not valid UTF-8.
+pub fn deobfuscate_to_writer<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], w: &mut dyn fmt::Write) -> fmt::Result {
+	let mut buf = [0u8; WRITER_CHUNK + 3];
+	let src = s.as_ptr();
+	let mut pending = 0;
+	let mut i = 0;
+	let mut result = Ok(());
+	while i < LEN && result.is_ok() {
+		// Decode the next chunk after the carried over bytes
+		let n = if LEN - i < WRITER_CHUNK { LEN - i } else { WRITER_CHUNK };
+		let mut j = 0;
+		while j < n {
+			let ct = unsafe { read_volatile(src.offset((i + j) as isize)) ^ chain::<1>(src, i + j)[0] };
+			buf[pending + j] = ct ^ k[i + j];
+			j += 1;
+		}
+		i += n;
+		let total = pending + n;
+		let valid = match str::from_utf8(&buf[..total]) {
+			Ok(_) => total,
+			Err(err) if err.error_len().is_none() => err.valid_up_to(),
+			Err(_) => {
+				result = Err(fmt::Error);
+				0
+			},
+		};
+		if result.is_ok() && valid > 0 {
+			result = w.write_str(unsafe { str::from_utf8_unchecked(&buf[..valid]) });
+		}
+		// Carry the incomplete character over and wipe the rest
+		buf.copy_within(valid..total, 0);
+		pending = total - valid;
+		for b in &mut buf[pending..] {
+			unsafe { write_volatile(b, 0) };
+		}
+	}
+	if pending != 0 {
+		for b in &mut buf[..] {
+			unsafe { write_volatile(b, 0) };
+		}
+		if result.is_ok() {
+			result = Err(fmt::Error);
+		}
+	}
+	return result;
+}
+
+/// Obfuscates the data in place with the key stream of the given key.
+///
+/// Runtime twin of [`obfuscate`] for data whose length is only known at runtime,
+/// the result equals `obfuscate::<N>(data, &keystream::<N>(key))`.
+///
+/// This is obfuscation, not encryption. Do not rely on it to protect secrets.
+pub fn obfuscate_slice(data: &mut [u8], key: u64) {
+	let mut state = key;
+	let mut kb = [0u8; 8];
+	for i in 0..data.len() {
+		if i % 8 == 0 {
+			state = next_round64(state);
+			kb = state.to_le_bytes();
+		}
+		data[i] ^= kb[i % 8] ^ if i < 8 { 0 } else { data[i - 8] };
+	}
+}
+
+/// Deobfuscates the data in place with the key stream of the given key.
+///
+/// Undoes [`obfuscate_slice`].
+pub fn deobfuscate_slice(data: &mut [u8], key: u64) {
+	let mut state = key;
+	let mut kb = [0u8; 8];
+	// The previous 8 bytes of ciphertext
+	let mut prev = [0u8; 8];
+	for i in 0..data.len() {
+		if i % 8 == 0 {
+			state = next_round64(state);
+			kb = state.to_le_bytes();
+		}
+		let ct = data[i];
+		data[i] = ct ^ kb[i % 8] ^ prev[i % 8];
+		prev[i % 8] = ct;
+	}
+}
+
+/// Re-keys the data obfuscated by [`obfuscate_slice`] from one key to another.
+///
+/// Every byte is deobfuscated and obfuscated again on the fly, the plaintext is never written back to memory.
+pub fn rekey_slice(data: &mut [u8], from: u64, to: u64) {
+	let (mut from_state, mut to_state) = (from, to);
+	let (mut from_kb, mut to_kb) = ([0u8; 8], [0u8; 8]);
+	// The previous 8 bytes of ciphertext under the old key
+	let mut prev = [0u8; 8];
+	for i in 0..data.len() {
+		if i % 8 == 0 {
+			from_state = next_round64(from_state);
+			from_kb = from_state.to_le_bytes();
+			to_state = next_round64(to_state);
+			to_kb = to_state.to_le_bytes();
+		}
+		let ct = data[i];
+		let pt = ct ^ from_kb[i % 8] ^ prev[i % 8];
+		prev[i % 8] = ct;
+		data[i] = pt ^ to_kb[i % 8] ^ if i < 8 { 0 } else { data[i - 8] };
+	}
+}
+
+// Wipes the buffer when it goes out of scope, even when unwinding.
+pub(crate) struct Wipe<'a>(pub(crate) &'a mut [u8]);
+impl Drop for Wipe<'_> {
+	#[inline]
+	fn drop(&mut self) {
+		wipe(self.0);
+	}
+}
+
+#[inline(never)]
+pub(crate) fn wipe(buf: &mut [u8]) {
+	for b in buf.iter_mut() {
+		unsafe { write_volatile(b, 0) };
+	}
+}
+
+/// Verifies the deobfuscated string against the murmur3 checksum of its plaintext.
+///
+/// The checksum is stored xored with the mask. On mismatch the hook is called and the string is wiped.
+#[inline]
+pub fn verify<F: FnOnce()>(s: &mut [u8], check: &u32, seed: u32, mask: u32, hook: F) {
+	let expected = unsafe { read_volatile(check) } ^ mask;
+	if crate::murmur3(s, seed) != expected {
+		hook();
+		wipe(s);
+	}
+}
+
+/// Default hook of the `#[verified]` form.
+///
+/// Panics in debug builds and does nothing in release builds.
+#[cold]
+#[inline(never)]
+pub fn verify_failed() {
+	if cfg!(debug_assertions) {
+		panic!("obfuscated string failed its integrity check");
+	}
+}
+
+/// Fixed size secret which stays obfuscated under a key which can be rotated.
+///
+/// The secret is obfuscated with [`obfuscate_slice`] and only deobfuscated for the duration of [`RekeyedSecret::with`].
+/// Rotate the key periodically with [`RekeyedSecret::rekey`] to avoid long-lived secrets staying under the same key.
+///
+/// ```
+/// use obfstr::bytes::RekeyedSecret;
+///
+/// let mut password = *b"hunter2";
+/// let mut secret = RekeyedSecret::new(&mut password, 0x1234);
+/// // The plaintext is wiped
+/// assert_eq!(password, [0; 7]);
+/// secret.rekey(0x5678);
+/// assert!(secret.with(|s| s == b"hunter2"));
+/// ```
+pub struct RekeyedSecret<const LEN: usize> {
+	data: [u8; LEN],
+	key: u64,
+}
+impl<const LEN: usize> RekeyedSecret<LEN> {
+	/// Obfuscates the secret under the given key.
+	///
+	/// The plaintext is wiped, no copy of the plaintext is left behind.
+	#[inline]
+	pub fn new(plaintext: &mut [u8; LEN], key: u64) -> RekeyedSecret<LEN> {
+		let mut secret = RekeyedSecret { data: [0u8; LEN], key };
+		secret.data.copy_from_slice(plaintext);
+		wipe(plaintext);
+		obfuscate_slice(&mut secret.data, key);
+		secret
+	}
+	/// Re-keys the secret under the new key without deobfuscating it in memory, see [`rekey_slice`].
+	#[inline]
+	pub fn rekey(&mut self, key: u64) {
+		rekey_slice(&mut self.data, self.key, key);
+		self.key = key;
+	}
+	/// Deobfuscates the secret into a temporary buffer and passes it to the closure.
+	///
+	/// The temporary buffer is wiped after the closure returns.
+	pub fn with<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		let mut buf = self.data;
+		let buf = Wipe(&mut buf);
+		deobfuscate_slice(buf.0, self.key);
+		f(buf.0)
+	}
+}
+impl<const LEN: usize> Drop for RekeyedSecret<LEN> {
+	fn drop(&mut self) {
+		wipe(&mut self.data);
+		unsafe { write_volatile(&mut self.key, 0) };
+	}
+}
+impl<const LEN: usize> PartialEq<[u8]> for RekeyedSecret<LEN> {
+	#[inline]
+	fn eq(&self, other: &[u8]) -> bool {
+		equals_slice(&self.data, self.key, other)
+	}
+}
+impl<const LEN: usize> fmt::Debug for RekeyedSecret<LEN> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		// Do not leak the secret through debug formatting
+		f.debug_struct("RekeyedSecret").field("len", &LEN).finish()
+	}
+}
+
+/// Compares the data obfuscated by [`obfuscate_slice`] with the other string.
+///
+/// The data is never deobfuscated in memory.
+pub fn equals_slice(data: &[u8], key: u64, other: &[u8]) -> bool {
+	if data.len() != other.len() {
+		return false;
+	}
+	let mut state = key;
+	let mut kb = [0u8; 8];
+	for i in 0..data.len() {
+		if i % 8 == 0 {
+			state = next_round64(state);
+			kb = state.to_le_bytes();
+		}
+		if data[i] ^ kb[i % 8] ^ if i < 8 { 0 } else { data[i - 8] } != other[i] {
+			return false;
+		}
+	}
+	return true;
+}
+
+/// Deobfuscates the obfuscated string in place.
+///
+/// Walks the string back to front so the chained ciphertext is still intact when it is needed.
+#[inline(always)]
+pub fn deobfuscate_in_place<const LEN: usize>(s: &mut [u8; LEN], k: &[u8; LEN]) {
+	let mut i = LEN;
+	while i > 0 {
+		i -= 1;
+		s[i] ^= k[i] ^ if i < 8 { 0 } else { s[i - 8] };
+	}
+}
+
+/// Obfuscates the string deobfuscated with [`deobfuscate_in_place`] again.
+///
+/// Walks the string front to back, the inverse of [`deobfuscate_in_place`].
+#[inline(always)]
+pub fn reobfuscate_in_place<const LEN: usize>(s: &mut [u8; LEN], k: &[u8; LEN]) {
+	let mut i = 0;
+	while i < LEN {
+		s[i] ^= k[i] ^ if i < 8 { 0 } else { s[i - 8] };
+		i += 1;
+	}
+}
+
+/// Obfuscated static which is decoded in place while locked.
+///
+/// The string is only decoded while an [`InPlaceGuard`] is alive and is re-obfuscated when the guard drops.
+///
+/// # Thread safety
+///
+/// The static is shared between threads but only one guard can exist at a time.
+/// Locking while another guard is alive, on this or any other thread, panics instead of blocking.
+///
+/// Only available on targets with atomic compare and swap.
+#[cfg(target_has_atomic = "8")]
+pub struct InPlace<const LEN: usize> {
+	data: core::cell::UnsafeCell<[u8; LEN]>,
+	locked: AtomicBool,
+}
+#[cfg(target_has_atomic = "8")]
+unsafe impl<const LEN: usize> Sync for InPlace<LEN> {}
+#[cfg(target_has_atomic = "8")]
+impl<const LEN: usize> InPlace<LEN> {
+	/// Wraps the obfuscated data.
+	#[inline(always)]
+	pub const fn new(data: [u8; LEN]) -> InPlace<LEN> {
+		InPlace { data: core::cell::UnsafeCell::new(data), locked: AtomicBool::new(false) }
+	}
+	/// Decodes the data in place.
+	///
+	/// Panics if the data is already locked.
+	#[inline(always)]
+	pub fn lock(&self, keys: [u8; LEN]) -> InPlaceGuard<'_, LEN> {
+		if self.locked.swap(true, Ordering::Acquire) {
+			panic!("obfuscated static already locked");
+		}
+		deobfuscate_in_place::<LEN>(unsafe { &mut *self.data.get() }, &keys);
+		return InPlaceGuard { cell: self, keys };
+	}
+}
+
+/// Guard which dereferences to the data decoded in place and re-obfuscates it on drop.
+#[cfg(target_has_atomic = "8")]
+pub struct InPlaceGuard<'a, const LEN: usize> {
+	cell: &'a InPlace<LEN>,
+	keys: [u8; LEN],
+}
+#[cfg(target_has_atomic = "8")]
+impl<'a, const LEN: usize> ops::Deref for InPlaceGuard<'a, LEN> {
+	type Target = [u8; LEN];
+	#[inline(always)]
+	fn deref(&self) -> &[u8; LEN] {
+		unsafe { &*self.cell.data.get() }
+	}
+}
+#[cfg(target_has_atomic = "8")]
+impl<'a, const LEN: usize> Drop for InPlaceGuard<'a, LEN> {
+	#[inline(always)]
+	fn drop(&mut self) {
+		reobfuscate_in_place::<LEN>(unsafe { &mut *self.cell.data.get() }, &self.keys);
+		self.cell.locked.store(false, Ordering::Release);
+	}
+}
+
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use std::thread_local;
+
+/// Thread local buffer of a string deobfuscated once per thread.
+///
+/// Each `#[cached]` call site owns a cache in a `thread_local!` static.
+/// With `debug_assertions` enabled every access checks the cached string against a fresh deobfuscation to catch memory corruption.
+#[cfg(feature = "std")]
+pub struct Cache<const LEN: usize> {
+	data: core::cell::UnsafeCell<[u8; LEN]>,
+	init: core::cell::Cell<bool>,
+}
+#[cfg(feature = "std")]
+impl<const LEN: usize> Cache<LEN> {
+	/// Constructs an empty cache.
+	#[inline(always)]
+	pub const fn new() -> Cache<LEN> {
+		Cache { data: core::cell::UnsafeCell::new([0u8; LEN]), init: core::cell::Cell::new(false) }
+	}
+	/// Returns the cached string, deobfuscating it on first use by this thread.
+	#[inline(always)]
+	pub fn get(cache: &'static std::thread::LocalKey<Cache<LEN>>, s: &[u8; LEN], k: &[u8; LEN]) -> Cached<LEN> {
+		let data = cache.with(|cache| {
+			if !cache.init.get() {
+				// Safety: no references to the buffer exist before it is initialized
+				unsafe { *cache.data.get() = deobfuscate::<LEN>(s, k) };
+				cache.init.set(true);
+			}
+			cache.data.get() as *const [u8; LEN]
+		});
+		let cached = Cached { data, _marker: core::marker::PhantomData };
+		debug_assert!(*cached == deobfuscate::<LEN>(s, k), "obfuscated string cache corrupted");
+		return cached;
+	}
+}
+#[cfg(feature = "std")]
+impl<const LEN: usize> Default for Cache<LEN> {
+	#[inline(always)]
+	fn default() -> Cache<LEN> {
+		Cache::new()
+	}
+}
+
+/// Dereferences to the string in a thread local [`Cache`].
+///
+/// The guard cannot leave the thread which owns the cache.
+#[cfg(feature = "std")]
+pub struct Cached<const LEN: usize> {
+	data: *const [u8; LEN],
+	_marker: core::marker::PhantomData<*const ()>,
+}
+#[cfg(feature = "std")]
+impl<const LEN: usize> ops::Deref for Cached<LEN> {
+	type Target = [u8; LEN];
+	#[inline(always)]
+	fn deref(&self) -> &[u8; LEN] {
+		// Safety: the cache is never written to again and outlives the guard on this thread
+		unsafe { &*self.data }
+	}
+}
+
+/// Folds a runtime key into the key stream.
+#[inline(always)]
+pub const fn fold_key<const LEN: usize>(k: &[u8; LEN], rk: u64) -> [u8; LEN] {
+	let mut keys = *k;
+	let mut i = 0;
+	while i + BLOCK <= LEN {
+		let mut words = load_block(&keys, i);
+		let mut j = 0;
+		while j < BLOCK_WORDS {
+			words[j] ^= rk.to_le();
+			j += 1;
+		}
+		store_block(&mut keys, i, words);
+		i += BLOCK;
+	}
+	let rk = rk.to_le_bytes();
+	while i < LEN {
+		keys[i] ^= rk[i % 8];
+		i += 1;
+	}
+	return keys;
+}
+
+/// Deobfuscates the input string obfuscated with the key stream folded with a runtime key, see [`fold_key`].
+///
+/// With the wrong runtime key the result is garbage.
+#[inline(always)]
+pub fn deobfuscate_keyed<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], rk: u64) -> [u8; LEN] {
+	// The transform is linear, fold the runtime key into the deobfuscated string
+	let buf = deobfuscate::<LEN>(s, k);
+	return fold_key::<LEN>(&buf, rk);
+}
+
+/// Maximum length of strings encoded as immediates, longer strings fall back to static data.
+pub const IMMEDIATE_MAX: usize = 32;
+
+/// Obfuscates the input string as 64-bit words to be encoded as immediates.
+///
+/// Returns an empty array if `WORDS` is zero regardless of the input string.
+#[inline(always)]
+pub const fn immediate_words<const WORDS: usize>(s: &[u8], k: &[u64; WORDS]) -> [u64; WORDS] {
+	if WORDS != 0 && WORDS != s.len().div_ceil(8) {
+		panic!("key stream words do not match the input string len");
+	}
+	let mut words = [0u64; WORDS];
+	let mut i = 0;
+	while i < WORDS {
+		let mut bytes = [0u8; 8];
+		let mut j = 0;
+		while j < 8 && i * 8 + j < s.len() {
+			bytes[j] = s[i * 8 + j];
+			j += 1;
+		}
+		words[i] = u64::from_ne_bytes(bytes) ^ k[i];
+		i += 1;
+	}
+	return words;
+}
+
+/// Deobfuscates the string encoded as immediates.
+///
+/// Each word passes through `black_box` to keep LLVM from folding them back into a constant byte array.
+/// If `WORDS` is zero the string is instead deobfuscated from static data of length `SLEN`.
+#[inline(always)]
+pub fn deobfuscate_immediate<const LEN: usize, const WORDS: usize, const SLEN: usize>(ct: [u64; WORDS], k: [u64; WORDS], s: &[u8; SLEN], sk: &[u8; SLEN]) -> [u8; LEN] {
+	let mut buf = [0u8; LEN];
+	if WORDS == 0 {
+		buf[..SLEN].copy_from_slice(&deobfuscate::<SLEN>(s, sk));
+		return buf;
+	}
+	let mut i = 0;
+	while i < WORDS {
+		let word = (hint::black_box(ct[i]) ^ hint::black_box(k[i])).to_ne_bytes();
+		let n = if LEN - i * 8 < 8 { LEN - i * 8 } else { 8 };
+		buf[i * 8..i * 8 + n].copy_from_slice(&word[..n]);
+		i += 1;
+	}
+	return buf;
+}
+
+/// Splits the length in 2 to 4 pieces with random boundaries.
+///
+/// Unused pieces have zero length.
+#[inline(always)]
+pub const fn split_lens(len: usize, seed: u64) -> [usize; 4] {
+	let n = 2 + (seed % 3) as usize;
+	// Random weights between 1 and 16 for each piece
+	let mut weights = [0usize; 4];
+	let mut total = 0;
+	let mut i = 0;
+	while i < n {
+		weights[i] = 1 + (seed >> (8 + i * 4) & 15) as usize;
+		total += weights[i];
+		i += 1;
+	}
+	let mut lens = [0usize; 4];
+	let mut rest = len;
+	let mut i = 0;
+	while i < n - 1 {
+		lens[i] = len * weights[i] / total;
+		rest -= lens[i];
+		i += 1;
+	}
+	lens[n - 1] = rest;
+	return lens;
+}
+
+/// Copies a piece of the obfuscated data.
+#[inline(always)]
+pub const fn piece<const N: usize>(data: &[u8], offset: usize) -> [u8; N] {
+	let mut piece = [0u8; N];
+	let mut i = 0;
+	while i < N {
+		piece[i] = data[offset + i];
+		i += 1;
+	}
+	return piece;
+}
+
+/// Gathers the pieces of obfuscated data in order.
+#[inline(always)]
+pub fn gather<const LEN: usize, const A: usize, const B: usize, const C: usize, const D: usize>(a: &[u8; A], b: &[u8; B], c: &[u8; C], d: &[u8; D]) -> [u8; LEN] {
+	assert_eq!(A + B + C + D, LEN);
+	let mut data = [0u8; LEN];
+	let mut offset = 0;
+	for piece in [&a[..], &b[..], &c[..], &d[..]] {
+		let src = piece.as_ptr();
+		let mut i = 0;
+		while i < piece.len() {
+			data[offset + i] = unsafe { read_volatile(src.offset(i as isize)) };
+			i += 1;
+		}
+		offset += piece.len();
+	}
+	return data;
+}
+
+/// Deobfuscates the obfuscated input string split in pieces.
+#[inline(always)]
+pub fn deobfuscate_split<const LEN: usize, const A: usize, const B: usize, const C: usize, const D: usize>(a: &[u8; A], b: &[u8; B], c: &[u8; C], d: &[u8; D], k: &[u8; LEN]) -> [u8; LEN] {
+	let data = gather::<LEN, A, B, C, D>(a, b, c, d);
+	return deobfuscate::<LEN>(&data, k);
+}
+
+/// Compares the obfuscated string split in pieces with the other string.
+#[inline(always)]
+pub fn equals_split<const LEN: usize, const A: usize, const B: usize, const C: usize, const D: usize>(a: &[u8; A], b: &[u8; B], c: &[u8; C], d: &[u8; D], k: &[u8; LEN], other: &[u8]) -> bool {
+	if other.len() != LEN {
+		return false;
+	}
+	let data = gather::<LEN, A, B, C, D>(a, b, c, d);
+	return equals::<LEN>(&data, k, other);
+}
+
+/// Obfuscated data padded to hide its length.
+///
+/// The true length is stored obfuscated in `len`.
+#[doc(hidden)]
+#[repr(C)]
+pub struct Padded<T> {
+	pub data: T,
+	pub len: u64,
+}
+
+/// Deobfuscated padded data which dereferences to the true length.
+pub struct Unpadded<T, const PADDED: usize> {
+	pub(crate) buf: [T; PADDED],
+	pub(crate) len: usize,
+}
+impl<T, const PADDED: usize> ops::Deref for Unpadded<T, PADDED> {
+	type Target = [T];
+	#[inline(always)]
+	fn deref(&self) -> &[T] {
+		&self.buf[..self.len]
+	}
+}
+
+/// Rounds the length up to the next multiple of 16 and adds a random extra of up to 15.
+///
+/// The padded length is always larger than the string length.
+#[inline(always)]
+pub const fn padded_len(len: usize, seed: u64) -> usize {
+	return (len / 16 + 1) * 16 + (seed % 16) as usize;
+}
+
+/// Pads the string with filler bytes derived from the seed.
+#[inline(always)]
+pub const fn pad<const PADDED: usize>(s: &[u8], seed: u64) -> [u8; PADDED] {
+	if s.len() > PADDED {
+		panic!("input string len larger than the padded len");
+	}
+	let mut data = keystream::<PADDED>(seed);
+	let mut i = 0;
+	while i < s.len() {
+		data[i] = s[i];
+		i += 1;
+	}
+	return data;
+}
+
+// Recovers the true length of the padded data.
+#[inline(always)]
+pub(crate) fn unpad_len<T, const PADDED: usize>(s: &Padded<[T; PADDED]>, len_key: u64) -> usize {
+	let len = (unsafe { read_volatile(&s.len) } ^ len_key) as usize;
+	return if len > PADDED { PADDED } else { len };
+}
+
+/// Deobfuscates the padded input string, the result dereferences to the true length.
+#[inline(always)]
+pub fn deobfuscate_padded<const PADDED: usize>(s: &Padded<[u8; PADDED]>, k: &[u8; PADDED], len_key: u64) -> Unpadded<u8, PADDED> {
+	let len = unpad_len(s, len_key);
+	let buf = deobfuscate::<PADDED>(&s.data, k);
+	return Unpadded { buf, len };
+}
+
+/// Deobfuscates the true length of the padded input string directly into the destination.
+///
+/// The destination may be uninitialized, returns the deobfuscated string.
+/// Panics if the destination is shorter than the string before anything is written.
+#[inline(always)]
+pub fn deobfuscate_padded_into<'a, const PADDED: usize>(s: &Padded<[u8; PADDED]>, k: &[u8; PADDED], len_key: u64, dst: &'a mut (impl Buffer<u8> + ?Sized)) -> &'a mut [u8] {
+	let len = unpad_len(s, len_key);
+	// Safety: only initialized values are written to the destination
+	let dst = unsafe { dst.uninit_prefix(len) };
+	let src = s.data.as_ptr();
+	for i in 0..len {
+		let ct = unsafe { read_volatile(src.offset(i as isize)) ^ chain::<1>(src, i)[0] };
+		dst[i] = MaybeUninit::new(ct ^ k[i]);
+	}
+	return unsafe { assume_init(dst) };
+}
+
+/// Compares the padded obfuscated string with the other string.
+///
+/// Only the true length is compared, the padding is never decoded.
+#[inline(always)]
+pub fn equals_padded<const PADDED: usize>(s: &Padded<[u8; PADDED]>, k: &[u8; PADDED], len_key: u64, other: &[u8]) -> bool {
+	let len = unpad_len(s, len_key);
+	if other.len() != len {
+		return false;
+	}
+	let src = s.data.as_ptr();
+	let mut i = 0;
+	while i < len {
+		let ct = unsafe { read_volatile(src.offset(i as isize)) ^ chain::<1>(src, i)[0] };
+		if ct ^ k[i] != other[i] {
+			return false;
+		}
+		i += 1;
+	}
+	return true;
+}
+
+/// Obfuscates the input string padded to the key stream length with the pad byte.
+///
+/// Panics if the input string is longer than the key stream, which is a compile error in const context.
+#[inline(always)]
+pub const fn obfuscate_padded<const LEN: usize>(s: &[u8], k: &[u8; LEN], pad: u8) -> [u8; LEN] {
+	if s.len() > LEN {
+		panic!("input string len larger than key stream len");
+	}
+	let mut data = [pad; LEN];
+	let mut i = 0;
+	while i < s.len() {
+		data[i] = s[i];
+		i += 1;
+	}
+	return obfuscate::<LEN>(&data, k);
+}
+
+/// Compares the first `len` bytes of the obfuscated string with the other string.
+///
+/// The remaining bytes are never decoded.
+#[inline(always)]
+pub fn equals_prefix<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], len: usize, other: &[u8]) -> bool {
+	if len > LEN || other.len() != len {
+		return false;
+	}
+	let src = s.as_ptr();
+	let mut i = 0;
+	while i < len {
+		let ct = unsafe { read_volatile(src.offset(i as isize)) ^ chain::<1>(src, i)[0] };
+		if ct ^ k[i] != other[i] {
+			return false;
+		}
+		i += 1;
+	}
+	return true;
+}
+
+/// Fixed size slot holding a string of length `LEN` padded to `PAD` bytes.
+///
+/// Dereferences to the whole slot, the true length is available as a const.
+pub struct Slot<const PAD: usize, const LEN: usize> {
+	pub data: [u8; PAD],
+}
+impl<const PAD: usize, const LEN: usize> Slot<PAD, LEN> {
+	/// The true length of the string.
+	pub const LEN: usize = LEN;
+
+	/// Returns the string without the padding.
+	#[inline(always)]
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.data[..LEN]
+	}
+}
+impl<const PAD: usize, const LEN: usize> ops::Deref for Slot<PAD, LEN> {
+	type Target = [u8; PAD];
+	#[inline(always)]
+	fn deref(&self) -> &[u8; PAD] {
+		&self.data
+	}
+}
+
+/// Relocated pointer to obfuscated data, biased by a random offset.
+///
+/// The pointer is filled in by relocation at load time and keeps working under ASLR.
+/// It lives in its own static, the obfuscated data stays in read-only data.
+#[doc(hidden)]
+#[repr(transparent)]
+pub struct Anchor(pub *const u8);
+unsafe impl Sync for Anchor {}
+
+/// Derives the runtime key from the distance between the anchor and the address of the data.
+///
+/// The compiletime side folds `selfkey(bias)` into the key stream where the bias is a random offset per string.
+/// The anchor points `bias` bytes past the data, decoding only cancels out when the data is decoded in place.
+#[inline(always)]
+pub const fn selfkey(delta: usize) -> u64 {
+	return crate::splitmix(delta as u64);
+}
+
+// Distance between the anchor and the data, read through a volatile load so it is not folded to the bias.
+#[inline(always)]
+pub(crate) fn anchor_delta(anchor: &Anchor, data: *const u8) -> usize {
+	let anchor = unsafe { read_volatile(&anchor.0) };
+	return (anchor as usize).wrapping_sub(data as usize);
+}
+
+/// Deobfuscates the input string bound to its own address.
+///
+/// Decoding a copy of the data anywhere else produces garbage.
+#[inline(always)]
+pub fn deobfuscate_selfkeyed<const LEN: usize>(s: &[u8; LEN], anchor: &Anchor, k: &[u8; LEN]) -> [u8; LEN] {
+	let delta = anchor_delta(anchor, s.as_ptr());
+	let buf = deobfuscate::<LEN>(s, k);
+	return fold_key::<LEN>(&buf, selfkey(delta));
+}
+
+#[inline(always)]
+pub fn equals<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], other: &[u8]) -> bool {
+	if other.len() != LEN {
+		return false;
+	}
+	if SIMPLE_DECODE {
+		for j in 0..LEN {
+			if s[j] ^ k[j] ^ if j < 8 { 0 } else { s[j - 8] } != other[j] {
+				return false;
+			}
+		}
+		return true;
+	}
+	let mut i = 0;
+	// Try to tickle the LLVM optimizer in _just_ the right way
+	// Use `read_volatile` to avoid constant folding a specific read and optimize the rest
+	// Volatile reads of any size larger than 8 bytes appears to cause a bunch of one byte reads
+	// Hand optimize in chunks of 8 and 4 bytes to avoid this
+	unsafe {
+		let src = s.as_ptr();
+		// Process in chunks of 16 or 32 bytes on x86_64
+		#[cfg(target_arch = "x86_64")]
+		match simd_equals::<LEN>(src, k, other.as_ptr()) {
+			Some(next) => i += next,
+			None => return false,
+		}
+		// Process in chunks of 8 bytes on 64-bit targets
+		#[cfg(target_pointer_width = "64")]
+		while i < LEN & !7 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u8; 8]);
+			let tmp = u64::from_ne_bytes([ct[0], ct[1], ct[2], ct[3], ct[4], ct[5], ct[6], ct[7]]) ^
+				u64::from_ne_bytes([k[i + 0], k[i + 1], k[i + 2], k[i + 3], k[i + 4], k[i + 5], k[i + 6], k[i + 7]]) ^
+				u64::from_ne_bytes(chain::<8>(src, i));
+			let other = u64::from_ne_bytes([other[i + 0], other[i + 1], other[i + 2], other[i + 3], other[i + 4], other[i + 5], other[i + 6], other[i + 7]]);
+			if tmp != other {
+				return false;
+			}
+			i += 8;
+		}
+		// Process in chunks of 4 bytes on 32-bit and 64-bit targets
+		#[cfg(not(target_pointer_width = "16"))]
+		while i < LEN & !3 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u8; 4]);
+			let tmp = u32::from_ne_bytes([ct[0], ct[1], ct[2], ct[3]]) ^
+				u32::from_ne_bytes([k[i + 0], k[i + 1], k[i + 2], k[i + 3]]) ^
+				u32::from_ne_bytes(chain::<4>(src, i));
+			let other = u32::from_ne_bytes([other[i + 0], other[i + 1], other[i + 2], other[i + 3]]);
+			if tmp != other {
+				return false;
+			}
+			i += 4;
+		}
+		// Process in chunks of 2 bytes on 16-bit targets
+		#[cfg(target_pointer_width = "16")]
+		while i < LEN & !1 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u8; 2]);
+			let cb = chain::<2>(src, i);
+			if ct[0] ^ k[i + 0] ^ cb[0] != other[i + 0] || ct[1] ^ k[i + 1] ^ cb[1] != other[i + 1] {
+				return false;
+			}
+			i += 2;
+		}
+		// Process the remaining bytes
+		match LEN % TAIL_CHUNK {
+			1 => {
+				let ct = read_volatile(src.offset(i as isize));
+				ct ^ k[i] ^ chain::<1>(src, i)[0] == other[i]
+			},
+			2 => {
+				let ct = read_volatile(src.offset(i as isize) as *const [u8; 2]);
+				let cb = chain::<2>(src, i);
+				u16::from_ne_bytes([ct[0], ct[1]]) ^ u16::from_ne_bytes([k[i + 0] ^ cb[0], k[i + 1] ^ cb[1]]) == u16::from_ne_bytes([other[i + 0], other[i + 1]])
+			},
+			3 => {
+				let ct = read_volatile(src.offset(i as isize) as *const [u8; 3]);
+				let cb = chain::<3>(src, i);
+				u32::from_ne_bytes([ct[0], ct[1], ct[2], 0]) ^ u32::from_ne_bytes([k[i + 0] ^ cb[0], k[i + 1] ^ cb[1], k[i + 2] ^ cb[2], 0]) == u32::from_ne_bytes([other[i + 0], other[i + 1], other[i + 2], 0])
+			},
+			_ => true,
+		}
+	}
+}
+
+// Add/rotate transform: each byte is offset by its key byte and then rotated by the top bits of that key byte.
+#[inline(always)]
+const fn encode2(p: u8, k: u8) -> u8 {
+	return p.wrapping_add(k).rotate_left((k >> 5) as u32);
+}
+#[inline(always)]
+const fn decode2(c: u8, k: u8) -> u8 {
+	return c.rotate_right((k >> 5) as u32).wrapping_sub(k);
+}
+// Applies `encode2` to the 8 bytes of the words at once.
+#[inline(always)]
+const fn encode2_word(p: u64, k: u64) -> u64 {
+	const LO: u64 = 0x0101010101010101;
+	const HI: u64 = LO << 7;
+	// Add the bytes without carrying into the next byte
+	let mut x = ((p & !HI) + (k & !HI)) ^ ((p ^ k) & HI);
+	// Rotate every byte by the top 3 bits of its key byte, one bit of the rotation at a time
+	let rotated = (x << 1) & 0xfefefefefefefefe | (x >> 7) & 0x0101010101010101;
+	x ^= (x ^ rotated) & ((k >> 5 & LO) * 0xff);
+	let rotated = (x << 2) & 0xfcfcfcfcfcfcfcfc | (x >> 6) & 0x0303030303030303;
+	x ^= (x ^ rotated) & ((k >> 6 & LO) * 0xff);
+	let rotated = (x << 4) & 0xf0f0f0f0f0f0f0f0 | (x >> 4) & 0x0f0f0f0f0f0f0f0f;
+	x ^= (x ^ rotated) & ((k >> 7 & LO) * 0xff);
+	return x;
+}
+
+/// Obfuscates the input string and given key stream with the add/rotate transform.
+///
+/// Unlike [`obfuscate`] combining two ciphertexts with the same key stream does not cancel out the key stream.
+#[inline(always)]
+pub const fn obfuscate2<const LEN: usize>(s: &[u8], k: &[u8; LEN]) -> [u8; LEN] {
+	if s.len() != LEN {
+		panic!("input string len not equal to key stream len");
+	}
+	let mut data = [0u8; LEN];
+	let mut i = 0;
+	// Encode in blocks of words
+	while i + BLOCK <= LEN {
+		let mut words = load_block(s, i);
+		let keys = load_block(k, i);
+		let mut j = 0;
+		while j < BLOCK_WORDS {
+			words[j] = encode2_word(words[j], keys[j]);
+			j += 1;
+		}
+		store_block(&mut data, i, words);
+		i += BLOCK;
+	}
+	// Encode the remaining bytes
+	while i < LEN {
+		data[i] = encode2(s[i], k[i]);
+		i += 1;
+	}
+	return data;
+}
+
+/// Deobfuscates the input string obfuscated with [`obfuscate2`].
+#[inline(always)]
+pub fn deobfuscate2<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN]) -> [u8; LEN] {
+	let mut buf = [0u8; LEN];
+	deobfuscate2_into::<LEN>(s, k, &mut buf);
+	return buf;
+}
+
+/// Deobfuscates the string obfuscated with [`obfuscate2`] directly into the destination.
+///
+/// The destination may be uninitialized, returns the deobfuscated string.
+/// Panics if the destination is shorter than the string before anything is written.
+#[inline(always)]
+pub fn deobfuscate2_into<'a, const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], dst: &'a mut (impl Buffer<u8> + ?Sized)) -> &'a mut [u8] {
+	// Safety: only initialized values are written to the destination
+	let buf = unsafe { dst.uninit_prefix(LEN) };
+	let mut i = 0;
+	unsafe {
+		let src = s.as_ptr();
+		// Read in chunks of 8 bytes, the transform itself is bytewise
+		while i < LEN & !7 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u8; 8]);
+			let mut j = 0;
+			while j < 8 {
+				buf[i + j] = MaybeUninit::new(decode2(ct[j], k[i + j]));
+				j += 1;
+			}
+			i += 8;
+		}
+		// Process the remaining bytes
+		while i < LEN {
+			let ct = read_volatile(src.offset(i as isize));
+			buf[i] = MaybeUninit::new(decode2(ct, k[i]));
+			i += 1;
+		}
+		return assume_init(buf);
+	}
+}
+
+/// Compares the string obfuscated with [`obfuscate2`] with the other string.
+#[inline(always)]
+pub fn equals2<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], other: &[u8]) -> bool {
+	if other.len() != LEN {
+		return false;
+	}
+	let mut i = 0;
+	unsafe {
+		let src = s.as_ptr();
+		// Read in chunks of 8 bytes, the transform itself is bytewise
+		while i < LEN & !7 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u8; 8]);
+			let mut j = 0;
+			while j < 8 {
+				if decode2(ct[j], k[i + j]) != other[i + j] {
+					return false;
+				}
+				j += 1;
+			}
+			i += 8;
+		}
+		// Process the remaining bytes
+		while i < LEN {
+			let ct = read_volatile(src.offset(i as isize));
+			if decode2(ct, k[i]) != other[i] {
+				return false;
+			}
+			i += 1;
+		}
+	}
+	return true;
+}
+
+/// Compares two obfuscated strings without deobfuscating either.
+///
+/// Both keystreams are folded into the comparison, returns `false` if the lengths differ.
+#[inline(always)]
+pub fn equals_obf<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], other: &[u8], other_k: &[u8]) -> bool {
+	if other.len() != LEN || other_k.len() != LEN {
+		return false;
+	}
+	let mut i = 0;
+	unsafe {
+		let src = s.as_ptr();
+		let other_src = other.as_ptr();
+		// Process in chunks of 8 bytes on 64-bit targets
+		#[cfg(target_pointer_width = "64")]
+		while i < LEN & !7 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u8; 8]);
+			let other_ct = read_volatile(other_src.offset(i as isize) as *const [u8; 8]);
+			let tmp = u64::from_ne_bytes([ct[0], ct[1], ct[2], ct[3], ct[4], ct[5], ct[6], ct[7]]) ^
+				u64::from_ne_bytes([k[i + 0], k[i + 1], k[i + 2], k[i + 3], k[i + 4], k[i + 5], k[i + 6], k[i + 7]]) ^
+				u64::from_ne_bytes(chain::<8>(src, i));
+			let other = u64::from_ne_bytes([other_ct[0], other_ct[1], other_ct[2], other_ct[3], other_ct[4], other_ct[5], other_ct[6], other_ct[7]]) ^
+				u64::from_ne_bytes([other_k[i + 0], other_k[i + 1], other_k[i + 2], other_k[i + 3], other_k[i + 4], other_k[i + 5], other_k[i + 6], other_k[i + 7]]) ^
+				u64::from_ne_bytes(chain::<8>(other_src, i));
+			if tmp != other {
+				return false;
+			}
+			i += 8;
+		}
+		// Process in chunks of 4 bytes on 32-bit and 64-bit targets
+		#[cfg(not(target_pointer_width = "16"))]
+		while i < LEN & !3 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u8; 4]);
+			let other_ct = read_volatile(other_src.offset(i as isize) as *const [u8; 4]);
+			let tmp = u32::from_ne_bytes([ct[0], ct[1], ct[2], ct[3]]) ^
+				u32::from_ne_bytes([k[i + 0], k[i + 1], k[i + 2], k[i + 3]]) ^
+				u32::from_ne_bytes(chain::<4>(src, i));
+			let other = u32::from_ne_bytes([other_ct[0], other_ct[1], other_ct[2], other_ct[3]]) ^
+				u32::from_ne_bytes([other_k[i + 0], other_k[i + 1], other_k[i + 2], other_k[i + 3]]) ^
+				u32::from_ne_bytes(chain::<4>(other_src, i));
+			if tmp != other {
+				return false;
+			}
+			i += 4;
+		}
 		// Process the remaining bytes
-		match LEN % 4 {
-			1 => {
-				let ct = read_volatile(src.offset(i as isize));
-				write(dest.offset(i as isize), ct ^ k[i]);
-			},
-			2 => {
-				let ct = read_volatile(src.offset(i as isize) as *const [u8; 2]);
-				write(dest.offset(i as isize) as *mut [u8; 2], [
-					ct[0] ^ k[i + 0],
-					ct[1] ^ k[i + 1],
-				]);
-			},
-			3 => {
-				let ct = read_volatile(src.offset(i as isize) as *const [u8; 3]);
-				write(dest.offset(i as isize) as *mut [u8; 2], [
-					ct[0] ^ k[i + 0],
-					ct[1] ^ k[i + 1],
-				]);
-				write(dest.offset(i as isize + 2), ct[2] ^ k[i + 2]);
-			},
-			_ => (),
+		while i < LEN {
+			let ct = read_volatile(src.offset(i as isize));
+			let other_ct = read_volatile(other_src.offset(i as isize));
+			if ct ^ k[i] ^ chain::<1>(src, i)[0] != other_ct ^ other_k[i] ^ chain::<1>(other_src, i)[0] {
+				return false;
+			}
+			i += 1;
 		}
 	}
-	return buf;
+	return true;
 }
 
+/// Checks if the haystack starts with the obfuscated string.
+///
+/// Returns `false` if the haystack is shorter than the obfuscated string.
 #[inline(always)]
-pub fn equals<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], other: &[u8]) -> bool {
+pub fn starts_with<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], haystack: &[u8]) -> bool {
+	if haystack.len() < LEN {
+		return false;
+	}
+	equals::<LEN>(s, k, &haystack[..LEN])
+}
+
+/// Checks if the haystack ends with the obfuscated string.
+///
+/// Returns `false` if the haystack is shorter than the obfuscated string.
+#[inline(always)]
+pub fn ends_with<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], haystack: &[u8]) -> bool {
+	if haystack.len() < LEN {
+		return false;
+	}
+	equals::<LEN>(s, k, &haystack[haystack.len() - LEN..])
+}
+
+/// Checks if the haystack contains the obfuscated string.
+///
+/// Implements the same quicksearch as [`position`](crate::position) but decodes the needle one byte at a time inside the search loop.
+/// The needle is never deobfuscated as a whole, however the jump table derived from it lives on the stack for the duration of the search.
+#[inline(always)]
+pub fn contains<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], haystack: &[u8]) -> bool {
+	// Short-circuit empty needles
+	if LEN == 0 {
+		return true;
+	}
+	if LEN > haystack.len() {
+		return false;
+	}
+	let src = s.as_ptr();
+	let needle = |i: usize| unsafe { read_volatile(src.offset(i as isize)) ^ chain::<1>(src, i)[0] } ^ k[i];
+	// Initialize the jump table
+	let mut jumps = [if LEN > 255 { 255 } else { LEN as u8 }; 256];
+	let tail = LEN - 1;
+	let mut i = 0;
+	while i < tail {
+		jumps[needle(i) as usize] = if tail - i > 255 { 255 } else { (tail - i) as u8 };
+		i += 1;
+	}
+	// Find the needle
+	let sentinel = needle(tail);
+	let mut offset = 0;
+	while offset < haystack.len() - tail {
+		let chr = haystack[offset + tail];
+		if chr == sentinel {
+			let mut i = 0;
+			while i < tail && haystack[offset + i] == needle(i) {
+				i += 1;
+			}
+			if i == tail {
+				return true;
+			}
+		}
+		offset += jumps[chr as usize] as usize;
+	}
+	return false;
+}
+
+/// Compares the obfuscated string with the other string in constant time.
+///
+/// Unlike [`equals`] this does not return early on the first difference.
+/// All `LEN` bytes are processed and the differences accumulated in a mask which is only inspected at the end.
+///
+/// The length of `other` is checked up front and is not considered secret.
+/// The loop itself has no data-dependent branches, but no guarantees can be made about what the optimizer or the hardware do with it.
+/// This is sufficient to avoid leaking the length of the matching prefix through trivial timing measurements, it is not a cryptographic guarantee.
+#[inline(always)]
+pub fn ct_equals<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], other: &[u8]) -> bool {
 	if other.len() != LEN {
 		return false;
 	}
+	let mut diff = 0u64;
 	let mut i = 0;
-	// Try to tickle the LLVM optimizer in _just_ the right way
-	// Use `read_volatile` to avoid constant folding a specific read and optimize the rest
-	// Volatile reads of any size larger than 8 bytes appears to cause a bunch of one byte reads
-	// Hand optimize in chunks of 8 and 4 bytes to avoid this
+	// Same volatile-read structure as `equals` to avoid constant folding the keystream
 	unsafe {
 		let src = s.as_ptr();
 		// Process in chunks of 8 bytes on 64-bit targets
@@ -280,48 +3044,38 @@ pub fn equals<const LEN: usize>(s: &[u8; LEN], k: &[u8; LEN], other: &[u8]) -> b
 		while i < LEN & !7 {
 			let ct = read_volatile(src.offset(i as isize) as *const [u8; 8]);
 			let tmp = u64::from_ne_bytes([ct[0], ct[1], ct[2], ct[3], ct[4], ct[5], ct[6], ct[7]]) ^
-				u64::from_ne_bytes([k[i + 0], k[i + 1], k[i + 2], k[i + 3], k[i + 4], k[i + 5], k[i + 6], k[i + 7]]);
+				u64::from_ne_bytes([k[i + 0], k[i + 1], k[i + 2], k[i + 3], k[i + 4], k[i + 5], k[i + 6], k[i + 7]]) ^
+				u64::from_ne_bytes(chain::<8>(src, i));
 			let other = u64::from_ne_bytes([other[i + 0], other[i + 1], other[i + 2], other[i + 3], other[i + 4], other[i + 5], other[i + 6], other[i + 7]]);
-			if tmp != other {
-				return false;
-			}
+			diff |= tmp ^ other;
 			i += 8;
 		}
-		// Process in chunks of 4 bytes
+		// Process in chunks of 4 bytes on 32-bit and 64-bit targets
+		#[cfg(not(target_pointer_width = "16"))]
 		while i < LEN & !3 {
 			let ct = read_volatile(src.offset(i as isize) as *const [u8; 4]);
 			let tmp = u32::from_ne_bytes([ct[0], ct[1], ct[2], ct[3]]) ^
-				u32::from_ne_bytes([k[i + 0], k[i + 1], k[i + 2], k[i + 3]]);
+				u32::from_ne_bytes([k[i + 0], k[i + 1], k[i + 2], k[i + 3]]) ^
+				u32::from_ne_bytes(chain::<4>(src, i));
 			let other = u32::from_ne_bytes([other[i + 0], other[i + 1], other[i + 2], other[i + 3]]);
-			if tmp != other {
-				return false;
-			}
+			diff |= (tmp ^ other) as u64;
 			i += 4;
 		}
 		// Process the remaining bytes
-		match LEN % 4 {
-			1 => {
-				let ct = read_volatile(src.offset(i as isize));
-				ct ^ k[i] == other[i]
-			},
-			2 => {
-				let ct = read_volatile(src.offset(i as isize) as *const [u8; 2]);
-				u16::from_ne_bytes([ct[0], ct[1]]) ^ u16::from_ne_bytes([k[i + 0], k[i + 1]]) == u16::from_ne_bytes([other[i + 0], other[i + 1]])
-			},
-			3 => {
-				let ct = read_volatile(src.offset(i as isize) as *const [u8; 3]);
-				u32::from_ne_bytes([ct[0], ct[1], ct[2], 0]) ^ u32::from_ne_bytes([k[i + 0], k[i + 1], k[i + 2], 0]) == u32::from_ne_bytes([other[i + 0], other[i + 1], other[i + 2], 0])
-			},
-			_ => true,
+		while i < LEN {
+			let ct = read_volatile(src.offset(i as isize));
+			diff |= (ct ^ k[i] ^ chain::<1>(src, i)[0] ^ other[i]) as u64;
+			i += 1;
 		}
 	}
+	hint::black_box(diff) == 0
 }
 
 // Test correct processing of less than multiple of 8 lengths
 #[test]
 fn test_remaining_bytes() {
 	const STRING: &[u8] = b"01234567ABCDEFGHI";
-	fn test<const LEN: usize>(key: u32) {
+	fn test<const LEN: usize>(key: u64) {
 		let keys = keystream::<LEN>(key);
 		let data = obfuscate::<LEN>(&STRING[..LEN], &keys);
 		let buffer = deobfuscate::<LEN>(&data, &keys);
@@ -331,6 +3085,7 @@ fn test_remaining_bytes() {
 		assert_eq!(&buffer[..], &STRING[..LEN]);
 		// Specialized equals check should succeed
 		assert!(equals::<LEN>(&data, &keys, &STRING[..LEN]));
+		assert!(ct_equals::<LEN>(&data, &keys, &STRING[..LEN]));
 	}
 	test::<8>(0x1111);
 	test::<9>(0x2222);
@@ -343,6 +3098,622 @@ fn test_remaining_bytes() {
 	test::<16>(0x9999);
 }
 
+#[test]
+fn test_keystream64() {
+	const STRING: &[u8] = b"01234567ABCDEFGHIJKLMNOP";
+	fn test<const LEN: usize, const WORDS: usize>(key: u64) {
+		let keys = keystream::<LEN>(key);
+		let keys64 = keystream64::<WORDS>(key);
+		let data = obfuscate::<LEN>(&STRING[..LEN], &keys);
+		let data64 = obfuscate64::<LEN, WORDS>(&STRING[..LEN], &keys64);
+		// Both pipelines produce the same ciphertext
+		assert_eq!(data, data64.0);
+		assert_eq!(&deobfuscate64::<LEN, WORDS>(&data64, &keys64)[..], &STRING[..LEN]);
+	}
+	test::<0, 0>(0x1111);
+	test::<1, 1>(0x2222);
+	test::<7, 1>(0x3333);
+	test::<8, 1>(0x4444);
+	test::<9, 2>(0x5555);
+	test::<15, 2>(0x6666);
+	test::<16, 2>(0x7777);
+	test::<17, 3>(0x8888);
+	test::<24, 3>(0x9999);
+}
+
+#[test]
+fn test_generators() {
+	const STRING: &[u8] = b"01234567ABCDEFGHI";
+	const LEN: usize = STRING.len();
+	fn test<const GEN: u8>(key: u64) -> [u8; LEN] {
+		let keys = keystream_with::<LEN, GEN>(key);
+		let data = obfuscate::<LEN>(STRING, &keys);
+		assert_eq!(&deobfuscate::<LEN>(&data, &keys)[..], STRING);
+		assert!(equals::<LEN>(&data, &keys, STRING));
+		// The word key stream agrees with the byte key stream
+		let keys64 = keystream64_with::<3, GEN>(key);
+		assert_eq!(obfuscate64::<LEN, 3>(STRING, &keys64).0, data);
+		// The zero key does not get stuck
+		assert_ne!(keystream_with::<8, GEN>(0), [0u8; 8]);
+		return keys;
+	}
+	let streams = [test::<0>(0x1234), test::<1>(0x1234), test::<2>(0x1234), test::<3>(0x1234)];
+	for i in 0..streams.len() {
+		for j in i + 1..streams.len() {
+			assert_ne!(streams[i], streams[j]);
+		}
+	}
+	assert_eq!(keystream_with::<LEN, 0>(0x1234), keystream::<LEN>(0x1234));
+}
+
+#[test]
+fn test_keystream_key_width() {
+	// Keys differing only in the upper 32 bits produce different key streams
+	assert_ne!(keystream::<16>(0x0000_0001_1234_5678), keystream::<16>(0x0000_0002_1234_5678));
+	assert_ne!(keystream64::<2>(0x0000_0001_1234_5678), keystream64::<2>(0x0000_0002_1234_5678));
+	// A zero key does not produce a zero key stream
+	assert_ne!(keystream::<8>(0), [0u8; 8]);
+	// The deprecated key stream is unchanged
+	#[allow(deprecated)]
+	let keys = keystream_u32::<5>(0x10203040);
+	let round = next_round(0x10203040).to_le_bytes();
+	assert_eq!(&keys[..4], &round);
+}
+
+#[test]
+fn test_keystream_mixed() {
+	const STRING: &[u8] = b"01234567ABCDEFGHI";
+	fn test<const LEN: usize>(key: u64, rounds: u32) {
+		let keys = keystream_mixed::<LEN>(key, rounds);
+		let data = obfuscate::<LEN>(&STRING[..LEN], &keys);
+		let buffer = deobfuscate::<LEN>(&data, &keys);
+		assert_ne!(&data[..], &STRING[..LEN]);
+		assert_eq!(&buffer[..], &STRING[..LEN]);
+		assert!(equals::<LEN>(&data, &keys, &STRING[..LEN]));
+	}
+	test::<1>(0x1111, 1);
+	test::<7>(0x2222, 2);
+	test::<8>(0x3333, 3);
+	test::<9>(0x4444, 4);
+	test::<17>(0x5555, 2);
+
+	// Different round counts produce different key streams
+	assert_ne!(keystream_mixed::<16>(0x1234, 2), keystream_mixed::<16>(0x1234, 3));
+
+	// No repeated 4-byte windows in a 4 KiB key stream
+	const KEYS: [u8; 4096] = keystream_mixed::<4096>(0x0123456789abcdef, 2);
+	let mut windows: Vec<[u8; 4]> = KEYS.windows(4).map(|w| [w[0], w[1], w[2], w[3]]).collect();
+	windows.sort_unstable();
+	let len = windows.len();
+	windows.dedup();
+	assert_eq!(windows.len(), len);
+}
+
+#[test]
+fn test_chaining() {
+	const STRING: &[u8; 20] = b"aaaaaaaaaaaaaaaaaaaa";
+	const LEN: usize = STRING.len();
+	// Even with a weak key stream identical blocks encrypt differently
+	let keys = [0x5a; LEN];
+	let data = obfuscate::<LEN>(STRING, &keys);
+	assert_ne!(data[0..8], data[8..16]);
+	assert_eq!(&deobfuscate::<LEN>(&data, &keys), STRING);
+	assert!(equals::<LEN>(&data, &keys, STRING));
+	assert!(!equals::<LEN>(&data, &keys, b"aaaaaaaaaaaaaaaaaaab"));
+	assert!(ct_equals::<LEN>(&data, &keys, STRING));
+	assert!(contains::<LEN>(&data, &keys, b"xxaaaaaaaaaaaaaaaaaaaaxx"));
+	assert!(equals_obf::<LEN>(&data, &keys, &data, &keys));
+	let keys = keystream::<LEN>(0x1234);
+	let data = obfuscate::<LEN>(STRING, &keys);
+	assert_eq!(&deobfuscate::<LEN>(&data, &keys), STRING);
+	let keys64 = keystream64::<3>(0x1234);
+	let data64 = obfuscate64::<LEN, 3>(STRING, &keys64);
+	assert_eq!(&deobfuscate64::<LEN, 3>(&data64, &keys64), STRING);
+	// Changing one ciphertext byte also garbles the byte in the next block
+	let mut tampered = data;
+	tampered[3] ^= 1;
+	let buf = deobfuscate::<LEN>(&tampered, &keys);
+	assert_ne!(buf[3], b'a');
+	assert_ne!(buf[11], b'a');
+}
+
+#[test]
+fn test_deobfuscate_keyed() {
+	const STRING: &[u8] = b"01234567ABCDEFGHI";
+	const LEN: usize = STRING.len();
+	let keys = keystream::<LEN>(0x1234);
+	let data = obfuscate::<LEN>(STRING, &fold_key::<LEN>(&keys, 0xfeedface));
+	assert_eq!(&deobfuscate_keyed::<LEN>(&data, &keys, 0xfeedface)[..], STRING);
+	assert_ne!(&deobfuscate_keyed::<LEN>(&data, &keys, 0xfeedfacf)[..], STRING);
+	// The compiletime key stream alone is not enough
+	assert_ne!(&deobfuscate::<LEN>(&data, &keys)[..], STRING);
+
+	let rk = crate::RUNTIME_KEY;
+	assert_eq!(crate::obfbytes!(rk => b"keyed bytes"), b"keyed bytes");
+	assert_ne!(crate::obfbytes!(rk ^ 0x8000 => b"keyed bytes"), b"keyed bytes");
+	assert_eq!(crate::obfstr!(rk => "keyed"), Ok("keyed"));
+}
+
+#[test]
+fn test_deobfuscate_selfkeyed() {
+	const STRING: &[u8] = b"01234567ABCDEFGHI";
+	const LEN: usize = STRING.len();
+	const KEYS: [u8; LEN] = keystream::<LEN>(0x1234);
+	const BIAS: usize = 0x5a5a5;
+	static SDATA: [u8; LEN] = obfuscate::<LEN>(STRING, &fold_key::<LEN>(&KEYS, selfkey(BIAS)));
+	static ANCHOR: Anchor = Anchor(SDATA.as_ptr().wrapping_add(BIAS));
+	assert_eq!(&deobfuscate_selfkeyed::<LEN>(&SDATA, &ANCHOR, &KEYS)[..], STRING);
+	// Decoding a copy of the ciphertext produces garbage
+	let copy = SDATA;
+	assert_ne!(&deobfuscate_selfkeyed::<LEN>(&copy, &ANCHOR, &KEYS)[..], STRING);
+	// The anchor is not the address of the data
+	assert_eq!(anchor_delta(&ANCHOR, SDATA.as_ptr()), BIAS);
+
+	assert_eq!(crate::obfbytes!(#[selfkey] b"selfkeyed bytes"), b"selfkeyed bytes");
+	assert_eq!(crate::obfstr!(#[selfkey] "selfkeyed"), "selfkeyed");
+}
+
+#[test]
+fn test_masked() {
+	const STRING: &[u8] = b"01234567ABCDEFGHI";
+	const LEN: usize = STRING.len();
+	const MASK: u64 = 0x0123_4567_89ab_cdef;
+	assert_eq!(unmask(mask_parts(MASK)), MASK);
+	// The masked key stream never equals the raw key stream
+	let keys = keystream::<LEN>(0x1234);
+	let masked = fold_key::<LEN>(&keys, MASK);
+	assert_ne!(masked, keys);
+	let keys64 = keystream64::<3>(0x1234);
+	let masked64 = mask64::<3>(&keys64, MASK);
+	assert!(keys64.iter().zip(masked64.iter()).all(|(a, b)| a != b));
+	// Round trip through the masked code paths
+	let data = obfuscate::<LEN>(STRING, &keys);
+	assert!(equals_masked::<LEN>(&data, &masked, mask_parts(MASK), STRING));
+	assert!(!equals_masked::<LEN>(&data, &masked, mask_parts(MASK), b"01234567ABCDEFGHJ"));
+	assert!(!equals_masked::<LEN>(&data, &keys, mask_parts(MASK), STRING));
+	let data64 = obfuscate64::<LEN, 3>(STRING, &keys64);
+	assert_eq!(&deobfuscate64_masked::<LEN, 3>(&data64, &masked64, mask_parts(MASK))[..], STRING);
+	if MASKED {
+		assert_eq!(unmask_keys::<LEN>(&masked, mask_parts(MASK)), keys);
+	}
+}
+
+#[test]
+fn test_pick_key() {
+	assert!(has_printable_run(b"\x01abcd\x02", 4));
+	assert!(!has_printable_run(b"\x01abc\x02def\xff", 4));
+	assert!(has_printable_run(b"a\tb c", 5));
+
+	// Construct a plaintext which encrypts to printable ASCII under the initial key
+	const LEN: usize = 16;
+	const KEY: u64 = 0x1234;
+	let keys = keys64_with::<2, 0>(KEY);
+	let string = deobfuscate64::<LEN, 2>(&Aligned(*b"AAAAAAAAAAAAAAAA"), &keys);
+	let key = pick_key::<LEN, 2, 0>(&string, KEY, false);
+	assert_ne!(key, KEY);
+	let data = obfuscate64::<LEN, 2>(&string, &keys64_with::<2, 0>(key));
+	assert!(!has_printable_run(&data.0, PRINTABLE_RUN));
+	assert_eq!(&deobfuscate64::<LEN, 2>(&data, &keys64_with::<2, 0>(key)), &string);
+
+	// Only the prefix of long strings is scanned
+	const LONG: usize = 2048;
+	let mut ciphertext = [0x01u8; LONG];
+	ciphertext[1500..1508].copy_from_slice(b"AAAAAAAA");
+	let long = deobfuscate64::<LONG, 256>(&Aligned(ciphertext), &keys64_with::<256, 0>(KEY));
+	assert_eq!(pick_key::<LONG, 256, 0>(&long, KEY, false), KEY);
+	ciphertext[100..108].copy_from_slice(b"AAAAAAAA");
+	let long = deobfuscate64::<LONG, 256>(&Aligned(ciphertext), &keys64_with::<256, 0>(KEY));
+	assert_ne!(pick_key::<LONG, 256, 0>(&long, KEY, false), KEY);
+}
+
+#[test]
+fn test_padded() {
+	const STRING: &[u8] = b"01234567ABCDEFGH";
+	const LEN: usize = STRING.len();
+	const PADDED: usize = padded_len(LEN, 0x1234);
+	const KEYS: [u8; PADDED] = keystream::<PADDED>(0x5678);
+	const LEN_KEY: u64 = 0xabcdef;
+	static SDATA: Padded<[u8; PADDED]> = Padded {
+		data: obfuscate::<PADDED>(&pad::<PADDED>(STRING, 0x9abc), &KEYS),
+		len: LEN as u64 ^ LEN_KEY,
+	};
+	assert_ne!(core::mem::size_of_val(&SDATA.data), LEN);
+	assert_eq!(&*deobfuscate_padded::<PADDED>(&SDATA, &KEYS, LEN_KEY), STRING);
+	assert!(equals_padded::<PADDED>(&SDATA, &KEYS, LEN_KEY, STRING));
+	assert!(!equals_padded::<PADDED>(&SDATA, &KEYS, LEN_KEY, b"01234567ABCDEFG"));
+	assert!(!equals_padded::<PADDED>(&SDATA, &KEYS, LEN_KEY, b"01234567ABCDEFGI"));
+	assert!(!equals_padded::<PADDED>(&SDATA, &KEYS, LEN_KEY, &pad::<PADDED>(STRING, 0x9abc)));
+
+	assert_eq!(crate::obfbytes!(#[padded] b"padded bytes"), b"padded bytes");
+	assert_eq!(crate::obfstr!(#[padded] "padded"), "padded");
+	assert_eq!(crate::obfstr!(#[padded] ""), "");
+
+	let mut buf = [0xffu8; 32];
+	assert_eq!(deobfuscate_padded_into::<PADDED>(&SDATA, &KEYS, LEN_KEY, &mut buf[..]), STRING);
+	assert_eq!(buf[LEN], 0xff);
+	let mut buf = [MaybeUninit::<u8>::uninit(); 20];
+	assert_eq!(crate::obfbytes!(#[padded] buf <- b"padded bytes"), b"padded bytes");
+	crate::obfstr! { #[padded] let a = "abc"; let b = ""; }
+	assert_eq!((a, b), ("abc", ""));
+	assert!(crate::obfeq!(#[padded] "padded", "padded"));
+	assert!(!crate::obfeq!(#[padded] "padde", "padded"));
+	assert!(!crate::obfeq!(#[padded] "paddeD", "padded"));
+	assert!(crate::obfeq_bytes!(#[padded] b"\x00\xff", b"\x00\xff"));
+}
+
+#[test]
+fn test_deobfuscate_into() {
+	const STRING: &[u8] = b"0123456789ABCDEFG";
+	fn test<const N: usize>() {
+		let keys = keystream::<N>(0x1234);
+		let data = obfuscate::<N>(&STRING[..N], &keys);
+		let mut buf = [0xffu8; 20];
+		deobfuscate_into::<N>(&data, &keys, &mut buf);
+		assert_eq!(&buf[..N], &deobfuscate::<N>(&data, &keys));
+		assert!(buf[N..].iter().all(|&b| b == 0xff));
+		let data = obfuscate2::<N>(&STRING[..N], &keys);
+		deobfuscate2_into::<N>(&data, &keys, &mut buf);
+		assert_eq!(&buf[..N], &deobfuscate2::<N>(&data, &keys));
+
+		let mut uninit = [MaybeUninit::<u8>::uninit(); 20];
+		assert_eq!(deobfuscate2_into::<N>(&data, &keys, &mut uninit), &deobfuscate2::<N>(&data, &keys));
+		let data = obfuscate::<N>(&STRING[..N], &keys);
+		assert_eq!(deobfuscate_into::<N>(&data, &keys, &mut uninit[..]), &STRING[..N]);
+		let keys: &'static [u8; N] = &const { keystream::<N>(0x1234) };
+		assert_eq!(deobfuscate_small_into::<N>(&data, keys, mask_parts(0), &mut uninit), &STRING[..N]);
+	}
+	test::<1>(); test::<2>(); test::<3>(); test::<4>(); test::<5>(); test::<6>();
+	test::<7>(); test::<8>(); test::<9>(); test::<10>(); test::<11>(); test::<12>();
+	test::<13>(); test::<14>(); test::<15>(); test::<16>(); test::<17>();
+
+	let mut buf = [0u8; 17];
+	assert_eq!(crate::obfbytes!(buf <- b"0123456789ABCDEFG"), STRING);
+	assert_eq!(crate::obfstr!(buf <- "short"), "short");
+
+	let mut buf = [MaybeUninit::<u8>::uninit(); 17];
+	assert_eq!(crate::obfbytes!(buf <- b"0123456789ABCDEFG"), STRING);
+	assert_eq!(crate::obfstr!(buf <- "short"), "short");
+	assert_eq!(crate::obfcstr!(buf <- c"cstr").to_bytes(), b"cstr");
+	let buf = &mut buf[..];
+	assert_eq!(crate::obfstr!(buf <- "slice"), "slice");
+}
+
+#[test]
+#[should_panic]
+fn test_deobfuscate_into_small() {
+	let mut buf = [0u8; 4];
+	let _ = crate::obfstr!(buf <- "too long");
+}
+
+#[cfg(panic = "unwind")]
+#[test]
+fn test_deobfuscate_into_small_untouched() {
+	// The destination is checked before anything is written
+	let mut buf = [0xffu8; 7];
+	let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+		let _ = crate::obfstr!(buf <- "too long");
+	}));
+	assert!(result.is_err());
+	assert_eq!(buf, [0xff; 7]);
+
+	let mut buf = [MaybeUninit::<u8>::uninit(); 7];
+	let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+		let _ = crate::obfstr!(buf <- "too long");
+	}));
+	assert!(result.is_err());
+}
+
+#[test]
+fn test_simd() {
+	const STRING: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdef";
+	fn test<const N: usize>() {
+		let keys = keystream::<N>(N as u64);
+		let data = obfuscate::<N>(&STRING[..N], &keys);
+		assert_eq!(&deobfuscate::<N>(&data, &keys), &STRING[..N]);
+		assert!(equals::<N>(&data, &keys, &STRING[..N]));
+		for j in 0..N {
+			let mut other = [0u8; N];
+			other.copy_from_slice(&STRING[..N]);
+			other[j] ^= 1;
+			assert!(!equals::<N>(&data, &keys, &other));
+		}
+	}
+	macro_rules! lengths {
+		($($n:literal)*) => { $(test::<$n>();)* };
+	}
+	lengths!(1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50
+		51 52 53 54 55 56 57 58 59 60 61 62 63 64 65 66 67 68 69 70 71 72 73 74 75 76 77 78 79 80 81 82 83 84 85 86 87 88 89 90 91 92 93 94 95 96 97 98 99 100);
+}
+
+#[test]
+fn test_deobfuscate_to_writer() {
+	const STRING: &str = "Ünïcödé strings €€€ with multi-byte characters 🌍🌍🌍 straddle the chunk boundaries ✓✓✓ \
+		in several places, ☃ snowmen and 🦀 crabs included, to make sure no character is split between writes ñññ.";
+	const LEN: usize = STRING.len();
+	const KEYS: [u8; LEN] = keystream::<LEN>(0x1234);
+	const DATA: [u8; LEN] = obfuscate::<LEN>(STRING.as_bytes(), &KEYS);
+
+	struct Chunks(String, usize);
+	impl fmt::Write for Chunks {
+		fn write_str(&mut self, s: &str) -> fmt::Result {
+			assert!(s.len() <= WRITER_CHUNK + 3);
+			self.1 += 1;
+			self.0.push_str(s);
+			Ok(())
+		}
+	}
+	let mut w = Chunks(String::new(), 0);
+	assert_eq!(deobfuscate_to_writer::<LEN>(&DATA, &KEYS, &mut w), Ok(()));
+	assert_eq!(w.0, STRING);
+	assert!(w.1 > LEN / WRITER_CHUNK);
+
+	// Invalid UTF-8 is an error
+	const INVALID: [u8; 3] = obfuscate::<3>(b"a\xffb", &[1, 2, 3]);
+	assert!(deobfuscate_to_writer::<3>(&INVALID, &[1, 2, 3], &mut String::new()).is_err());
+	const TRUNCATED: [u8; 2] = obfuscate::<2>(b"a\xe2", &[1, 2]);
+	assert!(deobfuscate_to_writer::<2>(&TRUNCATED, &[1, 2], &mut String::new()).is_err());
+
+	let mut s = String::new();
+	crate::obfwrite_str!(&mut s, STRING).unwrap();
+	assert_eq!(s, STRING);
+}
+
+#[test]
+fn test_small() {
+	const STRING: &[u8] = b"0123456789ABCDEFGHIJK";
+	static KEYS: [u8; 21] = keystream::<21>(0x1234);
+	static MASKED_KEYS: [u8; 21] = fold_key::<21>(&KEYS, 0x0123456789abcdef);
+	let data = obfuscate::<21>(STRING, &KEYS);
+	let mut buf = [0u8; 21];
+	unsafe { deobfuscate_dyn(data.as_ptr(), KEYS.as_ptr(), buf.as_mut_ptr(), 21) };
+	assert_eq!(&buf, STRING);
+	assert!(unsafe { equals_dyn(data.as_ptr(), KEYS.as_ptr(), 0, 21, STRING) });
+	assert!(unsafe { equals_dyn(data.as_ptr(), MASKED_KEYS.as_ptr(), 0x0123456789abcdef, 21, STRING) });
+	assert!(!unsafe { equals_dyn(data.as_ptr(), KEYS.as_ptr(), 0, 21, b"0123456789ABCDEFGHIJ") });
+	assert!(!unsafe { equals_dyn(data.as_ptr(), KEYS.as_ptr(), 0, 21, b"0123456789ABCDEFGHIJL") });
+	let mut buf = [0u8; 21];
+	unmask_dyn(&mut buf, 0x0123456789abcdef);
+	assert_eq!(buf, fold_key::<21>(&[0; 21], 0x0123456789abcdef));
+}
+
+#[cfg(target_has_atomic = "8")]
+#[test]
+fn test_in_place() {
+	const STRING: &[u8] = b"0123456789ABCDEFGHIJK";
+	const KEYS: [u8; 21] = keystream::<21>(0x1234);
+	const DATA: [u8; 21] = obfuscate::<21>(STRING, &KEYS);
+	static SDATA: InPlace<21> = InPlace::new(DATA);
+	{
+		let guard = SDATA.lock(KEYS);
+		assert_eq!(&*guard, STRING);
+	}
+	assert_eq!(unsafe { *SDATA.data.get() }, DATA);
+	assert_eq!(&*SDATA.lock(KEYS), STRING);
+	assert_eq!(unsafe { *SDATA.data.get() }, DATA);
+
+	#[cfg(panic = "unwind")]
+	{
+		let guard = SDATA.lock(KEYS);
+		assert!(std::panic::catch_unwind(|| { let _ = SDATA.lock(KEYS); }).is_err());
+		drop(guard);
+		assert_eq!(unsafe { *SDATA.data.get() }, DATA);
+	}
+
+	for _ in 0..2 {
+		assert_eq!(&*crate::obfbytes!(#[in_place] b"in place"), b"in place");
+	}
+}
+
+#[cfg(feature = "std")]
+#[test]
+#[cfg_attr(target_family = "wasm", ignore = "threads are not supported")]
+fn test_cached() {
+	use std::sync::Barrier;
+	const QUERY: &str = "SELECT name FROM users WHERE id = ?";
+	fn check(s: &str) -> (bool, usize) {
+		(s == QUERY, s.as_ptr() as usize)
+	}
+	fn query() -> (bool, usize) {
+		check(crate::obfstr!(#[cached] QUERY))
+	}
+	static BARRIER: Barrier = Barrier::new(4);
+	let threads: Vec<_> = (0..4).map(|_| std::thread::spawn(|| {
+		let (ok, ptr) = query();
+		assert!(ok);
+		// Later uses on the same thread hit the same slot
+		for _ in 0..3 {
+			assert_eq!(query(), (true, ptr));
+		}
+		// Keep all threads alive so their slots are distinct
+		BARRIER.wait();
+		ptr
+	})).collect();
+	let mut ptrs: Vec<usize> = threads.into_iter().map(|thread| thread.join().unwrap()).collect();
+	ptrs.sort();
+	ptrs.dedup();
+	assert_eq!(ptrs.len(), 4);
+
+	assert_eq!(&*crate::obfbytes!(#[cached] b""), b"");
+}
+
+#[test]
+fn test_obfuscate_slice() {
+	const STRING: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+	fn test<const N: usize>() {
+		let mut data = [0u8; N];
+		data.copy_from_slice(&STRING[..N]);
+		obfuscate_slice(&mut data, 0x1234);
+		assert_eq!(data, obfuscate::<N>(&STRING[..N], &keystream::<N>(0x1234)));
+		assert!(equals_slice(&data, 0x1234, &STRING[..N]));
+		assert_eq!(equals_slice(&data, 0x1235, &STRING[..N]), N == 0);
+		assert!(!equals_slice(&data, 0x1234, &STRING[..N + 1]));
+		rekey_slice(&mut data, 0x1234, 0x5678);
+		assert_eq!(data, obfuscate::<N>(&STRING[..N], &keystream::<N>(0x5678)));
+		rekey_slice(&mut data, 0x5678, 0x1234);
+		deobfuscate_slice(&mut data, 0x1234);
+		assert_eq!(&data[..], &STRING[..N]);
+	}
+	test::<0>();
+	test::<1>();
+	test::<7>();
+	test::<8>();
+	test::<13>();
+	test::<16>();
+	test::<35>();
+}
+
+#[test]
+fn test_rekeyed_secret() {
+	const SECRET: &[u8; 35] = b"correct horse battery staple 123456";
+	let mut plaintext = *SECRET;
+	let mut secret = RekeyedSecret::new(&mut plaintext, 0x1234);
+	assert_eq!(plaintext, [0; 35]);
+	assert_ne!(&secret.data, SECRET);
+	let mut seen = vec![secret.data];
+	let mut key = 0x1234;
+	for _ in 0..8 {
+		key = crate::splitmix(key);
+		secret.rekey(key);
+		assert!(secret.with(|s| s == SECRET));
+		assert!(secret == SECRET[..]);
+		// The ciphertext differs after every rotation
+		assert_ne!(&secret.data, SECRET);
+		assert!(!seen.contains(&secret.data));
+		seen.push(secret.data);
+	}
+	assert_eq!(format!("{:?}", secret), "RekeyedSecret { len: 35 }");
+}
+
+#[test]
+fn test_obfuscate_padded() {
+	const KEYS: [u8; 16] = keystream::<16>(0x1234);
+	const EXACT: [u8; 16] = obfuscate_padded::<16>(b"0123456789ABCDEF", &KEYS, 0);
+	const SHORT: [u8; 16] = obfuscate_padded::<16>(b"short", &KEYS, b'.');
+	assert_eq!(EXACT, obfuscate::<16>(b"0123456789ABCDEF", &KEYS));
+	assert_eq!(&deobfuscate::<16>(&EXACT, &KEYS), b"0123456789ABCDEF");
+	assert_eq!(&deobfuscate::<16>(&SHORT, &KEYS), b"short...........");
+	assert!(equals_prefix::<16>(&EXACT, &KEYS, 16, b"0123456789ABCDEF"));
+	assert!(equals_prefix::<16>(&SHORT, &KEYS, 5, b"short"));
+	assert!(!equals_prefix::<16>(&SHORT, &KEYS, 5, b"shore"));
+	assert!(!equals_prefix::<16>(&SHORT, &KEYS, 6, b"short"));
+	assert!(!equals_prefix::<16>(&SHORT, &KEYS, 17, b"short............"));
+
+	let slot = crate::obfbytes!(pad 8, b"8 bytes!");
+	assert_eq!(slot.as_bytes(), b"8 bytes!");
+	let slot = crate::obfbytes!(pad 64, b"short");
+	assert_eq!(crate::bytes::Slot::<64, 5>::LEN, 5);
+	assert_eq!(slot.as_bytes(), b"short");
+	assert_eq!(&slot[5..], &[0; 59]);
+	assert!(crate::obfeq_bytes!(pad 64, b"short", b"short"));
+	assert!(!crate::obfeq_bytes!(pad 64, b"short\0", b"short"));
+	assert!(!crate::obfeq_bytes!(pad 64, b"shore", b"short"));
+	assert!(crate::obfeq!(pad 8, "8 bytes!", "8 bytes!"));
+}
+
+#[test]
+fn test_split() {
+	const STRING: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+	fn test<const LEN: usize, const A: usize, const B: usize, const C: usize, const D: usize>() {
+		let keys = keystream::<LEN>(0x1234);
+		let data = obfuscate::<LEN>(&STRING[..LEN], &keys);
+		let a = piece::<A>(&data, 0);
+		let b = piece::<B>(&data, A);
+		let c = piece::<C>(&data, A + B);
+		let d = piece::<D>(&data, A + B + C);
+		assert_eq!(&deobfuscate_split::<LEN, A, B, C, D>(&a, &b, &c, &d, &keys)[..], &STRING[..LEN]);
+		assert!(equals_split::<LEN, A, B, C, D>(&a, &b, &c, &d, &keys, &STRING[..LEN]));
+		assert!(!equals_split::<LEN, A, B, C, D>(&a, &b, &c, &d, &keys, &STRING[1..LEN + 1]));
+	}
+	test::<24, 7, 8, 9, 0>();
+	test::<24, 9, 8, 7, 0>();
+	test::<32, 8, 8, 8, 8>();
+	test::<36, 9, 9, 9, 9>();
+	test::<21, 7, 7, 7, 0>();
+	test::<16, 0, 7, 9, 0>();
+
+	let mut seed = 0x1234;
+	for len in 0..40 {
+		seed = crate::splitmix(seed);
+		let lens = split_lens(len, seed);
+		assert_eq!(lens.iter().sum::<usize>(), len);
+	}
+
+	assert_eq!(crate::obfbytes!(#[split] b"split bytes"), b"split bytes");
+	assert_eq!(crate::obfstr!(#[split] "split across several statics"), "split across several statics");
+	assert_eq!(crate::obfstr!(#[split] ""), "");
+	assert!(crate::obfeq!(#[split] "split across several statics", "split across several statics"));
+	assert!(!crate::obfeq!(#[split] "split across several statics", "split across several static"));
+	assert!(crate::obfeq_bytes!(#[split] b"", b""));
+}
+
+#[test]
+fn test_immediate() {
+	const STRING: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+	fn test<const LEN: usize, const WORDS: usize>() {
+		let keys = keystream64::<WORDS>(0x1234);
+		let words = immediate_words::<WORDS>(&STRING[..LEN], &keys);
+		assert_eq!(&deobfuscate_immediate::<LEN, WORDS, 0>(words, keys, &[], &[])[..], &STRING[..LEN]);
+	}
+	test::<1, 1>();
+	test::<7, 1>();
+	test::<8, 1>();
+	test::<9, 2>();
+	test::<32, 4>();
+
+	assert_eq!(crate::obfbytes!(#[immediate] b"immediate bytes"), b"immediate bytes");
+	assert_eq!(crate::obfstr!(#[immediate] ""), "");
+	assert_eq!(crate::obfstr!(#[immediate] "0123456789ABCDEFGHIJKLMNOPQRSTUV"), "0123456789ABCDEFGHIJKLMNOPQRSTUV");
+	// Falls back to static data above the threshold
+	assert_eq!(crate::obfstr!(#[immediate] "0123456789ABCDEFGHIJKLMNOPQRSTUVW"), "0123456789ABCDEFGHIJKLMNOPQRSTUVW");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_link_section() {
+	// The linker defines the start and stop symbols of sections named as C identifiers
+	extern "C" {
+		static __start_obfstr_test_data: u8;
+		static __stop_obfstr_test_data: u8;
+		static __start_obfstr_test_keys: u8;
+		static __stop_obfstr_test_keys: u8;
+	}
+	assert_eq!(crate::obfstr!(#[link_section = "obfstr_test_data"] "data"), "data");
+	assert_eq!(crate::obfstr!(#[link_section(data = "obfstr_test_data", keys = "obfstr_test_keys")] "keys"), "keys");
+	let data = (&raw const __stop_obfstr_test_data as usize) - (&raw const __start_obfstr_test_data as usize);
+	let keys = (&raw const __stop_obfstr_test_keys as usize) - (&raw const __start_obfstr_test_keys as usize);
+	assert!(data >= 8);
+	assert!(keys >= 4);
+}
+
+#[test]
+fn test_cipher2() {
+	fn test<const LEN: usize>(s: &[u8; LEN], key: u64) {
+		let keys = keystream::<LEN>(key);
+		let data = obfuscate2::<LEN>(s, &keys);
+		assert_eq!(&deobfuscate2::<LEN>(&data, &keys), s);
+		assert!(equals2::<LEN>(&data, &keys, s));
+		assert!(!equals2::<LEN>(&data, &keys, &s[..LEN - 1]));
+		let mut other = *s;
+		other[LEN - 1] ^= 1;
+		assert!(!equals2::<LEN>(&data, &keys, &other));
+	}
+	test(b"a", 0x1234);
+	test(b"Hello world", 0x5678);
+	test(b"The quick brown fox jumps over the lazy dog", 0x9abc);
+	// Every byte value round trips under every key byte
+	let mut k = 0u8;
+	loop {
+		let mut p = 0u8;
+		loop {
+			assert_eq!(decode2(encode2(p, k), k), p);
+			if p == 255 { break; }
+			p += 1;
+		}
+		if k == 255 { break; }
+		k += 1;
+	}
+}
+
 #[test]
 fn test_equals() {
 	const STRING: &str = "Hello ðŸŒ";
@@ -352,6 +3723,119 @@ fn test_equals() {
 	assert!(equals::<LEN>(&OBFSTRING, &KEYSTREAM, STRING.as_bytes()));
 }
 
+#[test]
+fn test_ct_equals() {
+	const STRING: &[u8] = b"0123-4567-89AB-CDEF";
+	const LEN: usize = STRING.len();
+	const KEYSTREAM: [u8; LEN] = keystream::<LEN>(0x10203040);
+	const OBFSTRING: [u8; LEN] = obfuscate::<LEN>(STRING, &KEYSTREAM);
+	assert!(ct_equals::<LEN>(&OBFSTRING, &KEYSTREAM, b"0123-4567-89AB-CDEF"));
+	assert!(!ct_equals::<LEN>(&OBFSTRING, &KEYSTREAM, b"X123-4567-89AB-CDEF"));
+	assert!(!ct_equals::<LEN>(&OBFSTRING, &KEYSTREAM, b"0123-4567-89AB-CDEX"));
+	assert!(!ct_equals::<LEN>(&OBFSTRING, &KEYSTREAM, b"0123-4567-89AB-CDE"));
+
+	assert!(obfeq_ct!("0123-4567-89AB-CDEF", "0123-4567-89AB-CDEF"));
+	assert!(!obfeq_ct!("X123-4567-89AB-CDEF", "0123-4567-89AB-CDEF"));
+	assert!(!obfeq_ct!("0123-4567-89AB-CDEX", "0123-4567-89AB-CDEF"));
+}
+
+#[test]
+fn test_equals_obf() {
+	const STRING: &[u8] = b"Duplicated literal";
+	const OTHER: &[u8] = b"Duplicated literaL";
+	const LEN: usize = STRING.len();
+	const KEYS1: [u8; LEN] = keystream::<LEN>(0x10203040);
+	const KEYS2: [u8; LEN] = keystream::<LEN>(0x50607080);
+	const DATA1: [u8; LEN] = obfuscate::<LEN>(STRING, &KEYS1);
+	const DATA2: [u8; LEN] = obfuscate::<LEN>(STRING, &KEYS2);
+	const DATA3: [u8; LEN] = obfuscate::<LEN>(OTHER, &KEYS1);
+	// Same plaintext with different keys
+	assert_ne!(DATA1, DATA2);
+	assert!(equals_obf::<LEN>(&DATA1, &KEYS1, &DATA2, &KEYS2));
+	// Different plaintext of the same length
+	assert!(!equals_obf::<LEN>(&DATA1, &KEYS1, &DATA3, &KEYS1));
+	assert!(!equals_obf::<LEN>(&DATA2, &KEYS2, &DATA3, &KEYS1));
+	// Different lengths
+	assert!(!equals_obf::<LEN>(&DATA1, &KEYS1, &DATA2[1..], &KEYS2[1..]));
+
+	assert!(obfeq_obf!("abc", "abc"));
+	assert!(!obfeq_obf!("abc", "abcd"));
+	assert!(!obfeq_obf!("abc", "abd"));
+}
+
+#[test]
+fn test_starts_ends_with() {
+	const STRING: &[u8] = b"/api/v1/";
+	const LEN: usize = STRING.len();
+	const KEYSTREAM: [u8; LEN] = keystream::<LEN>(0x10203040);
+	const OBFSTRING: [u8; LEN] = obfuscate::<LEN>(STRING, &KEYSTREAM);
+	// Haystack shorter than the needle
+	assert!(!starts_with::<LEN>(&OBFSTRING, &KEYSTREAM, b"/api/v1"));
+	assert!(!ends_with::<LEN>(&OBFSTRING, &KEYSTREAM, b"/api/v1"));
+	// Haystack equal to the needle
+	assert!(starts_with::<LEN>(&OBFSTRING, &KEYSTREAM, b"/api/v1/"));
+	assert!(ends_with::<LEN>(&OBFSTRING, &KEYSTREAM, b"/api/v1/"));
+	// Haystack longer than the needle
+	assert!(starts_with::<LEN>(&OBFSTRING, &KEYSTREAM, b"/api/v1/users"));
+	assert!(!ends_with::<LEN>(&OBFSTRING, &KEYSTREAM, b"/api/v1/users"));
+	assert!(!starts_with::<LEN>(&OBFSTRING, &KEYSTREAM, b"/www/api/v1/"));
+	assert!(ends_with::<LEN>(&OBFSTRING, &KEYSTREAM, b"/www/api/v1/"));
+
+	assert!(obf_starts_with!("/api/v1/users", "/api/v1/"));
+	assert!(obf_ends_with!("/api/v1/users", "users"));
+	assert!(!obf_ends_with!("users", "/users"));
+}
+
+#[test]
+fn test_contains() {
+	assert!(obfcontains!("needle in a haystack", "needle"));
+	assert!(obfcontains!("haystack with a needle", "needle"));
+	assert!(obfcontains!("needle", "needle"));
+	assert!(!obfcontains!("needl", "needle"));
+	assert!(!obfcontains!("haystack with needl e", "needle"));
+	assert!(obfcontains!("aaaaab", "aab"));
+	assert!(obfcontains!("abababac", "ababac"));
+	assert!(obfcontains!("x", ""));
+	assert!(obfcontains!(b"\x00\x01MZ\x90\x00", "MZ"));
+	assert!(obfcontains!(&b"\x00\x01MZ\x90\x00"[..], "\x01"));
+}
+
+#[test]
+fn test_strswitch() {
+	fn dispatch(cmd: &str) -> i32 {
+		__strswitch!(0; cmd => {
+			"start" => 1,
+			"stop" => 2,
+			_ => 0,
+		})
+	}
+	assert_eq!(dispatch("start"), 1);
+	assert_eq!(dispatch("stop"), 2);
+	assert_eq!(dispatch(""), 0);
+	// The 8-bit murmur3 of "aaakm" collides with "start" under key 0
+	assert_eq!(crate::murmur3(b"aaakm", 0) as u8, crate::murmur3(b"start", 0) as u8);
+	assert_eq!(dispatch("aaakm"), 0);
+
+	let owned = String::from("stop");
+	let result = strswitch!(&owned => {
+		"start" => "started",
+		"stop" => "stopped",
+		_ => "unknown",
+	});
+	assert_eq!(result, "stopped");
+}
+
+#[test]
+fn test_obfeq_bytes() {
+	const NEEDLE: &[u8] = b"\x00MAGIC\xffv2\x00";
+	let packet = [0x00, b'M', b'A', b'G', b'I', b'C', 0xff, b'v', b'2', 0x00, 0x42];
+	assert!(obfeq_bytes!(&packet[..10], NEEDLE));
+	assert!(obfeq_bytes!(&packet[..10], b"\x00MAGIC\xffv2\x00"));
+	assert!(!obfeq_bytes!(&packet[..], NEEDLE));
+	assert!(!obfeq_bytes!(&packet[..9], NEEDLE));
+	assert!(!obfeq_bytes!(&packet[..10], b"\x00MAGIC\xfev2\x00"));
+}
+
 #[test]
 fn test_obfstr_let() {
 	obfstr! {
@@ -376,3 +3860,81 @@ fn test_obfstr_const() {
 	assert_eq!(obfbytes!(ABC.as_bytes()), "ABC".as_bytes());
 	assert_eq!(obfbytes!(WORLD.as_bytes()), "🌍".as_bytes());
 }
+
+#[test]
+fn test_keystream_byte_order() {
+	// The key stream is little endian regardless of the host
+	assert_eq!(keystream::<16>(0x0123456789abcdef), [157, 170, 143, 164, 7, 56, 122, 21, 182, 115, 253, 163, 208, 136, 28, 2]);
+	assert_eq!(keystream_with::<16, 3>(0x0123456789abcdef), [39, 198, 175, 122, 33, 98, 185, 88, 55, 189, 13, 136, 15, 142, 72, 215]);
+	assert_eq!(obfuscate::<11>(b"Hello world", &keystream::<11>(0x0123456789abcdef)), [213, 207, 227, 200, 104, 24, 13, 122, 17, 208, 122]);
+	assert_eq!(crate::words::keystream::<6>(0x0123456789abcdef), [43677, 42127, 14343, 5498, 29622, 41981]);
+	// The 64-bit words lay out the same bytes in memory
+	let words = keystream64::<2>(0x0123456789abcdef);
+	assert_eq!([words[0].to_ne_bytes(), words[1].to_ne_bytes()].concat(), keystream::<16>(0x0123456789abcdef));
+	assert_eq!(&obfuscate64::<11, 2>(b"Hello world", &words).0, &obfuscate::<11>(b"Hello world", &keystream::<11>(0x0123456789abcdef)));
+}
+
+#[test]
+#[allow(clippy::large_const_arrays)]
+fn test_long_literal() {
+	// Long strings are evaluated in blocks of words, these must match the bytewise definitions
+	const LEN: usize = 0x10000 + 37;
+	const STRING: [u8; LEN] = {
+		const TEXT: &[u8] = b"The quick brown fox jumps over the lazy dog. ";
+		let mut s = [0u8; LEN];
+		let mut i = 0;
+		while i < LEN {
+			s[i] = TEXT[i % TEXT.len()];
+			i += 1;
+		}
+		s
+	};
+	const KEYS: [u8; LEN] = keystream::<LEN>(0x1234);
+	const DATA: [u8; LEN] = obfuscate::<LEN>(&STRING, &KEYS);
+	const DATA2: [u8; LEN] = obfuscate2::<LEN>(&STRING, &KEYS);
+	const FOLDED: [u8; LEN] = fold_key::<LEN>(&KEYS, 0x0123456789abcdef);
+	const MIXED: [u8; LEN] = keystream_mixed::<LEN>(0x1234, 2);
+
+	let words = keystream64::<{ LEN.div_ceil(8) }>(0x1234);
+	for i in 0..LEN {
+		assert_eq!(KEYS[i], words[i / 8].to_ne_bytes()[i % 8]);
+		assert_eq!(DATA[i], STRING[i] ^ KEYS[i] ^ if i < 8 { 0 } else { DATA[i - 8] });
+		assert_eq!(DATA2[i], encode2(STRING[i], KEYS[i]));
+		assert_eq!(FOLDED[i], KEYS[i] ^ 0x0123456789abcdefu64.to_le_bytes()[i % 8]);
+		assert_eq!(MIXED[i], mix_word(0x1234, (i / 8) as u64, 2).to_le_bytes()[i % 8]);
+	}
+	assert_eq!(&obfuscate64::<LEN, { LEN.div_ceil(8) }>(&STRING, &words).0, &DATA);
+	assert_eq!(deobfuscate::<LEN>(&DATA, &KEYS), STRING);
+
+	assert_eq!(crate::obfbytes!(&STRING), &STRING);
+	const TEXT: &str = match str::from_utf8(&STRING) { Ok(s) => s, Err(_) => panic!() };
+	assert_eq!(crate::obfstr!(TEXT), TEXT);
+}
+
+#[test]
+fn test_verified() {
+	assert_eq!(obfstr!(#[verified] "Hello world"), "Hello world");
+	assert_eq!(obfstr!(#[verified] ""), "");
+	let mut fired = false;
+	assert_eq!(obfbytes!(#[verified(|| fired = true)] b"\x00\x01\x02"), b"\x00\x01\x02");
+	assert!(!fired);
+
+	// Corrupt the obfuscated static through a pointer, the hook fires and the string is wiped
+	const STRING: &[u8] = b"Hello world";
+	const KEYS: [u8; 11] = keystream::<11>(0x1234);
+	static mut SDATA: [u8; 11] = obfuscate::<11>(STRING, &KEYS);
+	static CHECK: u32 = crate::murmur3(STRING, 42) ^ 0x5555;
+	let sdata = core::ptr::addr_of_mut!(SDATA);
+	let mut fired = 0;
+	let mut s = deobfuscate::<11>(unsafe { &*sdata }, &KEYS);
+	verify(&mut s, &CHECK, 42, 0x5555, || fired += 1);
+	assert_eq!((fired, &s), (0, b"Hello world"));
+	unsafe { (*sdata)[3] ^= 0x20 };
+	let mut s = deobfuscate::<11>(unsafe { &*sdata }, &KEYS);
+	verify(&mut s, &CHECK, 42, 0x5555, || fired += 1);
+	assert_eq!((fired, &s), (1, &[0; 11]));
+
+	let mut s = deobfuscate::<11>(unsafe { &*sdata }, &KEYS);
+	let result = std::panic::catch_unwind(move || verify(&mut s, &CHECK, 42, 0x5555, verify_failed));
+	assert_eq!(result.is_err(), cfg!(debug_assertions));
+}
@@ -0,0 +1,188 @@
+/*!
+Compiletime ASCII case conversion.
+*/
+
+/// Compiletime ASCII lowercase conversion.
+///
+/// Returns a `&'static str` with the ASCII characters of the string constant lowercased, other characters are unchanged.
+/// The `wide` form returns a `&'static [u16; LEN]` of a wide string literal or wide string constant instead.
+///
+/// ```
+/// assert_eq!(obfstr::lower!("KERNEL32.DLL"), "kernel32.dll");
+/// assert_eq!(obfstr::lower!(wide "NTDLL.DLL"), obfstr::wide!("ntdll.dll"));
+///
+/// // Obfuscate the lowercased string
+/// assert_eq!(obfstr::obfstr!(obfstr::lower!("Hello World")), "hello world");
+/// ```
+#[macro_export]
+macro_rules! lower {
+	(wide $s:literal) => {
+		$crate::lower!(wide $crate::wide!($s))
+	};
+	(wide $s:expr) => {{
+		const _CASE_WORDS: &[u16] = $s;
+		const _CASE_LEN: usize = _CASE_WORDS.len();
+		const _CASE_FOLDED: [u16; _CASE_LEN] = $crate::to_ascii_lower_wide::<_CASE_LEN>(_CASE_WORDS);
+		&_CASE_FOLDED
+	}};
+	($s:expr) => {{
+		const _CASE_STRING: &str = $s;
+		const _CASE_LEN: usize = _CASE_STRING.len();
+		const _CASE_FOLDED: [u8; _CASE_LEN] = $crate::to_ascii_lower::<_CASE_LEN>(_CASE_STRING);
+		$crate::unsafe_as_str(&_CASE_FOLDED)
+	}};
+}
+
+/// Compiletime ASCII uppercase conversion.
+///
+/// Returns a `&'static str` with the ASCII characters of the string constant uppercased, other characters are unchanged.
+/// The `wide` form returns a `&'static [u16; LEN]` of a wide string literal or wide string constant instead.
+///
+/// ```
+/// assert_eq!(obfstr::upper!("kernel32.dll"), "KERNEL32.DLL");
+/// assert_eq!(obfstr::upper!(wide "ntdll.dll"), obfstr::wide!("NTDLL.DLL"));
+/// ```
+#[macro_export]
+macro_rules! upper {
+	(wide $s:literal) => {
+		$crate::upper!(wide $crate::wide!($s))
+	};
+	(wide $s:expr) => {{
+		const _CASE_WORDS: &[u16] = $s;
+		const _CASE_LEN: usize = _CASE_WORDS.len();
+		const _CASE_FOLDED: [u16; _CASE_LEN] = $crate::to_ascii_upper_wide::<_CASE_LEN>(_CASE_WORDS);
+		&_CASE_FOLDED
+	}};
+	($s:expr) => {{
+		const _CASE_STRING: &str = $s;
+		const _CASE_LEN: usize = _CASE_STRING.len();
+		const _CASE_FOLDED: [u8; _CASE_LEN] = $crate::to_ascii_upper::<_CASE_LEN>(_CASE_STRING);
+		$crate::unsafe_as_str(&_CASE_FOLDED)
+	}};
+}
+
+/// Encodes the input string as a lowercase wide string (utf-16) constant.
+///
+/// The type of the returned constant is `&'static [u16; LEN]`, only the ASCII range is lowercased.
+///
+/// ```
+/// assert_eq!(obfstr::wide_lower!("AbC"), obfstr::wide!("abc"));
+/// ```
+///
+/// Obfuscate the lowercase wide string with [`obfwide!`](crate::obfwide!) or compare with [`obfeq!`](crate::obfeq!) by passing the lowercased string:
+///
+/// ```
+/// let module: &[u16] = obfstr::wide_lower!("NTDLL.DLL");
+/// assert_eq!(obfstr::obfwide!(obfstr::lower!("NTDLL.DLL")), module);
+/// assert!(obfstr::obfeq!(wide module, obfstr::lower!("NTDLL.DLL")));
+/// ```
+#[macro_export]
+macro_rules! wide_lower {
+	($s:expr) => {{
+		use ::core::primitive::*;
+		const _WIDE_STRING: &str = $s;
+		const _WIDE_LEN: usize = $crate::wide::len(_WIDE_STRING);
+		const _WIDE_WORDS: [u16; _WIDE_LEN] = $crate::wide::encode::<_WIDE_LEN>(_WIDE_STRING);
+		const _WIDE_FOLDED: [u16; _WIDE_LEN] = $crate::to_ascii_lower_wide::<_WIDE_LEN>(&_WIDE_WORDS);
+		&_WIDE_FOLDED
+	}};
+}
+
+/// Converts the ASCII characters of the string to lowercase.
+///
+/// Other bytes are unchanged, the result is valid UTF-8. `LEN` must equal the length of the string.
+#[doc(hidden)]
+pub const fn to_ascii_lower<const LEN: usize>(s: &str) -> [u8; LEN] {
+	let s = s.as_bytes();
+	let mut data = [0u8; LEN];
+	let mut i = 0;
+	while i < LEN {
+		data[i] = s[i].to_ascii_lowercase();
+		i += 1;
+	}
+	return data;
+}
+
+/// Converts the ASCII characters of the string to uppercase.
+///
+/// Other bytes are unchanged, the result is valid UTF-8. `LEN` must equal the length of the string.
+#[doc(hidden)]
+pub const fn to_ascii_upper<const LEN: usize>(s: &str) -> [u8; LEN] {
+	let s = s.as_bytes();
+	let mut data = [0u8; LEN];
+	let mut i = 0;
+	while i < LEN {
+		data[i] = s[i].to_ascii_uppercase();
+		i += 1;
+	}
+	return data;
+}
+
+/// Converts the ASCII range words of the wide string to lowercase.
+///
+/// Other words are unchanged. `LEN` must equal the length of the wide string.
+#[doc(hidden)]
+pub const fn to_ascii_lower_wide<const LEN: usize>(s: &[u16]) -> [u16; LEN] {
+	let mut data = [0u16; LEN];
+	let mut i = 0;
+	while i < LEN {
+		data[i] = if s[i] < 0x80 { (s[i] as u8).to_ascii_lowercase() as u16 } else { s[i] };
+		i += 1;
+	}
+	return data;
+}
+
+/// Converts the ASCII range words of the wide string to uppercase.
+///
+/// Other words are unchanged. `LEN` must equal the length of the wide string.
+#[doc(hidden)]
+pub const fn to_ascii_upper_wide<const LEN: usize>(s: &[u16]) -> [u16; LEN] {
+	let mut data = [0u16; LEN];
+	let mut i = 0;
+	while i < LEN {
+		data[i] = if s[i] < 0x80 { (s[i] as u8).to_ascii_uppercase() as u16 } else { s[i] };
+		i += 1;
+	}
+	return data;
+}
+
+#[test]
+fn test_case() {
+	assert_eq!(lower!("KeRnEl32.DLL"), "kernel32.dll");
+	assert_eq!(upper!("KeRnEl32.DLL"), "KERNEL32.DLL");
+	// Non-ASCII characters are unchanged
+	assert_eq!(lower!("ÉCOLE Ünïcode 🌍"), "École Ünïcode 🌍");
+	assert_eq!(upper!("école ünïcode 🌍"), "éCOLE üNïCODE 🌍");
+	assert_eq!(lower!(""), "");
+
+	assert_eq!(lower!(wide "KeRnEl32.DLL"), crate::wide!("kernel32.dll"));
+	assert_eq!(upper!(wide "KeRnEl32.DLL"), crate::wide!("KERNEL32.DLL"));
+	assert_eq!(lower!(wide "ÉCOLE 🌍"), crate::wide!("École 🌍"));
+	// Words outside of the ASCII range with an ASCII low byte are unchanged
+	assert_eq!(lower!(wide &[0x0141, 0x0041]), &[0x0141, 0x0061]);
+	assert_eq!(upper!(wide &[0x0161, 0x0061]), &[0x0161, 0x0041]);
+
+	assert_eq!(crate::obfstr!(lower!("Hello World")), "hello world");
+	assert_eq!(crate::obfwide!(upper!("Hello World")), crate::wide!("HELLO WORLD"));
+}
+
+#[test]
+fn test_wide_lower() {
+	assert_eq!(wide_lower!("KERNEL32.DLL"), crate::wide!("kernel32.dll"));
+	assert_eq!(wide_lower!("ÉCOLE 🌍"), crate::wide!("École 🌍"));
+	assert_eq!(wide_lower!("KERNEL32.DLL"), lower!(wide "KERNEL32.DLL"));
+
+	// Module names lowercased in place while walking the loader list
+	let mut modules: Vec<Vec<u16>> = ["ntdll.dll", "KERNEL32.DLL", "KernelBase.dll"].iter().map(|s| s.encode_utf16().collect()).collect();
+	for module in &mut modules {
+		for unit in module.iter_mut() {
+			if *unit < 0x80 {
+				*unit = (*unit as u8).to_ascii_lowercase() as u16;
+			}
+		}
+	}
+	let found = modules.iter().position(|module| crate::obfeq!(wide &module[..], lower!("Kernel32.dll")));
+	assert_eq!(found, Some(1));
+	assert_eq!(&modules[1][..], wide_lower!("Kernel32.dll"));
+	assert_eq!(crate::obfwide!(lower!("Kernel32.dll")), wide_lower!("Kernel32.dll"));
+}
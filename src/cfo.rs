@@ -3,20 +3,253 @@ Control Flow Obfuscation
 ========================
 */
 
-/// Generates the keys and xor values for a sequence of statements.
-pub const fn generate<const LEN: usize>(mut key: u32, mut xor: u32, stmts: &[&'static str; LEN]) -> [(&'static str, u32, u32); LEN] {
-	let mut result = [("", 0, 0); LEN];
+/// Maximum number of times the xor value of a statement is re-derived to avoid a collision.
+const MAX_SALT: u32 = 64;
+
+/// Records the statements executed by [`obfstmt!`] in debug builds.
+#[doc(hidden)]
+pub struct Trace<const LEN: usize> {
+	stmts: [usize; LEN],
+	len: usize,
+	misses: usize,
+}
+
+#[allow(clippy::new_without_default)]
+impl<const LEN: usize> Trace<LEN> {
+	#[inline(always)]
+	pub const fn new() -> Trace<LEN> {
+		Trace { stmts: [0; LEN], len: 0, misses: 0 }
+	}
+
+	#[inline(always)]
+	pub fn enter(&mut self, i: usize) {
+		if self.len < LEN {
+			self.stmts[self.len] = i;
+			self.len += 1;
+		}
+	}
+
+	/// Counts a key without matching state, panics after too many misses.
+	#[track_caller]
+	pub fn miss(&mut self, key: u64, exit: u64) {
+		self.misses += 1;
+		if self.misses > (LEN + 1) * 4 {
+			panic!("obfstmt: no state matches key {:#x}, expected exit {:#x} after statements {:?}", key, exit, &self.stmts[..self.len]);
+		}
+	}
+}
+
+#[cfg(test)]
+std::thread_local! {
+	static CORRUPT_KEY: core::cell::Cell<u64> = const { core::cell::Cell::new(0) };
+	static KEY_LOG: core::cell::RefCell<Option<Vec<u64>>> = const { core::cell::RefCell::new(None) };
+	static KEY_PATCH: core::cell::Cell<Option<(u64, u64)>> = const { core::cell::Cell::new(None) };
+}
+
+/// Lets the tests observe and corrupt the key of the dispatch loop in debug builds.
+#[doc(hidden)]
+#[inline(always)]
+pub fn test_hook(key: u64) -> u64 {
+	#[cfg(test)]
+	{
+		KEY_LOG.with(|log| if let Some(log) = &mut *log.borrow_mut() { log.push(key) });
+		if let Some((from, to)) = KEY_PATCH.with(|patch| patch.get()) {
+			if key == from {
+				return to;
+			}
+		}
+		return key ^ CORRUPT_KEY.with(|corrupt| corrupt.get());
+	}
+	#[cfg(not(test))]
+	return key;
+}
+
+/// Dummy values read by the junk operations.
+#[doc(hidden)]
+pub static JUNK: [u64; 8] = [
+	0x243f6a8885a308d3, 0x13198a2e03707344, 0xa4093822299f31d0, 0x082efa98ec4e6c89,
+	0x452821e638d01377, 0xbe5466cf34e90c6c, 0xc0ac29b7c97c50dd, 0x3f84d5b5b5470917,
+];
+
+/// Junk operations interleaved with the statements of [`obfstmt!`](crate::obfstmt!).
+///
+/// Every operation reads a dummy value with a volatile read and combines it with the junk value, the operations are selected by the seed.
+/// The result is routed through [`black_box`](core::hint::black_box) so the operations cannot be removed.
+#[inline(always)]
+pub fn junk<const SEED: u64, const COUNT: usize>(mut value: u64) -> u64 {
+	let mut seed = SEED;
 	let mut i = 0;
-	while i < stmts.len() {
-		key ^= xor;
-		xor = crate::murmur3(stmts[i].as_bytes(), key);
-		// FIXME! This should check for collisions...
-		result[i] = (stmts[i], key, xor);
+	while i < COUNT {
+		seed = crate::splitmix(seed);
+		let dummy = unsafe { core::ptr::read_volatile(&JUNK[(seed % 8) as usize]) };
+		value = match seed >> 62 {
+			0 => value.wrapping_add(dummy),
+			1 => value ^ dummy.rotate_left((seed >> 8) as u32 & 63),
+			2 => value.wrapping_mul(dummy | 1),
+			_ => value.wrapping_sub(dummy >> (seed >> 16 & 31)),
+		};
+		value = core::hint::black_box(value);
+		i += 1;
+	}
+	value
+}
+
+/// Maximum number of decoy states in [`obfstmt!`](crate::obfstmt!).
+pub const MAX_DECOYS: usize = 32;
+
+/// Hash of a state key folded into the integrity accumulator of [`obfstmt!`](crate::obfstmt!).
+pub const fn key_hash(key: u64) -> u64 {
+	crate::murmur3_64(&key.to_le_bytes(), 0)
+}
+
+/// Folds a state key hash into the integrity accumulator.
+#[inline(always)]
+pub const fn fold(acc: u64, hash: u64) -> u64 {
+	(acc ^ hash).wrapping_mul(0x9e3779b97f4a7c15)
+}
+
+// Implements the key generation for a key type, `obfstmt!` uses 64-bit keys, the 32-bit `generate` is kept for compatibility.
+macro_rules! keys {
+	($ty:ty, $hash:path, $generate:ident, $collides:ident) => {
+		/// Generates the keys and xor values for a sequence of statements.
+		///
+		/// The dispatch loop starts with the initial key and xor value and applies `key ^= xor` after every state.
+		/// The key of the first statement is `init ^ xor`, the statement sets the xor value which leads to the next key.
+		/// The xor value of a statement is the keyed hash of the statement with the key of the statement.
+		///
+		/// The keys of all statements, the initial key and the exit key are distinct.
+		/// When the next key collides the xor value is re-derived with an incremented salt.
+		pub const fn $generate<const LEN: usize>(init: $ty, mut xor: $ty, stmts: &[&'static str; LEN]) -> [(&'static str, $ty, $ty); LEN] {
+			let mut result = [("", 0, 0); LEN];
+			let mut key = init;
+			let mut i = 0;
+			while i < stmts.len() {
+				key ^= xor;
+				let mut salt = 0u32;
+				loop {
+					xor = $hash(stmts[i].as_bytes(), key.wrapping_add((salt as $ty).wrapping_mul(0x9e3779b9)));
+					if !$collides(init, &result, i, key, key ^ xor) {
+						break;
+					}
+					salt += 1;
+					if salt >= MAX_SALT {
+						panic!("obfstmt: unable to generate collision free keys");
+					}
+				}
+				result[i] = (stmts[i], key, xor);
+				i += 1;
+			}
+			result
+		}
+
+		// Checks the key following the statement at index `len` against the initial key and the keys generated so far.
+		const fn $collides<const LEN: usize>(init: $ty, stmts: &[(&'static str, $ty, $ty); LEN], len: usize, key: $ty, next: $ty) -> bool {
+			if next == init || next == key {
+				return true;
+			}
+			let mut i = 0;
+			while i < len {
+				if next == stmts[i].1 {
+					return true;
+				}
+				i += 1;
+			}
+			return false;
+		}
+	};
+}
+
+keys!(u32, crate::murmur3, generate, collides);
+keys!(u64, crate::murmur3_64, generate64, collides64);
+
+/// Returns the expected integrity accumulator after all statements are executed in order.
+pub const fn checksum64<const LEN: usize>(init: u64, stmts: &[(&'static str, u64, u64); LEN]) -> u64 {
+	let mut acc = init;
+	let mut i = 0;
+	while i < LEN {
+		acc = fold(acc, key_hash(stmts[i].1));
+		i += 1;
+	}
+	acc
+}
+
+/// Returns the key after the last statement which exits the loop.
+pub const fn exit64<const LEN: usize>(key: u64, xor: u64, stmts: &[(&'static str, u64, u64); LEN]) -> u64 {
+	if LEN == 0 { key ^ xor } else { stmts[LEN - 1].1 ^ stmts[LEN - 1].2 }
+}
+
+/// Generates the keys, junk and xor values of the decoy states.
+///
+/// The decoy keys are distinct from each other, the initial key, the exit key and the keys of all statements.
+/// The xor value of a decoy leads to a statement or another decoy.
+pub const fn decoys64<const LEN: usize>(init: u64, exit: u64, stmts: &[(&'static str, u64, u64); LEN], count: usize) -> [(u64, u64, u64); MAX_DECOYS] {
+	if count > MAX_DECOYS {
+		panic!("obfstmt: too many decoys");
+	}
+	let mut result = [(0, 0, 0); MAX_DECOYS];
+	let mut i = 0;
+	while i < count {
+		let mut salt = 0u32;
+		let key = loop {
+			let key = crate::murmur3_64(b"DECOY", init.wrapping_add(((i as u32 * MAX_SALT + salt) as u64).wrapping_mul(0x9e3779b9)));
+			if !collides64(init, stmts, LEN, exit, key) && !collides_decoy64(&result, i, key) {
+				break key;
+			}
+			salt += 1;
+			if salt >= MAX_SALT {
+				panic!("obfstmt: unable to generate collision free decoys");
+			}
+		};
+		let junk = crate::murmur3_64(b"JUNK", key);
+		let target = (junk % (LEN + i + 1) as u64) as usize;
+		let next = if target < LEN { stmts[target].1 } else if target < LEN + i { result[target - LEN].0 } else { exit };
+		result[i] = (key, junk, key ^ next);
 		i += 1;
 	}
 	result
 }
 
+// Checks the decoy key against the decoy keys generated so far.
+const fn collides_decoy64(decoys: &[(u64, u64, u64); MAX_DECOYS], len: usize, key: u64) -> bool {
+	let mut i = 0;
+	while i < len {
+		if key == decoys[i].0 {
+			return true;
+		}
+		i += 1;
+	}
+	return false;
+}
+
+/// Selects `a` if the condition is true and `b` otherwise without a conditional jump.
+#[doc(hidden)]
+#[inline(always)]
+pub fn select(cond: bool, a: u64, b: u64) -> u64 {
+	let mask = core::hint::black_box((cond as u64).wrapping_neg());
+	return b ^ (mask & (a ^ b));
+}
+
+/// Generates the keys for [`obfbranch!`](crate::obfbranch!).
+///
+/// Returns the xor values leading from the initial key to the `then` and `else` keys followed by those keys.
+/// The initial key and the `then` and `else` keys are distinct.
+pub const fn branch(init: u64, then: &str, otherwise: &str) -> [u64; 4] {
+	let mut salt = 0u32;
+	loop {
+		let offset = (salt as u64).wrapping_mul(0x9e3779b97f4a7c15);
+		let xor_then = crate::murmur3_64(then.as_bytes(), init.wrapping_add(offset));
+		let xor_else = crate::murmur3_64(otherwise.as_bytes(), !init.wrapping_add(offset));
+		let (key_then, key_else) = (init ^ xor_then, init ^ xor_else);
+		if key_then != init && key_else != init && key_then != key_else {
+			return [xor_then, xor_else, key_then, key_else];
+		}
+		salt += 1;
+		if salt >= MAX_SALT {
+			panic!("obfbranch: unable to generate collision free keys");
+		}
+	}
+}
+
 /// Statement control flow obfuscation.
 ///
 /// Given a sequence of statements obfuscates the relationship between each statement.
@@ -26,8 +259,28 @@ pub const fn generate<const LEN: usize>(mut key: u32, mut xor: u32, stmts: &[&'s
 /// Variables cannot be declared inside the obfuscated statements, declare and initialize any variables needed beforehand.
 /// Control flow analysis will fail. The declared variables will need to be mutable and have an initial value.
 ///
-/// There's a risk that the obfuscated code fails to work due to two statements generating the same random key accidentally.
-/// This is presented at runtime with an infinite loop pending extra validation checks.
+/// Statements which are `return`, `break` or `continue` leave the block as expected, `?` returns from the enclosing function.
+/// Unlabeled `break` and `continue` nested deeper inside a statement fail to compile, label the enclosing loop instead.
+///
+/// A trailing expression without semicolon is the value of the block, it is evaluated as the last statement.
+///
+/// Blocks can be nested, every expansion declares its constants in its own block scope which shadows those of the enclosing block.
+///
+/// The state keys are 64-bit, two statements generating the same random key are detected at compiletime and the keys are re-derived.
+/// In debug builds a key without matching state panics instead of looping forever.
+///
+/// With `tamper = handler;` before the block every statement folds a hash of its key into an accumulator.
+/// When the accumulator does not match the expected value after the last statement the handler is called, then the block completes.
+/// This detects states which are skipped by patching the dispatch, statements which leave the block early are not checked.
+///
+/// With `junk = N;` before the block every state executes N junk operations on a dummy value.
+/// The options are given in the order `seed`, `decoys`, `junk` and `tamper`.
+///
+/// Optionally up to 32 decoy states can be added with `decoys = N;` before the block.
+/// The decoys look like the real states but their keys are never reached from the initial key.
+///
+/// The initial key is random, `seed = K;` before the block (and before `decoys`) pins it to reproduce the key schedule.
+/// The schedule is computed by [`cfo::generate64`](crate::cfo::generate64) from the initial key, the initial xor value `murmur3_64(b"XOR", K)` and the stringified statements.
 ///
 /// # Examples
 ///
@@ -42,55 +295,683 @@ pub const fn generate<const LEN: usize>(mut key: u32, mut xor: u32, stmts: &[&'s
 ///# obfstr::obfstmt! {}
 /// assert_eq!(tmp, 10);
 /// ```
+///
+/// Yielding a value:
+///
+/// ```
+/// let mut tmp = 0;
+/// let value = obfstr::obfstmt! {
+/// 	tmp += 5;
+/// 	tmp *= 3;
+/// 	tmp + 1
+/// };
+/// assert_eq!(value, 16);
+/// ```
+///
+/// Adding decoy states:
+///
+/// ```
+/// let mut tmp = 0;
+/// obfstr::obfstmt!(decoys = 8; {
+/// 	tmp = 2;
+/// 	tmp *= 22;
+/// });
+/// assert_eq!(tmp, 44);
+/// ```
+///
+/// Checking the integrity of the block:
+///
+/// ```
+/// fn on_tamper() {
+/// 	std::process::abort();
+/// }
+/// let mut licensed = false;
+/// obfstr::obfstmt!(tamper = on_tamper; {
+/// 	licensed = true;
+/// 	licensed &= 1 + 1 == 2;
+/// });
+/// assert!(licensed);
+/// ```
+///
+/// Nested unlabeled `continue` fails to compile:
+///
+/// ```compile_fail,E0695
+/// let mut tmp = 0;
+/// for i in 0..3 {
+/// 	obfstr::obfstmt! {
+/// 		if i == 1 { continue; };
+/// 		tmp += i;
+/// 	}
+/// }
+/// ```
 #[macro_export]
 macro_rules! obfstmt {
-	($($stmt:stmt;)*) => {{
+	(seed = $seed:expr; $(decoys = $n:expr;)? $(junk = $j:expr;)? $(tamper = $f:expr;)? { $($tt:tt)* }) => {
+		$crate::__obfstmt!(@seed [$seed] [$($n)?] (0usize $(+ $j)?) [$($f)?] $($tt)*)
+	};
+	(decoys = $n:expr; $(junk = $j:expr;)? $(tamper = $f:expr;)? { $($tt:tt)* }) => {
+		$crate::__obfstmt!(@seed [] [$n] (0usize $(+ $j)?) [$($f)?] $($tt)*)
+	};
+	(junk = $j:expr; $(tamper = $f:expr;)? { $($tt:tt)* }) => {
+		$crate::__obfstmt!(@seed [] [] (0usize + $j) [$($f)?] $($tt)*)
+	};
+	(tamper = $f:expr; { $($tt:tt)* }) => {
+		$crate::__obfstmt!(@seed [] [] (0usize) [$f] $($tt)*)
+	};
+	($($tt:tt)*) => {
+		$crate::__obfstmt!($crate::random!(u64, stringify!($($tt)*)), $crate::murmur3_64(b"XOR", _OBFSTMT_KEY), (0usize;), (0usize), (); $($tt)*)
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfstmt {
+	// Fill in the defaults of the options
+	(@seed [] $decoys:tt $junk:tt $tamper:tt $($tt:tt)*) => {
+		$crate::__obfstmt!(@decoys [$crate::random!(u64, stringify!($($tt)*))] $decoys $junk $tamper $($tt)*)
+	};
+	(@seed [$seed:expr] $decoys:tt $junk:tt $tamper:tt $($tt:tt)*) => {
+		$crate::__obfstmt!(@decoys [$seed] $decoys $junk $tamper $($tt)*)
+	};
+	(@decoys $seed:tt [] $junk:tt $tamper:tt $($tt:tt)*) => {
+		$crate::__obfstmt!(@tamper $seed (0usize;) $junk $tamper $($tt)*)
+	};
+	(@decoys $seed:tt [$n:expr] $junk:tt $tamper:tt $($tt:tt)*) => {
+		$crate::__obfstmt!(@tamper $seed ($n; 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31) $junk $tamper $($tt)*)
+	};
+	(@tamper [$seed:expr] $decoys:tt $junk:tt [] $($tt:tt)*) => {
+		$crate::__obfstmt!($seed, $crate::murmur3_64(b"XOR", _OBFSTMT_KEY), $decoys, $junk, (); $($tt)*)
+	};
+	(@tamper [$seed:expr] $decoys:tt $junk:tt [$f:expr] $($tt:tt)*) => {
+		$crate::__obfstmt!($seed, $crate::murmur3_64(b"XOR", _OBFSTMT_KEY), $decoys, $junk, ($f); $($tt)*)
+	};
+	// Tag every statement with how it leaves the dispatch loop
+	(@parse $key:expr, $xor:expr, $cfg:tt, $value:ident, $x:expr; [$($done:tt)*] continue; $($tail:tt)*) => {
+		$crate::__obfstmt!(@parse $key, $xor, $cfg, $value, $x + 1usize; [$($done)* (continue, $x) continue;] $($tail)*)
+	};
+	(@parse $key:expr, $xor:expr, $cfg:tt, $value:ident, $x:expr; [$($done:tt)*] break; $($tail:tt)*) => {
+		$crate::__obfstmt!(@parse $key, $xor, $cfg, $value, $x + 1usize; [$($done)* (break, $x) break;] $($tail)*)
+	};
+	(@parse $key:expr, $xor:expr, $cfg:tt, $value:ident, $x:expr; [$($done:tt)*] continue $label:lifetime; $($tail:tt)*) => {
+		$crate::__obfstmt!(@parse $key, $xor, $cfg, $value, $x + 1usize; [$($done)* (diverge, $x) continue $label;] $($tail)*)
+	};
+	(@parse $key:expr, $xor:expr, $cfg:tt, $value:ident, $x:expr; [$($done:tt)*] break $label:lifetime $($e:expr)?; $($tail:tt)*) => {
+		$crate::__obfstmt!(@parse $key, $xor, $cfg, $value, $x + 1usize; [$($done)* (diverge, $x) break $label $($e)?;] $($tail)*)
+	};
+	(@parse $key:expr, $xor:expr, $cfg:tt, $value:ident, $x:expr; [$($done:tt)*] return $($e:expr)?; $($tail:tt)*) => {
+		$crate::__obfstmt!(@parse $key, $xor, $cfg, $value, $x + 1usize; [$($done)* (diverge, $x) return $($e)?;] $($tail)*)
+	};
+	(@parse $key:expr, $xor:expr, $cfg:tt, $value:ident, $x:expr; [$($done:tt)*] $stmt:stmt; $($tail:tt)*) => {
+		$crate::__obfstmt!(@parse $key, $xor, $cfg, $value, $x + 1usize; [$($done)* (stmt, $x) $stmt;] $($tail)*)
+	};
+	// A trailing expression without semicolon is the value of the block, its statement leaves the dispatch loop
+	(@parse $key:expr, $xor:expr, $cfg:tt, $value:ident, $x:expr; [$($done:tt)*] $e:expr) => {
+		$crate::__obfstmt!(@gen $key, $xor, $cfg, $value, ::core::mem::MaybeUninit::uninit(); [$($done)* (value, $x) $value = ::core::mem::MaybeUninit::new($e);])
+	};
+	(@parse $key:expr, $xor:expr, $cfg:tt, $value:ident, $x:expr; [$($done:tt)*]) => {
+		$crate::__obfstmt!(@gen $key, $xor, $cfg, $value, ::core::mem::MaybeUninit::new(()); [$($done)*])
+	};
+	(@gen $key:expr, $xor:expr, ($tamper:tt, $junk:expr; $decoys:expr; $($d:literal)*), $value:ident, $init:expr; [$(($kind:ident, $i:expr) $stmt:stmt;)*]) => {{
 		// Initial KEY and XOR values
-		const _OBFSTMT_KEY: u32 = $crate::random!(u32, stringify!($($stmt;)*));
-		const _OBFSTMT_XOR: u32 = $crate::murmur3(b"XOR", _OBFSTMT_KEY);
+		const _OBFSTMT_KEY: u64 = $key;
+		const _OBFSTMT_XOR: u64 = $xor;
 		// Count the number of statements
 		const _OBFSTMT_LEN: usize = <[&'static str]>::len(&[$(stringify!($stmt)),*]);
 		// Generate key and xor values of every statement and the final exit code
-		const _OBFSTMT_STMTS: [(&'static str, u32, u32); _OBFSTMT_LEN] =
-			$crate::cfo::generate::<{_OBFSTMT_LEN}>(_OBFSTMT_KEY, _OBFSTMT_XOR, &[$(stringify!($stmt)),*]);
-		const _OBFSTMT_EXIT: u32 = if _OBFSTMT_LEN == 0 { _OBFSTMT_KEY ^ _OBFSTMT_XOR }
-			else { _OBFSTMT_STMTS[_OBFSTMT_LEN - 1].1 ^ _OBFSTMT_STMTS[_OBFSTMT_LEN - 1].2 };
+		const _OBFSTMT_STMTS: [(&'static str, u64, u64); _OBFSTMT_LEN] =
+			$crate::cfo::generate64::<{_OBFSTMT_LEN}>(_OBFSTMT_KEY, _OBFSTMT_XOR, &[$(stringify!($stmt)),*]);
+		const _OBFSTMT_EXIT: u64 = $crate::cfo::exit64::<{_OBFSTMT_LEN}>(_OBFSTMT_KEY, _OBFSTMT_XOR, &_OBFSTMT_STMTS);
+		// Generate the unreachable decoy states
+		const _OBFSTMT_DECOYS_LEN: usize = $decoys;
+		// Number of junk operations per state
+		const _OBFSTMT_JUNK: usize = $junk;
+		const _OBFSTMT_DECOYS: [(u64, u64, u64); $crate::cfo::MAX_DECOYS] =
+			$crate::cfo::decoys64::<{_OBFSTMT_LEN}>(_OBFSTMT_KEY, _OBFSTMT_EXIT, &_OBFSTMT_STMTS, _OBFSTMT_DECOYS_LEN);
 		// Initialize the key and xor values
 		let mut key = _OBFSTMT_KEY;
 		#[allow(unused_mut)]
 		let mut xor = _OBFSTMT_XOR;
-		loop {
-			$crate::__obfstmt_match!(key, xor, 0usize, [$($stmt;)*], []);
+		#[allow(unused_mut)]
+		let mut $value = $init;
+		#[allow(unused_mut)]
+		let mut junk = _OBFSTMT_KEY;
+		#[cfg(debug_assertions)]
+		let mut trace = $crate::cfo::Trace::<{_OBFSTMT_LEN}>::new();
+		$crate::__obfstmt_tamper!($tamper, init, acc, _OBFSTMT_KEY, ());
+		// Breaks with the index + 1 of a `continue` or `break` statement, or 0 at the exit
+		// The junk value is never read after a statement which leaves the block early
+		#[allow(unused_assignments)]
+		let _obfstmt_flow: usize = 'obfstmt: loop {
+			match key {
+				// Have to use match guard here because an expression isn't allowed in pattern position
+				// The result is still optimized to a binary search for the right key per block
+				$(
+					key if key == { _OBFSTMT_STMTS[$i].1 } => {
+						#[cfg(debug_assertions)]
+						trace.enter($i);
+						junk = $crate::cfo::junk::<{_OBFSTMT_STMTS[$i].1}, _OBFSTMT_JUNK>(junk);
+						$crate::__obfstmt_tamper!($tamper, $kind, acc, _OBFSTMT_STMTS[$i].1, $crate::cfo::checksum64::<{_OBFSTMT_LEN}>(_OBFSTMT_KEY, &_OBFSTMT_STMTS));
+						$crate::__obfstmt_arm!($kind, 'obfstmt, $i, xor, $stmt);
+					},
+				)*
+				// Decoys beyond the requested count are removed by the constant guard
+				$(
+					key if $d < _OBFSTMT_DECOYS_LEN && key == { _OBFSTMT_DECOYS[$d].0 } => {
+						junk = junk.wrapping_mul(_OBFSTMT_DECOYS[$d].1 | 1) ^ (_OBFSTMT_DECOYS[$d].1 >> 7);
+						xor = _OBFSTMT_DECOYS[$d].2;
+					},
+				)*
+				_OBFSTMT_EXIT => {
+					$crate::__obfstmt_tamper!($tamper, exit, acc, _OBFSTMT_EXIT, $crate::cfo::checksum64::<{_OBFSTMT_LEN}>(_OBFSTMT_KEY, &_OBFSTMT_STMTS));
+					break 'obfstmt 0usize;
+				},
+				// A key without matching state would loop forever, panic in debug builds instead
+				#[cfg(debug_assertions)]
+				_ => trace.miss(key, _OBFSTMT_EXIT),
+				#[cfg(not(debug_assertions))]
+				_ => (),
+			}
 			key ^= xor;
+			#[cfg(debug_assertions)]
+			{
+				key = $crate::cfo::test_hook(key);
+			}
+		};
+		if _OBFSTMT_DECOYS_LEN > 0 || _OBFSTMT_JUNK > 0 {
+			::core::hint::black_box(junk);
+		}
+		// Continue or break the enclosing loop outside of the dispatch loop
+		$($crate::__obfstmt_flow!($kind, _obfstmt_flow, $i);)*
+		// Safety: with a trailing expression the loop only breaks with 0 after writing the value
+		unsafe { $value.assume_init() }
+	}};
+	// 64-bit keys with decoys, junk and integrity check
+	($key:expr, $xor:expr, ($($decoys:tt)*), ($junk:expr), $tamper:tt; $($tt:tt)*) => {
+		$crate::__obfstmt!(@parse $key, $xor, ($tamper, $junk; $($decoys)*), _obfstmt_value, 0usize; [] $($tt)*)
+	};
+}
+
+/// Generates the integrity check of [`obfstmt!`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfstmt_tamper {
+	((), $($tt:tt)*) => {};
+	(($f:expr), init, $acc:ident, $key:expr, $expected:expr) => {
+		let mut $acc: u64 = $key;
+	};
+	(($f:expr), stmt, $acc:ident, $key:expr, $expected:expr) => {
+		$acc = $crate::cfo::fold($acc, const { $crate::cfo::key_hash($key) });
+	};
+	// The value statement is the last statement and leaves the dispatch loop right away
+	(($f:expr), value, $acc:ident, $key:expr, $expected:expr) => {
+		$acc = $crate::cfo::fold($acc, const { $crate::cfo::key_hash($key) });
+		$crate::__obfstmt_tamper!(($f), exit, $acc, $key, $expected);
+	};
+	(($f:expr), exit, $acc:ident, $key:expr, $expected:expr) => {
+		if $acc != const { $expected } {
+			($f)();
+		}
+	};
+	// Statements leaving the dispatch loop early are not checked
+	(($f:expr), $kind:ident, $acc:ident, $key:expr, $expected:expr) => {};
+}
+
+/// Generates the body of a match arm for [`obfstmt!`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfstmt_arm {
+	(stmt, $label:lifetime, $i:expr, $xor:expr, $stmt:stmt) => {
+		// Unlabeled break and continue nested in the statement would target the dispatch loop, the labeled block rejects them
+		#[allow(unused_labels)]
+		'obfstmt_stmt: {
+			$stmt
+		}
+		$xor = _OBFSTMT_STMTS[$i].2;
+	};
+	(value, $label:lifetime, $i:expr, $xor:expr, $stmt:stmt) => {
+		// Leave the dispatch loop right away, the borrow checker rejects moves into the value inside a loop
+		#[allow(unused_labels)]
+		'obfstmt_stmt: {
+			$stmt
+		}
+		break $label 0usize
+	};
+	(continue, $label:lifetime, $i:expr, $xor:expr, $stmt:stmt) => {
+		break $label ($i + 1usize)
+	};
+	(break, $label:lifetime, $i:expr, $xor:expr, $stmt:stmt) => {
+		break $label ($i + 1usize)
+	};
+	(diverge, $label:lifetime, $i:expr, $xor:expr, $stmt:stmt) => {
+		$stmt
+	};
+}
+
+/// Generates the `continue` and `break` statements for [`obfstmt!`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfstmt_flow {
+	(continue, $flow:ident, $i:expr) => {
+		if $flow == $i + 1usize {
+			continue;
+		}
+	};
+	(break, $flow:ident, $i:expr) => {
+		if $flow == $i + 1usize {
+			break;
+		}
+	};
+	($kind:ident, $flow:ident, $i:expr) => {};
+}
+
+/// Branch control flow obfuscation.
+///
+/// Behaves like `if cond { then } else { otherwise }` but the condition selects the key of the branch in a dispatch loop instead of a conditional jump.
+/// The condition is evaluated exactly once.
+///
+/// Unlabeled `break` and `continue` inside the branches fail to compile, label the enclosing loop instead.
+///
+/// # Examples
+///
+/// ```
+/// fn sign(x: i32) -> &'static str {
+/// 	obfstr::obfbranch!(x < 0, { "negative" }, { "positive" })
+/// }
+/// assert_eq!(sign(-3), "negative");
+/// assert_eq!(sign(3), "positive");
+/// ```
+#[macro_export]
+macro_rules! obfbranch {
+	($cond:expr, { $($then:tt)* }, { $($else:tt)* }) => {{
+		const _OBFBRANCH_KEY: u64 = $crate::random!(u64, stringify!($cond));
+		const _OBFBRANCH_KEYS: [u64; 4] = $crate::cfo::branch(_OBFBRANCH_KEY, stringify!($($then)*), stringify!($($else)*));
+		// Select the xor value without a conditional jump
+		let xor = $crate::cfo::select($cond, _OBFBRANCH_KEYS[0], _OBFBRANCH_KEYS[1]);
+		let mut key = _OBFBRANCH_KEY;
+		// Unlabeled break and continue in the branches would target the dispatch loop, the labeled blocks reject them
+		'obfbranch: {
+			#[allow(unreachable_code, unused_labels)]
+			loop {
+				key ^= xor;
+				match key {
+					key if key == { _OBFBRANCH_KEYS[2] } => break 'obfbranch 'obfbranch_block: { $($then)* },
+					key if key == { _OBFBRANCH_KEYS[3] } => break 'obfbranch 'obfbranch_block: { $($else)* },
+					_ => (),
+				}
+			};
 		}
 	}};
 }
 
-/// Generates the match statement for [`obfstmt!`].
+/// Loop control flow obfuscation.
+///
+/// Flattens a `for` or `while` loop into a dispatch loop over hashed states.
+/// The loop header, the body and the step to the next iteration are separate states.
+/// Optionally up to 32 decoy states can be added with `decoys = N;` before the loop.
+///
+/// Unlabeled `break` and `continue` in the body keep their meaning.
+///
+/// # Limitations
+///
+/// The loop cannot be labeled and does not support `while let` or `loop`.
+/// Unlabeled `break` and `continue` inside the body target the flattened loop, labeled `break` and `continue` target the enclosing loops.
+///
+/// # Examples
+///
+/// ```
+/// let mut sum = 0;
+/// obfstr::obfloop!(for i in 0..10 {
+/// 	if i % 3 == 0 {
+/// 		continue;
+/// 	}
+/// 	if i == 8 {
+/// 		break;
+/// 	}
+/// 	sum += i;
+/// });
+/// assert_eq!(sum, 1 + 2 + 4 + 5 + 7);
+///
+/// let mut n = 27;
+/// let mut steps = 0;
+/// obfstr::obfloop!(decoys = 4; while n != 1 {
+/// 	n = if n % 2 == 0 { n / 2 } else { 3 * n + 1 };
+/// 	steps += 1;
+/// });
+/// assert_eq!(steps, 111);
+/// ```
+#[macro_export]
+macro_rules! obfloop {
+	(decoys = $n:expr; $($tt:tt)*) => {
+		$crate::__obfloop!(@split ($n; 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31) [] $($tt)*)
+	};
+	($($tt:tt)*) => {
+		$crate::__obfloop!(@split (0usize;) [] $($tt)*)
+	};
+}
+
 #[doc(hidden)]
 #[macro_export]
-macro_rules! __obfstmt_match {
-	// Terminating case, generate the code
-	($key:expr, $xor:expr, $x:expr, [], [$($i:expr, $stmt:stmt;)*]) => {
-		match $key {
-			// Have to use match guard here because an expression isn't allowed in pattern position
-			// The result is still optimized to a binary search for the right key per block
-			$(
-				key if key == { _OBFSTMT_STMTS[$i].1 } => {
-					$stmt
-					$xor = _OBFSTMT_STMTS[$i].2;
+macro_rules! __obfloop {
+	// Split the loop body from the loop head
+	(@split $decoys:tt [$($head:tt)*] { $($body:tt)* }) => {
+		$crate::__obfloop!(@loop $decoys [$($head)*] { $($body)* })
+	};
+	(@split $decoys:tt [$($head:tt)*] $tt:tt $($tail:tt)+) => {
+		$crate::__obfloop!(@split $decoys [$($head)* $tt] $($tail)+)
+	};
+	(@loop $decoys:tt [for $pat:pat in $($iter:tt)+] $body:tt) => {{
+		let mut _obfloop_iter = ::core::iter::IntoIterator::into_iter($($iter)+);
+		let mut _obfloop_next = ::core::option::Option::None;
+		$crate::__obfloop!(@gen $decoys, stringify!(for $pat in $($iter)+), stringify!($body),
+			{
+				_obfloop_next = ::core::iter::Iterator::next(&mut _obfloop_iter);
+				_obfloop_next.is_some()
+			},
+			{
+				if let ::core::option::Option::Some($pat) = _obfloop_next.take() {
+					$crate::__obfloop!(@body $body)
+				}
+				else {
+					true
+				}
+			})
+	}};
+	(@loop $decoys:tt [while $($cond:tt)+] $body:tt) => {
+		$crate::__obfloop!(@gen $decoys, stringify!(while $($cond)+), stringify!($body),
+			{ $($cond)+ },
+			{ $crate::__obfloop!(@body $body) })
+	};
+	// Runs the body once inside a loop which catches unlabeled break and continue, evaluates to true if the body breaks
+	(@body $body:tt) => {{
+		let mut first = true;
+		#[allow(unused_assignments)]
+		let mut broke = true;
+		#[allow(unreachable_code, unused_assignments)]
+		loop {
+			if !first {
+				// Reached by continue
+				broke = false;
+				break;
+			}
+			first = false;
+			$body
+			broke = false;
+			break;
+		}
+		broke
+	}};
+	(@gen ($decoys:expr; $($d:literal)*), $head_str:expr, $body_str:expr, $head:block, $body:block) => {{
+		const _OBFLOOP_KEY: u64 = $crate::random!(u64, $head_str, $body_str);
+		const _OBFLOOP_XOR: u64 = $crate::murmur3_64(b"XOR", _OBFLOOP_KEY);
+		// The header, body and step states followed by the exit
+		const _OBFLOOP_STATES: [(&'static str, u64, u64); 3] = $crate::cfo::generate64::<3>(_OBFLOOP_KEY, _OBFLOOP_XOR, &[$head_str, $body_str, "step"]);
+		const _OBFLOOP_HEAD: u64 = _OBFLOOP_STATES[0].1;
+		const _OBFLOOP_BODY: u64 = _OBFLOOP_STATES[1].1;
+		const _OBFLOOP_STEP: u64 = _OBFLOOP_STATES[2].1;
+		const _OBFLOOP_EXIT: u64 = $crate::cfo::exit64::<3>(_OBFLOOP_KEY, _OBFLOOP_XOR, &_OBFLOOP_STATES);
+		// Generate the unreachable decoy states
+		const _OBFLOOP_DECOYS_LEN: usize = $decoys;
+		const _OBFLOOP_DECOYS: [(u64, u64, u64); $crate::cfo::MAX_DECOYS] =
+			$crate::cfo::decoys64::<3>(_OBFLOOP_KEY, _OBFLOOP_EXIT, &_OBFLOOP_STATES, _OBFLOOP_DECOYS_LEN);
+		let mut key = _OBFLOOP_KEY;
+		let mut xor = _OBFLOOP_XOR;
+		#[allow(unused_mut)]
+		let mut junk = _OBFLOOP_KEY;
+		loop {
+			match key {
+				key if key == _OBFLOOP_HEAD => {
+					let more: bool = $head;
+					xor = $crate::cfo::select(more, _OBFLOOP_HEAD ^ _OBFLOOP_BODY, _OBFLOOP_HEAD ^ _OBFLOOP_EXIT);
+				},
+				key if key == _OBFLOOP_BODY => {
+					let broke: bool = $body;
+					xor = $crate::cfo::select(broke, _OBFLOOP_BODY ^ _OBFLOOP_EXIT, _OBFLOOP_BODY ^ _OBFLOOP_STEP);
+				},
+				key if key == _OBFLOOP_STEP => {
+					xor = _OBFLOOP_STEP ^ _OBFLOOP_HEAD;
 				},
-			)*
-			_OBFSTMT_EXIT => break,
-			_ => (),
+				// Decoys beyond the requested count are removed by the constant guard
+				$(
+					key if $d < _OBFLOOP_DECOYS_LEN && key == { _OBFLOOP_DECOYS[$d].0 } => {
+						junk = junk.wrapping_mul(_OBFLOOP_DECOYS[$d].1 | 1) ^ (_OBFLOOP_DECOYS[$d].1 >> 7);
+						xor = _OBFLOOP_DECOYS[$d].2;
+					},
+				)*
+				_OBFLOOP_EXIT => break,
+				_ => (),
+			}
+			key ^= xor;
+		}
+		if _OBFLOOP_DECOYS_LEN > 0 {
+			::core::hint::black_box(junk);
+		}
+	}};
+}
+
+/// Keyed bijection used to hide the constants in [`obfmatch!`](crate::obfmatch!).
+#[inline(always)]
+pub const fn permute(mut v: u64, key: u64) -> u64 {
+	v ^= key;
+	v = v.wrapping_mul(key | 1);
+	v ^= v >> 29;
+	v = v.wrapping_mul(0xbf58476d1ce4e5b9);
+	v ^= v >> 32;
+	return v;
+}
+
+/// Integer types supported by [`obfmatch!`](crate::obfmatch!).
+#[doc(hidden)]
+pub trait Discriminant: Copy {
+	/// Smallest value of the type.
+	const MIN: i128;
+	/// Largest value of the type.
+	const MAX: i128;
+	/// Widens the value to 64 bits, signed values are sign extended.
+	fn widen(self) -> u64;
+}
+macro_rules! impl_discriminant {
+	($($ty:ty),*) => {$(
+		impl Discriminant for $ty {
+			const MIN: i128 = <$ty>::MIN as i128;
+			const MAX: i128 = <$ty>::MAX as i128;
+			#[inline(always)]
+			fn widen(self) -> u64 {
+				self as u64
+			}
+		}
+	)*};
+}
+impl_discriminant!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+struct PatCheck<T, const PAT: i128>(T);
+impl<T: Discriminant, const PAT: i128> PatCheck<T, PAT> {
+	const IN_RANGE: () = assert!(PAT >= T::MIN && PAT <= T::MAX, "obfmatch! pattern out of range for the type of the value");
+}
+
+/// Checks at compiletime that the pattern is in range for the type of the value.
+///
+/// Out of range literals are otherwise only rejected by the `overflowing_literals` lint, with the lint allowed they would silently wrap.
+#[doc(hidden)]
+#[inline(always)]
+pub fn check_pattern<T: Discriminant, const PAT: i128>(_: &T) {
+	let () = PatCheck::<T, PAT>::IN_RANGE;
+}
+
+/// Integer match obfuscation.
+///
+/// Every arm's constant is remapped through a compiletime random permutation.
+/// At runtime the value is remapped through the same permutation and compared against the remapped constants.
+/// The binary contains neither the original constants nor a table ordered by them.
+///
+/// Only integer literal patterns are supported, multiple patterns per arm can be separated by `|`.
+/// Ranges, bindings and other patterns produce a compile error. The `_` wildcard arm is required and must come last.
+/// The patterns are type checked against the value like in a plain `match`, the value must be an integer type of at most 64 bits.
+/// The value is compared after widening to 64 bits, negative values are sign extended the same way as the negative literals.
+///
+/// # Examples
+///
+/// ```
+/// fn opcode(op: u8) -> &'static str {
+/// 	obfstr::obfmatch!(op => {
+/// 		0x90 => "nop",
+/// 		0xc3 | 0xc2 => "ret",
+/// 		0xcc => "int3",
+/// 		_ => "unknown",
+/// 	})
+/// }
+/// assert_eq!(opcode(0x90), "nop");
+/// assert_eq!(opcode(0xc2), "ret");
+/// assert_eq!(opcode(0x00), "unknown");
+/// ```
+///
+/// Patterns out of range for the type of the value fail to compile:
+///
+/// ```compile_fail
+/// let op = 0u8;
+/// let _ = obfstr::obfmatch!(op => { 256 => true, _ => false });
+/// ```
+///
+/// Values wider than 64 bits fail to compile:
+///
+/// ```compile_fail
+/// let v = 1u128 << 64 | 1;
+/// let _ = obfstr::obfmatch!(v => { 1 => true, _ => false });
+/// ```
+#[macro_export]
+macro_rules! obfmatch {
+	($e:expr => { $($($pat:literal)|+ => $body:expr,)* _ => $default:expr $(,)? }) => {{
+		const _OBFMATCH_KEY: u64 = $crate::random!(u64, "obfmatch");
+		let _obfmatch_scrutinee = $e;
+		// Type check the patterns against the value, the dead match is removed without leaving the constants behind
+		if false {
+			match _obfmatch_scrutinee { $($($pat)|+ => (),)* _ => () }
+		}
+		$($($crate::cfo::check_pattern::<_, {$pat as i128}>(&_obfmatch_scrutinee);)+)*
+		let _obfmatch_value = ::core::hint::black_box($crate::cfo::permute($crate::cfo::Discriminant::widen(_obfmatch_scrutinee), _OBFMATCH_KEY));
+		$(
+			if $(_obfmatch_value == { const _OBFMATCH_PAT: u64 = $crate::cfo::permute($pat as i128 as u64, _OBFMATCH_KEY); _OBFMATCH_PAT })||+ {
+				$body
+			}
+			else
+		)*
+		{
+			$default
 		}
+	}};
+	($($tt:tt)*) => {
+		compile_error!("obfmatch! only supports integer literal patterns followed by a `_` wildcard arm")
 	};
-	// Generate increasing indices for every stmt
-	($key:expr, $xor:expr, $x:expr, [$stmt1:stmt; $($tail:stmt;)*], [$($i:expr, $stmt2:stmt;)*]) => {
-		$crate::__obfstmt_match!($key, $xor, $x + 1usize, [$($tail;)*], [$($i, $stmt2;)* $x, $stmt1; ])
+}
+
+/// Arithmetic identity which holds for every input, the identity is selected by the seed.
+///
+/// The inputs are routed through [`black_box`](core::hint::black_box) so the result cannot be folded at compiletime.
+/// The identities are chosen such that LLVM's known bits analysis cannot prove them.
+#[inline(always)]
+pub fn opaque<const SEED: u32>(x: u32, y: u32) -> bool {
+	let x = core::hint::black_box(x);
+	let y = core::hint::black_box(y);
+	match SEED % 4 {
+		// The product of two consecutive integers is even
+		0 => {
+			let t = x ^ y;
+			t.wrapping_mul(t.wrapping_add(1)) & 1 == 0
+		},
+		// The product of three consecutive integers is even
+		1 => {
+			let t = x.wrapping_add(y);
+			t.wrapping_mul(t.wrapping_add(1)).wrapping_mul(t.wrapping_add(2)) & 1 == 0
+		},
+		// Squares are 0, 1 or 4 modulo 8
+		2 => x.wrapping_mul(x) != y.wrapping_mul(8).wrapping_add(5),
+		// Squares are not 3, 6 or 7 modulo 8
+		_ => x.wrapping_mul(x) != y.wrapping_mul(y).wrapping_mul(7).wrapping_sub(1),
+	}
+}
+
+/// Opaque predicate which is always `true`.
+///
+/// Expands to an arithmetic identity over a random constant which the optimizer cannot prove to be constant.
+/// Optionally takes a runtime integer which is mixed into the input of the identity, the predicate is still always `true`.
+///
+/// # Examples
+///
+/// ```
+/// let mut value = 0;
+/// if obfstr::obftrue!() {
+/// 	value = 42;
+/// }
+/// else {
+/// 	value = 13;
+/// }
+/// assert_eq!(value, 42);
+///
+/// let pid = std::process::id();
+/// assert!(obfstr::obftrue!(pid));
+/// ```
+#[macro_export]
+macro_rules! obftrue {
+	() => {
+		$crate::cfo::opaque::<{$crate::random!(u32, "obftrue")}>($crate::random!(u32, "x"), $crate::random!(u32, "y"))
+	};
+	($e:expr) => {
+		$crate::cfo::opaque::<{$crate::random!(u32, "obftrue", stringify!($e))}>(($e as u32) ^ $crate::random!(u32, "x", stringify!($e)), $crate::random!(u32, "y", stringify!($e)))
 	};
 }
 
+/// Opaque predicate which is always `false`.
+///
+/// See [`obftrue!`] for details.
+///
+/// ```
+/// if obfstr::obffalse!() {
+/// 	unreachable!();
+/// }
+/// assert!(!obfstr::obffalse!(std::process::id()));
+/// ```
+#[macro_export]
+macro_rules! obffalse {
+	() => {
+		!$crate::obftrue!()
+	};
+	($e:expr) => {
+		!$crate::obftrue!($e)
+	};
+}
+
+#[test]
+fn test_obfmatch() {
+	fn plain(v: u8) -> i32 {
+		match v {
+			0 => 10,
+			1 | 2 => 20,
+			0x7f => 30,
+			0x80 | 0x81 | 0xff => 40,
+			_ => -1,
+		}
+	}
+	fn obfuscated(v: u8) -> i32 {
+		obfmatch!(v => {
+			0 => 10,
+			1 | 2 => 20,
+			0x7f => 30,
+			0x80 | 0x81 | 0xff => 40,
+			_ => -1,
+		})
+	}
+	for v in 0..=255u8 {
+		assert_eq!(plain(v), obfuscated(v), "{}", v);
+	}
+	let negative = |v: i32| obfmatch!(v => { -1 => true, _ => false });
+	assert!(negative(-1));
+	assert!(!negative(1));
+	let wide = |v: i64| obfmatch!(v => { -1 => 1, 0x7fff_ffff_ffff_ffff => 2, -0x8000_0000_0000_0000 => 3, _ => 0 });
+	for v in [-1, i64::MAX, i64::MIN, 0, 1, u32::MAX as i64, -1 << 32] {
+		let plain = match v { -1 => 1, 0x7fff_ffff_ffff_ffff => 2, -0x8000_0000_0000_0000 => 3, _ => 0 };
+		assert_eq!(wide(v), plain, "{}", v);
+	}
+}
+
 #[test]
 fn test_identical_stmt() {
 	let mut i: u8 = 0;
@@ -103,3 +984,522 @@ fn test_identical_stmt() {
 	obfstmt! {}
 	assert_eq!(i, 4);
 }
+
+#[test]
+fn test_collision() {
+	// Pinned keys under which the unsalted next key of the second statement collides with the initial key
+	const KEY: u64 = 0x42fc29b660b45630;
+	const XOR: u64 = 0x66c3433ee5175ee3;
+	let k0 = KEY ^ XOR;
+	let k1 = k0 ^ crate::murmur3_64(b"i += 1", k0);
+	assert_eq!(k1 ^ crate::murmur3_64(b"i += 1", k1), KEY);
+	const STMTS: [(&str, u64, u64); 3] = generate64::<3>(KEY, XOR, &["i += 1", "i += 1", "i *= 3"]);
+	assert_eq!((STMTS[0].1, STMTS[1].1), (k0, k1));
+	let exit = exit64::<3>(KEY, XOR, &STMTS);
+	let keys = [KEY, STMTS[0].1, STMTS[1].1, STMTS[2].1, exit];
+	for i in 0..keys.len() {
+		for j in i + 1..keys.len() {
+			assert_ne!(keys[i], keys[j], "{} {}", i, j);
+		}
+	}
+	// The statements still execute in order
+	let mut i: u32 = 0;
+	__obfstmt! { KEY, XOR, (0usize;), (0usize), ();
+		i += 1;
+		i += 1;
+		i *= 3;
+	}
+	assert_eq!(i, 6);
+}
+
+#[test]
+fn test_early_return() {
+	fn parse(s: &str, stop: bool) -> Result<i32, core::num::ParseIntError> {
+		let mut value = 0;
+		obfstmt! {
+			value = s.parse::<i32>()?;
+			if stop { return Ok(-value); };
+			value *= 2;
+			return Ok(value + 1);
+		}
+		unreachable!()
+	}
+	assert_eq!(parse("20", false), Ok(41));
+	assert_eq!(parse("20", true), Ok(-20));
+	assert!(parse("x", false).is_err());
+}
+
+#[test]
+fn test_continue_break() {
+	let mut seen = Vec::new();
+	for i in 0..10 {
+		let mut j = 0;
+		obfstmt! {
+			j = i * 10;
+			if i % 2 == 1 { j = -1; };
+			continue;
+		}
+		seen.push(j);
+	}
+	assert!(seen.is_empty());
+	let mut sum = 0;
+	'outer: for i in 0..10 {
+		obfstmt! {
+			if i == 7 { break 'outer; };
+			sum += i;
+		}
+		if i % 2 == 0 {
+			obfstmt! {
+				sum += 100;
+				continue;
+			}
+		}
+		let mut done = false;
+		obfstmt! {
+			done = i == 5;
+			if done { break 'outer; };
+		}
+	}
+	assert_eq!(sum, 1 + 2 + 3 + 4 + 5 + 300);
+	let mut count = 0;
+	loop {
+		obfstmt! {
+			count += 1;
+			break;
+			count += 100;
+		}
+	}
+	assert_eq!(count, 1);
+}
+
+#[test]
+fn test_value() {
+	let mut s = String::new();
+	let mut n = 0;
+	let value: String = obfstmt! {
+		s.push_str("Hello");
+		n = s.len();
+		s.push_str(" world");
+		s
+	};
+	assert_eq!(value, "Hello world");
+	assert_eq!(n, 5);
+	let value = obfstmt! { n * 2 };
+	assert_eq!(value, 10);
+	let mut seen = Vec::new();
+	'outer: for i in 0..4 {
+		let label = obfstmt! {
+			if i == 2 { continue 'outer; };
+			format!("#{}", i)
+		};
+		seen.push(label);
+	}
+	assert_eq!(seen, ["#0", "#1", "#3"]);
+}
+
+#[test]
+fn test_decoys() {
+	fn plain(x: u32) -> u32 {
+		let mut x = x;
+		obfstmt! {
+			x ^= 0x5a5a;
+			x = x.rotate_left(7);
+			x = x.wrapping_mul(9);
+		}
+		x
+	}
+	fn decoyed(x: u32) -> u32 {
+		let mut x = x;
+		obfstmt!(decoys = 16; {
+			x ^= 0x5a5a;
+			x = x.rotate_left(7);
+			x = x.wrapping_mul(9);
+		});
+		x
+	}
+	for x in [0, 1, 0x1234, u32::MAX] {
+		assert_eq!(plain(x), decoyed(x));
+	}
+	let value = obfstmt!(decoys = 32; { String::from("decoys") });
+	assert_eq!(value, "decoys");
+
+	// The decoy keys are distinct from each other and every reachable key
+	const KEY: u64 = 0x1234567;
+	const STMTS: [(&str, u64, u64); 3] = generate64::<3>(KEY, 0x89abcdef, &["a()", "b()", "c()"]);
+	const EXIT: u64 = exit64::<3>(KEY, 0x89abcdef, &STMTS);
+	assert!(decoys64::<3>(KEY, EXIT, &STMTS, 0).iter().all(|d| *d == (0, 0, 0)));
+	let decoys = decoys64::<3>(KEY, EXIT, &STMTS, MAX_DECOYS);
+	let mut keys: Vec<u64> = decoys.iter().map(|d| d.0).collect();
+	keys.extend([KEY, EXIT, STMTS[0].1, STMTS[1].1, STMTS[2].1]);
+	keys.sort_unstable();
+	keys.dedup();
+	assert_eq!(keys.len(), MAX_DECOYS + 5);
+}
+
+#[test]
+fn test_keys64() {
+	let mut x: u32 = 1;
+	obfstmt!(decoys = 32; {
+		x = x.wrapping_mul(31).wrapping_add(0);
+		x = x.wrapping_mul(31).wrapping_add(1);
+		x = x.wrapping_mul(31).wrapping_add(2);
+		x = x.wrapping_mul(31).wrapping_add(3);
+		x = x.wrapping_mul(31).wrapping_add(4);
+		x = x.wrapping_mul(31).wrapping_add(5);
+		x = x.wrapping_mul(31).wrapping_add(6);
+		x = x.wrapping_mul(31).wrapping_add(7);
+		x = x.wrapping_mul(31).wrapping_add(8);
+		x = x.wrapping_mul(31).wrapping_add(9);
+		x = x.wrapping_mul(31).wrapping_add(10);
+		x = x.wrapping_mul(31).wrapping_add(11);
+		x = x.wrapping_mul(31).wrapping_add(12);
+		x = x.wrapping_mul(31).wrapping_add(13);
+		x = x.wrapping_mul(31).wrapping_add(14);
+		x = x.wrapping_mul(31).wrapping_add(15);
+		x = x.wrapping_mul(31).wrapping_add(16);
+		x = x.wrapping_mul(31).wrapping_add(17);
+		x = x.wrapping_mul(31).wrapping_add(18);
+		x = x.wrapping_mul(31).wrapping_add(19);
+		x = x.wrapping_mul(31).wrapping_add(20);
+		x = x.wrapping_mul(31).wrapping_add(21);
+		x = x.wrapping_mul(31).wrapping_add(22);
+		x = x.wrapping_mul(31).wrapping_add(23);
+		x = x.wrapping_mul(31).wrapping_add(24);
+		x = x.wrapping_mul(31).wrapping_add(25);
+		x = x.wrapping_mul(31).wrapping_add(26);
+		x = x.wrapping_mul(31).wrapping_add(27);
+		x = x.wrapping_mul(31).wrapping_add(28);
+		x = x.wrapping_mul(31).wrapping_add(29);
+		x = x.wrapping_mul(31).wrapping_add(30);
+		x = x.wrapping_mul(31).wrapping_add(31);
+		x = x.wrapping_mul(31).wrapping_add(32);
+		x = x.wrapping_mul(31).wrapping_add(33);
+		x = x.wrapping_mul(31).wrapping_add(34);
+		x = x.wrapping_mul(31).wrapping_add(35);
+		x = x.wrapping_mul(31).wrapping_add(36);
+		x = x.wrapping_mul(31).wrapping_add(37);
+		x = x.wrapping_mul(31).wrapping_add(38);
+		x = x.wrapping_mul(31).wrapping_add(39);
+		x = x.wrapping_mul(31).wrapping_add(40);
+		x = x.wrapping_mul(31).wrapping_add(41);
+		x = x.wrapping_mul(31).wrapping_add(42);
+		x = x.wrapping_mul(31).wrapping_add(43);
+		x = x.wrapping_mul(31).wrapping_add(44);
+		x = x.wrapping_mul(31).wrapping_add(45);
+		x = x.wrapping_mul(31).wrapping_add(46);
+		x = x.wrapping_mul(31).wrapping_add(47);
+		x = x.wrapping_mul(31).wrapping_add(48);
+		x = x.wrapping_mul(31).wrapping_add(49);
+		x = x.wrapping_mul(31).wrapping_add(50);
+		x = x.wrapping_mul(31).wrapping_add(51);
+		x = x.wrapping_mul(31).wrapping_add(52);
+		x = x.wrapping_mul(31).wrapping_add(53);
+		x = x.wrapping_mul(31).wrapping_add(54);
+		x = x.wrapping_mul(31).wrapping_add(55);
+		x = x.wrapping_mul(31).wrapping_add(56);
+		x = x.wrapping_mul(31).wrapping_add(57);
+		x = x.wrapping_mul(31).wrapping_add(58);
+		x = x.wrapping_mul(31).wrapping_add(59);
+		x = x.wrapping_mul(31).wrapping_add(60);
+		x = x.wrapping_mul(31).wrapping_add(61);
+		x = x.wrapping_mul(31).wrapping_add(62);
+		x = x.wrapping_mul(31).wrapping_add(63);
+	});
+	assert_eq!(x, (0..64).fold(1u32, |x, i| x.wrapping_mul(31).wrapping_add(i)));
+
+	const KEY: u64 = 0x0123456789abcdef;
+	const STMTS: [(&str, u64, u64); 4] = generate64::<4>(KEY, 0xfedcba9876543210, &["i += 1", "i += 1", "i += 1", "i += 1"]);
+	let exit = exit64::<4>(KEY, 0xfedcba9876543210, &STMTS);
+	let mut keys: Vec<u64> = decoys64::<4>(KEY, exit, &STMTS, MAX_DECOYS).iter().map(|d| d.0).collect();
+	keys.extend([KEY, exit, STMTS[0].1, STMTS[1].1, STMTS[2].1, STMTS[3].1]);
+	assert!(keys.iter().all(|&key| key >> 32 != 0));
+	keys.sort_unstable();
+	keys.dedup();
+	assert_eq!(keys.len(), MAX_DECOYS + 6);
+}
+
+#[test]
+fn test_nested() {
+	let mut trace = Vec::new();
+	obfstmt! {
+		trace.push(1);
+		obfstmt! {
+			trace.push(2);
+			trace.push(3);
+			trace.push(4);
+		};
+		trace.push(5);
+	}
+	assert_eq!(trace, [1, 2, 3, 4, 5]);
+
+	let mut trace = Vec::new();
+	let mut n = 0;
+	let sum: i32 = obfstmt!(decoys = 8; {
+		trace.push(1);
+		n = obfstmt! {
+			trace.push(2);
+			trace.push(3);
+			4
+		};
+		trace.push(n);
+		obfstmt!(decoys = 4; {
+			trace.push(5);
+			trace.push(6);
+			trace.push(7);
+		});
+		trace.iter().sum()
+	});
+	assert_eq!(trace, [1, 2, 3, 4, 5, 6, 7]);
+	assert_eq!(sum, 28);
+}
+
+#[test]
+fn test_opaque() {
+	assert!(obftrue!());
+	assert!(!obffalse!());
+	let mut x = 0x243f6a88u32;
+	let mut y = 0x85a308d3u32;
+	for _ in 0..10000 {
+		x = x.wrapping_mul(0x9e3779b9).wrapping_add(0x7f4a7c15);
+		y ^= y << 13; y ^= y >> 17; y ^= y << 5;
+		assert!(opaque::<0>(x, y) && opaque::<1>(x, y) && opaque::<2>(x, y) && opaque::<3>(x, y), "{:#x} {:#x}", x, y);
+		assert!(obftrue!(x));
+		assert!(!obffalse!(x));
+		assert!(obftrue!(x as i8));
+	}
+	let edges = [0, 1, 2, 3, 5, 0x7fffffff, 0x80000000, u32::MAX - 1, u32::MAX];
+	for x in edges {
+		for y in edges {
+			assert!(opaque::<0>(x, y) && opaque::<1>(x, y) && opaque::<2>(x, y) && opaque::<3>(x, y), "{:#x} {:#x}", x, y);
+		}
+	}
+}
+
+#[test]
+fn test_obfbranch() {
+	fn plain(x: i32, log: &mut Vec<i32>) -> String {
+		if x % 3 == 0 { log.push(x); format!("fizz{}", x) } else { log.push(-x); x.to_string() }
+	}
+	fn obfuscated(x: i32, log: &mut Vec<i32>) -> String {
+		obfbranch!(x % 3 == 0, { log.push(x); format!("fizz{}", x) }, { log.push(-x); x.to_string() })
+	}
+	let (mut a, mut b) = (Vec::new(), Vec::new());
+	for x in -10..10 {
+		assert_eq!(plain(x, &mut a), obfuscated(x, &mut b));
+	}
+	assert_eq!(a, b);
+
+	// The condition is evaluated exactly once
+	let mut calls = 0;
+	let mut cond = |v: bool| { calls += 1; v };
+	let mut taken = Vec::new();
+	obfbranch!(cond(true), { taken.push("then"); }, { taken.push("else"); });
+	obfbranch!(cond(false), { taken.push("then"); }, { taken.push("else"); });
+	assert_eq!(calls, 2);
+	assert_eq!(taken, ["then", "else"]);
+
+	// Identical branches, early return and labeled break
+	let same = |c: bool| -> i32 { obfbranch!(c, { 1 }, { 1 }) };
+	assert_eq!(same(true) + same(false), 2);
+	let early = |c: bool| -> i32 { obfbranch!(c, { return 10; }, {}); 20 };
+	assert_eq!(early(true), 10);
+	assert_eq!(early(false), 20);
+	let mut n = 0;
+	'outer: loop {
+		n += 1;
+		obfbranch!(n == 3, { break 'outer; }, { continue 'outer; });
+	}
+	assert_eq!(n, 3);
+}
+
+#[test]
+fn test_obfloop() {
+	fn plain(items: &[i32]) -> (i32, usize) {
+		let mut acc = 0;
+		let mut iterations = 0;
+		for &x in items {
+			iterations += 1;
+			if x < 0 {
+				continue;
+			}
+			if x > 100 {
+				break;
+			}
+			acc += x;
+		}
+		(acc, iterations)
+	}
+	fn obfuscated(items: &[i32]) -> (i32, usize) {
+		let mut acc = 0;
+		let mut iterations = 0;
+		obfloop!(decoys = 8; for &x in items {
+			iterations += 1;
+			if x < 0 {
+				continue;
+			}
+			if x > 100 {
+				break;
+			}
+			acc += x;
+		});
+		(acc, iterations)
+	}
+	let inputs: [&[i32]; 4] = [&[], &[1, 2, 3], &[5, -1, 7, 200, 9], &[-5, -6, 101]];
+	for items in inputs {
+		assert_eq!(plain(items), obfuscated(items));
+	}
+
+	// While loop with nested loops and labeled break
+	let mut i = 0;
+	let mut pairs = Vec::new();
+	'outer: for _ in 0..3 {
+		obfloop!(while i < 10 {
+			i += 1;
+			for j in 0..i {
+				if j == 2 {
+					break;
+				}
+				pairs.push((i, j));
+			}
+			if i == 4 {
+				break 'outer;
+			}
+		});
+		pairs.push((0, 99));
+	}
+	assert_eq!(pairs, [(1, 0), (2, 0), (2, 1), (3, 0), (3, 1), (4, 0), (4, 1)]);
+
+	// Owned items are moved into the body
+	let mut words = Vec::new();
+	obfloop!(for s in vec![String::from("a"), String::from("b")] {
+		words.push(s);
+	});
+	assert_eq!(words, ["a", "b"]);
+}
+
+#[cfg(panic = "unwind")]
+#[test]
+#[cfg_attr(not(debug_assertions), ignore = "the trace is only checked in debug builds")]
+fn test_key_mismatch() {
+	let result = std::panic::catch_unwind(|| {
+		CORRUPT_KEY.with(|corrupt| corrupt.set(0x5a5a5a5a_5a5a5a5a));
+		let mut i = 0;
+		obfstmt! {
+			i += 1;
+			i += 2;
+			i += 3;
+		}
+		i
+	});
+	CORRUPT_KEY.with(|corrupt| corrupt.set(0));
+	let message = *result.unwrap_err().downcast::<String>().unwrap();
+	assert!(message.starts_with("obfstmt: no state matches key 0x"), "{}", message);
+	assert!(message.contains("expected exit 0x"), "{}", message);
+	assert!(message.contains("after statements ["), "{}", message);
+}
+
+#[test]
+#[cfg_attr(not(debug_assertions), ignore = "the keys are only observed in debug builds")]
+fn test_seed() {
+	const SEED: u64 = 0x1234_5678;
+	KEY_LOG.with(|log| *log.borrow_mut() = Some(Vec::new()));
+	let mut i = 0;
+	obfstmt!(seed = 0x1234_5678; {
+		i += 1;
+		i *= 10;
+		i -= 3;
+	});
+	let log = KEY_LOG.with(|log| log.borrow_mut().take()).unwrap();
+	assert_eq!(i, 7);
+
+	// Recompute the table and step the state machine manually
+	const XOR: u64 = crate::murmur3_64(b"XOR", SEED);
+	const STMTS: [(&str, u64, u64); 3] = generate64::<3>(SEED, XOR, &["i += 1", "i *= 10", "i -= 3"]);
+	let (mut key, mut xor) = (SEED, XOR);
+	let mut expected = Vec::new();
+	for &(_, stmt_key, stmt_xor) in &STMTS {
+		key ^= xor;
+		assert_eq!(key, stmt_key);
+		expected.push(key);
+		xor = stmt_xor;
+	}
+	key ^= xor;
+	assert_eq!(key, exit64::<3>(SEED, XOR, &STMTS));
+	expected.push(key);
+	assert_eq!(log, expected);
+}
+
+#[test]
+#[cfg_attr(not(debug_assertions), ignore = "the keys are only patched in debug builds")]
+fn test_tamper() {
+	std::thread_local! {
+		static TAMPERED: core::cell::Cell<u32> = const { core::cell::Cell::new(0) };
+	}
+	fn on_tamper() {
+		TAMPERED.with(|tampered| tampered.set(tampered.get() + 1));
+	}
+	fn run() -> i32 {
+		let mut i = 0;
+		obfstmt!(seed = 0xfeed; tamper = on_tamper; {
+			i += 1;
+			i *= 10;
+			i -= 3;
+		});
+		i
+	}
+	fn value() -> String {
+		let mut s = String::new();
+		obfstmt!(seed = 0xbeef; decoys = 4; tamper = on_tamper; {
+			s.push('a');
+			s.push('b');
+			s
+		})
+	}
+	// Record the keys of the statements in order, the decoys are never visited
+	KEY_LOG.with(|log| *log.borrow_mut() = Some(Vec::new()));
+	assert_eq!(run(), 7);
+	let run_keys = KEY_LOG.with(|log| log.borrow_mut().replace(Vec::new())).unwrap();
+	assert_eq!(value(), "ab");
+	let value_keys = KEY_LOG.with(|log| log.borrow_mut().take()).unwrap();
+	assert_eq!(TAMPERED.with(|tampered| tampered.get()), 0);
+
+	// Skip the second statement by patching its key to the key of the third statement
+	KEY_PATCH.with(|patch| patch.set(Some((run_keys[1], run_keys[2]))));
+	assert_eq!(run(), -2);
+	KEY_PATCH.with(|patch| patch.set(None));
+	assert_eq!(TAMPERED.with(|tampered| tampered.get()), 1);
+
+	// Skip the first statement of the value block
+	KEY_PATCH.with(|patch| patch.set(Some((value_keys[0], value_keys[1]))));
+	assert_eq!(value(), "b");
+	KEY_PATCH.with(|patch| patch.set(None));
+	assert_eq!(TAMPERED.with(|tampered| tampered.get()), 2);
+}
+
+#[test]
+fn test_junk() {
+	fn plain(x: u64) -> u64 {
+		let mut x = x;
+		obfstmt! {
+			x = x.wrapping_mul(0x9e3779b97f4a7c15);
+			x ^= x >> 29;
+			x = x.rotate_left(17);
+		}
+		x
+	}
+	fn junked(x: u64) -> u64 {
+		let mut x = x;
+		obfstmt!(decoys = 2; junk = 4; {
+			x = x.wrapping_mul(0x9e3779b97f4a7c15);
+			x ^= x >> 29;
+			x = x.rotate_left(17);
+		});
+		x
+	}
+	for x in [0, 1, 42, u64::MAX] {
+		assert_eq!(plain(x), junked(x));
+	}
+	let value = obfstmt!(junk = 8; { String::from("junk") });
+	assert_eq!(value, "junk");
+	assert_eq!(junk::<0x1234, 0>(5), 5);
+}
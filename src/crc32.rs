@@ -0,0 +1,116 @@
+/*!
+CRC-32, the checksum of zlib, PNG and PE tooling.
+*/
+
+const POLY: u32 = 0xedb88320;
+
+// Lookup tables for slice-by-4, the first table is the classic byte-at-a-time table.
+const TABLES: [[u32; 256]; 4] = tables();
+
+const fn tables() -> [[u32; 256]; 4] {
+	let mut tables = [[0u32; 256]; 4];
+	let mut i = 0;
+	while i < 256 {
+		let mut crc = i as u32;
+		let mut j = 0;
+		while j < 8 {
+			crc = if crc & 1 != 0 { crc >> 1 ^ POLY } else { crc >> 1 };
+			j += 1;
+		}
+		tables[0][i] = crc;
+		i += 1;
+	}
+	let mut i = 0;
+	while i < 256 {
+		let mut t = 1;
+		while t < 4 {
+			let prev = tables[t - 1][i];
+			tables[t][i] = prev >> 8 ^ tables[0][(prev & 0xff) as usize];
+			t += 1;
+		}
+		i += 1;
+	}
+	return tables;
+}
+
+/// Compiletime CRC-32 checksum.
+///
+/// Accepts a string or a byte string, guarantees compiletime evaluation of the checksum.
+///
+/// ```
+/// const CRC: u32 = obfstr::crc32!("123456789");
+/// assert_eq!(CRC, 0xcbf43926);
+/// assert_eq!(obfstr::crc32!(b"123456789"), 0xcbf43926);
+/// ```
+#[macro_export]
+macro_rules! crc32 {
+	($s:expr) => {{ const _CRC32_HASH: u32 = $crate::crc32($crate::AsBytes($s).bytes()); _CRC32_HASH }};
+}
+
+/// CRC-32 checksum.
+///
+/// The reflected IEEE 802.3 polynomial `0xedb88320` with initial value and final xor `0xffffffff`, the same as zlib's `crc32`.
+/// The lookup table is generated at compiletime.
+///
+/// See [`crc32_fast`] for a faster version which is not const.
+///
+/// ```
+/// assert_eq!(obfstr::crc32(b""), 0);
+/// assert_eq!(obfstr::crc32(b"The quick brown fox jumps over the lazy dog"), 0x414fa339);
+/// ```
+pub const fn crc32(bytes: &[u8]) -> u32 {
+	let mut crc = !0u32;
+	let mut i = 0usize;
+	while i < bytes.len() {
+		crc = crc >> 8 ^ TABLES[0][((crc ^ bytes[i] as u32) & 0xff) as usize];
+		i += 1;
+	}
+	return !crc;
+}
+
+/// CRC-32 checksum processing 4 bytes at a time.
+///
+/// Produces the same result as [`crc32`](crc32()).
+#[inline]
+pub fn crc32_fast(bytes: &[u8]) -> u32 {
+	let mut crc = !0u32;
+	let mut chunks = bytes.chunks_exact(4);
+	for chunk in &mut chunks {
+		crc ^= u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+		crc = TABLES[3][(crc & 0xff) as usize] ^
+			TABLES[2][(crc >> 8 & 0xff) as usize] ^
+			TABLES[1][(crc >> 16 & 0xff) as usize] ^
+			TABLES[0][(crc >> 24) as usize];
+	}
+	for &byte in chunks.remainder() {
+		crc = crc >> 8 ^ TABLES[0][((crc ^ byte as u32) & 0xff) as usize];
+	}
+	return !crc;
+}
+
+#[test]
+fn test_zlib() {
+	// Expected values computed with zlib's crc32
+	static TEST_VECTORS: [(u32, &[u8]); 6] = [
+		(0x00000000, b""),
+		(0xe8b7be43, b"a"),
+		(0x2144df1c, &[0, 0, 0, 0]),
+		(0xcbf43926, b"123456789"),
+		(0x414fa339, b"The quick brown fox jumps over the lazy dog"),
+		(0x352441c2, b"abc"),
+	];
+	for &(expected, input) in TEST_VECTORS.iter() {
+		assert_eq!(crc32(input), expected);
+		assert_eq!(crc32_fast(input), expected);
+	}
+
+	// Pseudorandom 1 KiB buffer
+	let buf: Vec<u8> = (0..1024).map(|i| crate::splitmix(i) as u8).collect();
+	assert_eq!(crc32(&buf), 0xd2d889ba);
+	assert_eq!(crc32_fast(&buf), 0xd2d889ba);
+	assert_eq!(crc32_fast(&buf[..1023]), 0xda6637bf);
+	for len in 0..16 {
+		assert_eq!(crc32_fast(&buf[..len]), crc32(&buf[..len]));
+	}
+	assert_eq!(crc32!("abc"), 0x352441c2);
+}
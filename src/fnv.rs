@@ -0,0 +1,106 @@
+/*!
+FNV-1a, the Fowler–Noll–Vo hash function.
+*/
+
+const OFFSET32: u32 = 0x811c9dc5;
+const PRIME32: u32 = 0x01000193;
+const OFFSET64: u64 = 0xcbf29ce484222325;
+const PRIME64: u64 = 0x00000100000001b3;
+
+/// Compiletime FNV-1a 32-bit hash.
+///
+/// Accepts a string or a byte string, guarantees compiletime evaluation of the hash.
+///
+/// ```
+/// const HASH: u32 = obfstr::fnv1a32!("foobar");
+/// assert_eq!(HASH, 0xbf9cf968);
+/// assert_eq!(obfstr::fnv1a32!(b"foobar"), 0xbf9cf968);
+/// ```
+#[macro_export]
+macro_rules! fnv1a32 {
+	($s:expr) => {{ const _FNV1A_HASH: u32 = $crate::fnv1a32($crate::AsBytes($s).bytes()); _FNV1A_HASH }};
+}
+
+/// Compiletime FNV-1a 64-bit hash.
+///
+/// Accepts a string or a byte string, guarantees compiletime evaluation of the hash.
+///
+/// ```
+/// const HASH: u64 = obfstr::fnv1a64!("foobar");
+/// assert_eq!(HASH, 0x85944171f73967e8);
+/// assert_eq!(obfstr::fnv1a64!(b"foobar"), 0x85944171f73967e8);
+/// ```
+#[macro_export]
+macro_rules! fnv1a64 {
+	($s:expr) => {{ const _FNV1A_HASH: u64 = $crate::fnv1a64($crate::AsBytes($s).bytes()); _FNV1A_HASH }};
+}
+
+/// FNV-1a 32-bit hash function.
+///
+/// Uses the standard offset basis `0x811c9dc5` and prime `0x01000193`.
+///
+/// The hash functions of this crate compared:
+///
+/// | Function | Output | Keyed | Notes |
+/// |----------|--------|-------|-------|
+/// | [`hash`](crate::hash()) | `u32` | No | DJB2 xor variant, used internally to derive the compiletime entropy |
+/// | [`hash64`](crate::hash64()) | `u64` | No | DJB2 xor variant with fewer collisions for longer strings |
+/// | [`fnv1a32`](fnv1a32()) | `u32` | No | Standard FNV-1a, interoperates with other tools |
+/// | [`fnv1a64`](fnv1a64()) | `u64` | No | Standard FNV-1a, interoperates with other tools |
+/// | [`crc32`](crate::crc32()) | `u32` | No | CRC-32 checksum, interoperates with zlib and PE tooling |
+/// | [`xxhash32`](crate::xxhash32()) | `u32` | Yes | xxHash32, fast with good distribution for longer inputs |
+/// | [`murmur3`](crate::murmur3()) | `u32` | Yes | MurmurHash3_x86_32, best distribution, used by [`hash_eq!`](crate::hash_eq!) |
+/// | [`siphash13`](crate::siphash13()) | `u64` | Yes | SipHash-1-3, resists crafted collisions when the key is secret |
+///
+/// ```
+/// assert_eq!(obfstr::fnv1a32(b""), 0x811c9dc5);
+/// assert_eq!(obfstr::fnv1a32(b"a"), 0xe40c292c);
+/// ```
+#[inline(always)]
+pub const fn fnv1a32(bytes: &[u8]) -> u32 {
+	let mut result = OFFSET32;
+	let mut i = 0usize;
+	while i < bytes.len() {
+		result = (result ^ bytes[i] as u32).wrapping_mul(PRIME32);
+		i += 1;
+	}
+	return result;
+}
+
+/// FNV-1a 64-bit hash function.
+///
+/// Uses the standard offset basis `0xcbf29ce484222325` and prime `0x100000001b3`.
+///
+/// ```
+/// assert_eq!(obfstr::fnv1a64(b""), 0xcbf29ce484222325);
+/// assert_eq!(obfstr::fnv1a64(b"a"), 0xaf63dc4c8601ec8c);
+/// ```
+#[inline(always)]
+pub const fn fnv1a64(bytes: &[u8]) -> u64 {
+	let mut result = OFFSET64;
+	let mut i = 0usize;
+	while i < bytes.len() {
+		result = (result ^ bytes[i] as u64).wrapping_mul(PRIME64);
+		i += 1;
+	}
+	return result;
+}
+
+#[test]
+fn test_vectors() {
+	// Test vectors from the FNV reference implementation
+	static TEST_VECTORS: [(u32, u64, &[u8]); 7] = [
+		(0x811c9dc5, 0xcbf29ce484222325, b""),
+		(0xe40c292c, 0xaf63dc4c8601ec8c, b"a"),
+		(0xe70c2de5, 0xaf63df4c8601f1a5, b"b"),
+		(0xbf9cf968, 0x85944171f73967e8, b"foobar"),
+		(0x050c5d1f, 0xaf63bd4c8601b7df, b"\0"),
+		(0x2b24d044, 0x089be207b544f1e4, b"a\0"),
+		(0x0c1c9eb8, 0x34531ca7168b8f38, b"foobar\0"),
+	];
+	for &(expected32, expected64, input) in TEST_VECTORS.iter() {
+		assert_eq!(fnv1a32(input), expected32);
+		assert_eq!(fnv1a64(input), expected64);
+	}
+	assert_eq!(fnv1a32!("foobar"), fnv1a32!(b"foobar" as &[u8]));
+}
@@ -0,0 +1,162 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use crate::ObfStr;
+
+#[cfg(feature = "std")]
+use std::sync::Once;
+#[cfg(not(feature = "std"))]
+use core::{hint, sync::atomic::{AtomicU8, Ordering}};
+
+#[cfg(not(feature = "std"))]
+const UNINIT: u8 = 0;
+#[cfg(not(feature = "std"))]
+const BUSY: u8 = 1;
+#[cfg(not(feature = "std"))]
+const READY: u8 = 2;
+
+/// Obfuscated string constant which is deobfuscated once on first access.
+///
+/// Constructed with [`obflazy!`]. For APIs which require a `&'static str`.
+///
+/// The string is deobfuscated into a buffer inside the static on the first call to [`ObfLazy::get`].
+/// **The plaintext then lives in memory for the rest of the process**, prefer [`obfstr!`] where a temporary suffices.
+///
+/// ```
+/// use obfstr::{obflazy, ObfLazy};
+///
+/// static BANNER: ObfLazy<20> = obflazy!("Welcome to obfstr!!!");
+///
+/// let banner: &'static str = BANNER.get();
+/// assert_eq!(banner, "Welcome to obfstr!!!");
+/// ```
+pub struct ObfLazy<const LEN: usize> {
+	obf: ObfStr<LEN>,
+	buf: UnsafeCell<[u8; LEN]>,
+	#[cfg(feature = "std")]
+	once: Once,
+	#[cfg(not(feature = "std"))]
+	state: AtomicU8,
+}
+
+// Safety: the buffer is only written once before it is published
+unsafe impl<const LEN: usize> Sync for ObfLazy<LEN> {}
+
+impl<const LEN: usize> ObfLazy<LEN> {
+	/// Wraps the obfuscated string constant.
+	#[inline(always)]
+	pub const fn new(obf: ObfStr<LEN>) -> ObfLazy<LEN> {
+		ObfLazy {
+			obf,
+			buf: UnsafeCell::new([0u8; LEN]),
+			#[cfg(feature = "std")]
+			once: Once::new(),
+			#[cfg(not(feature = "std"))]
+			state: AtomicU8::new(UNINIT),
+		}
+	}
+
+	/// Returns the length of the string in bytes.
+	#[inline(always)]
+	pub const fn len(&self) -> usize {
+		LEN
+	}
+
+	/// Returns `true` if the string is empty.
+	#[inline(always)]
+	pub const fn is_empty(&self) -> bool {
+		LEN == 0
+	}
+
+	/// Returns the deobfuscated string, deobfuscating it on first access.
+	///
+	/// Concurrent first accesses wait until the string is deobfuscated exactly once.
+	#[inline]
+	pub fn get(&'static self) -> &'static str {
+		self.init();
+		// Safety: the buffer is no longer written to after initialization
+		crate::unsafe_as_str(unsafe { &*self.buf.get() })
+	}
+
+	#[cfg(feature = "std")]
+	#[inline]
+	fn init(&self) {
+		self.once.call_once(|| self.decode());
+	}
+
+	#[cfg(not(feature = "std"))]
+	#[inline]
+	fn init(&self) {
+		if self.state.load(Ordering::Acquire) != READY {
+			self.init_slow();
+		}
+	}
+
+	#[cfg(not(feature = "std"))]
+	#[cold]
+	#[inline(never)]
+	fn init_slow(&self) {
+		loop {
+			match self.state.compare_exchange_weak(UNINIT, BUSY, Ordering::Acquire, Ordering::Acquire) {
+				Ok(_) => {
+					self.decode();
+					self.state.store(READY, Ordering::Release);
+					return;
+				},
+				Err(READY) => return,
+				Err(_) => hint::spin_loop(),
+			}
+		}
+	}
+
+	// Safety: must only be called once by the thread which won the initialization
+	fn decode(&self) {
+		self.obf.deobfuscate(unsafe { &mut *self.buf.get() });
+	}
+}
+
+impl<const LEN: usize> fmt::Debug for ObfLazy<LEN> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		// Do not leak the string through debug formatting
+		f.debug_struct("ObfLazy").field("len", &LEN).finish()
+	}
+}
+
+/// Compiletime string constant obfuscation, deobfuscated once into a `&'static str`.
+///
+/// Constructs an [`ObfLazy`] which can be used in statics.
+///
+/// ```
+/// static TARGET: obfstr::ObfLazy<11> = obfstr::obflazy!("app::secret");
+/// assert_eq!(TARGET.get(), "app::secret");
+/// ```
+#[macro_export]
+macro_rules! obflazy {
+	($s:expr) => {
+		$crate::ObfLazy::new($crate::obf!($s))
+	};
+}
+
+#[test]
+#[cfg_attr(target_family = "wasm", ignore = "threads are not supported")]
+fn test_concurrent_get() {
+	use std::sync::Barrier;
+	static SECRET: ObfLazy<24> = obflazy!("Lazily deobfuscated once");
+	static BARRIER: Barrier = Barrier::new(8);
+	let threads: Vec<_> = (0..8).map(|_| std::thread::spawn(|| {
+		BARRIER.wait();
+		SECRET.get()
+	})).collect();
+	for thread in threads {
+		let s = thread.join().unwrap();
+		assert_eq!(s, "Lazily deobfuscated once");
+		assert_eq!(s.as_ptr(), SECRET.get().as_ptr());
+	}
+	assert_eq!(format!("{:?}", SECRET), "ObfLazy { len: 24 }");
+}
+
+#[test]
+fn test_empty() {
+	static EMPTY: ObfLazy<0> = obflazy!("");
+	assert!(EMPTY.is_empty());
+	assert_eq!(EMPTY.get(), "");
+}
@@ -2,22 +2,44 @@
 Compiletime string constant obfuscation.
 */
 
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+#![allow(clippy::needless_return, clippy::identity_op, clippy::tabs_in_doc_comments, clippy::ptr_offset_with_cast, clippy::len_zero, clippy::manual_is_multiple_of, clippy::match_ref_pats)]
+#![cfg_attr(test, allow(unknown_lints, unnecessary_transmutes, clippy::toplevel_ref_arg, clippy::manual_range_contains))]
 
 use core::str;
 use core::ffi::CStr;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 #[doc(hidden)]
 pub mod wide;
 
-#[doc(hidden)]
 pub mod cfo;
 
 mod murmur3;
-pub use self::murmur3::murmur3;
+pub use self::murmur3::{MURMUR3_SEED, Murmur3Hasher, murmur3, murmur3_64, murmur3_nocase, murmur3_utf16, murmur3_utf16_nocase, murmur3_wide, murmur3_wide_nocase, murmur3_words, murmur3_words_nocase};
+
+#[cfg(feature = "api-hash")]
+pub mod api;
+
+mod case;
+pub use self::case::{to_ascii_lower, to_ascii_lower_wide, to_ascii_upper, to_ascii_upper_wide};
+
+mod crc32;
+pub use self::crc32::{crc32, crc32_fast};
+
+mod fnv;
+pub use self::fnv::{fnv1a32, fnv1a64};
+
+mod siphash;
+pub use self::siphash::{SIPHASH_KEYS, siphash13, siphash24};
+
+mod xxhash;
+pub use self::xxhash::xxhash32;
 
 mod pos;
-pub use self::pos::position;
+pub use self::pos::{pool_concat, pool_get, pool_pack, pool_range, pool_str, position, position_all, position_char, position_bytes, position_in_segments, position_wide, rposition, try_position};
 
 #[doc(hidden)]
 pub mod xref;
@@ -26,16 +48,65 @@ pub mod xref;
 
 /// Compiletime random number generator.
 ///
-/// Supported types are `u8`, `u16`, `u32`, `u64`, `usize`, `i8`, `i16`, `i32`, `i64`, `isize`, `bool`, `f32` and `f64`.
+/// Supported types are `u8`, `u16`, `u32`, `u64`, `u128`, `usize`, `i8`, `i16`, `i32`, `i64`, `i128`, `isize`, `bool`, `char`, `f32` and `f64`.
 ///
 /// The integer types generate a random value in their respective range.  
-/// The float types generate a random value in range of `[1.0, 2.0)`.
+/// The float types generate a random value in range of `[1.0, 2.0)`, use `random!(f32 in 0.0..1.0)` for a value uniform in `[0.0, 1.0)`.  
+/// The `char` type generates a uniformly chosen valid char, never a surrogate code point.
 ///
 /// ```
 /// const RND: i32 = obfstr::random!(u8) as i32;
 /// assert!(RND >= 0 && RND <= 255);
-/// # const _: f32 = obfstr::random!(f32);
-/// # const _: f64 = obfstr::random!(f64);
+/// const FLOAT: f32 = obfstr::random!(f32);
+/// assert!(FLOAT >= 1.0 && FLOAT < 2.0);
+/// ```
+///
+/// The integer and float types accept a range to generate a value in, exclusive `lo..hi` or inclusive `lo..=hi`.
+/// The integers are drawn with rejection sampling and have no modulo bias:
+///
+/// ```
+/// const JITTER: u32 = obfstr::random!(u32 in 10..100);
+/// assert!(JITTER >= 10 && JITTER < 100);
+/// const OFFSET: i8 = obfstr::random!(i8 in -4..=4, "offset");
+/// assert!(OFFSET >= -4 && OFFSET <= 4);
+/// ```
+///
+/// The floats map the `[1.0, 2.0)` value linearly to the range, the bounds must be finite:
+///
+/// ```
+/// const UNIT: f64 = obfstr::random!(f64 in 0.0..1.0);
+/// assert!(UNIT >= 0.0 && UNIT < 1.0);
+/// const ANGLE: f32 = obfstr::random!(f32 in -180.0..180.0);
+/// assert!(ANGLE >= -180.0 && ANGLE < 180.0);
+/// ```
+///
+/// ```compile_fail
+/// const NAN: f32 = obfstr::random!(f32 in 0.0..f32::NAN);
+/// ```
+///
+/// Empty or inverted ranges fail to compile:
+///
+/// ```compile_fail
+/// const EMPTY: u32 = obfstr::random!(u32 in 10..10);
+/// ```
+///
+/// Arrays of the integer types up to 64 bits expand every element from a single seed with a [`splitmix`] chain.
+/// The elements of arrays of 8 and 16-bit types are distinct as long as the type has enough values:
+///
+/// ```
+/// static IV: [u8; 16] = obfstr::random!([u8; 16]);
+/// const MASKS: [u64; 4] = obfstr::random!([u64; 4], "masks");
+/// assert_ne!(IV, obfstr::random!([u8; 16]));
+/// assert_ne!(MASKS[0], MASKS[1]);
+/// ```
+///
+/// The 128-bit types chain [`splitmix`] twice over the 64-bit entropy of the call site.
+/// The high half is `splitmix(entropy)` and the low half is `splitmix` of the high half:
+///
+/// ```
+/// const MASK: u128 = obfstr::random!(u128);
+/// assert_ne!(MASK, obfstr::random!(u128));
+/// assert_ne!(obfstr::random!(i128), obfstr::random!(i128));
 /// ```
 ///
 /// The behavior of the macro inside other macros can be surprising:
@@ -86,6 +157,148 @@ macro_rules! random {
 			$crate::entropy(concat!(file!(), ":", line!(), ":", column!() $(, ":", $seeds)*)));
 		_RANDOM
 	}};
+	([$ty:ident; $len:expr] $(, $seeds:expr)* $(,)?) => {{
+		const _RANDOM_LEN: usize = $len;
+		const _RANDOM_WORDS: [u64; _RANDOM_LEN] = $crate::random_array::<_RANDOM_LEN>(
+			$crate::entropy(concat!(file!(), ":", line!(), ":", column!() $(, ":", $seeds)*)),
+			$crate::__random_bits!($ty));
+		const _RANDOM: [$ty; _RANDOM_LEN] = {
+			let mut array = [0; _RANDOM_LEN];
+			let mut i = 0;
+			while i < _RANDOM_LEN {
+				array[i] = _RANDOM_WORDS[i] as $ty;
+				i += 1;
+			}
+			array
+		};
+		_RANDOM
+	}};
+	($ty:ident in $($range:tt)+) => {
+		$crate::__random_range!($ty [] $($range)+)
+	};
+}
+
+/// Compiletime random identifier-safe string.
+///
+/// Returns a `&'static str` of `len` random characters from `[A-Za-z0-9_]`.
+/// Accepts additional seeds like [`random!`].
+///
+/// ```
+/// const MARKER: &str = obfstr::random_ascii!(12);
+/// assert_eq!(MARKER.len(), 12);
+/// assert!(MARKER.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'_'));
+/// assert_ne!(MARKER, obfstr::random_ascii!(12));
+/// assert_eq!(obfstr::random_ascii!(0), "");
+/// ```
+#[macro_export]
+macro_rules! random_ascii {
+	($len:expr $(, $seeds:expr)* $(,)?) => {{
+		const _RANDOM_LEN: usize = $len;
+		const _RANDOM_ASCII: [u8; _RANDOM_LEN] = $crate::random_ascii::<_RANDOM_LEN>(
+			$crate::entropy(concat!(file!(), ":", line!(), ":", column!() $(, ":", $seeds)*)));
+		$crate::unsafe_as_str(&_RANDOM_ASCII)
+	}};
+}
+
+/// Compiletime array shuffle.
+///
+/// Returns the elements of the array in a random order, permuted with a Fisher–Yates shuffle seeded by the call site.
+/// Works for arrays of any `Copy` elements and can be used in const and static initializers.
+/// Accepts additional seeds like [`random!`].
+///
+/// ```
+/// const TABLE: [u8; 4] = obfstr::shuffle!([1, 2, 3, 4]);
+/// let mut sorted = TABLE;
+/// sorted.sort();
+/// assert_eq!(sorted, [1, 2, 3, 4]);
+/// ```
+///
+/// Shuffle the order of entries in a pool with `obfpool! { #[shuffle] ... }`, see [`obfpool!`].
+#[macro_export]
+macro_rules! shuffle {
+	($array:expr $(, $seeds:expr)* $(,)?) => {
+		$crate::shuffle($array, $crate::random!(u64, "shuffle" $(, $seeds)*))
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __random_range {
+	($ty:ident [$($lo:tt)+] ..= $hi:expr $(, $seeds:expr)* $(,)?) => {
+		$crate::__random_range!(@ $ty, true, $($lo)+, $hi $(, $seeds)*)
+	};
+	($ty:ident [$($lo:tt)+] .. $hi:expr $(, $seeds:expr)* $(,)?) => {
+		$crate::__random_range!(@ $ty, false, $($lo)+, $hi $(, $seeds)*)
+	};
+	($ty:ident [$($lo:tt)*] $next:tt $($rest:tt)*) => {
+		$crate::__random_range!($ty [$($lo)* $next] $($rest)*)
+	};
+	(@ f32, $inclusive:literal, $lo:expr, $hi:expr $(, $seeds:expr)*) => {
+		$crate::__random_range!(@float f32, $inclusive, $lo, $hi $(, $seeds)*)
+	};
+	(@ f64, $inclusive:literal, $lo:expr, $hi:expr $(, $seeds:expr)*) => {
+		$crate::__random_range!(@float f64, $inclusive, $lo, $hi $(, $seeds)*)
+	};
+	(@float $ty:ident, $inclusive:literal, $lo:expr, $hi:expr $(, $seeds:expr)*) => {{
+		const _RANDOM_LO: $ty = $lo;
+		const _RANDOM_HI: $ty = $hi;
+		const _RANDOM: $ty = {
+			assert!(_RANDOM_LO.is_finite() && _RANDOM_HI.is_finite(), "random range is not finite");
+			assert!(if $inclusive { _RANDOM_LO <= _RANDOM_HI } else { _RANDOM_LO < _RANDOM_HI }, "empty random range");
+			let unit = $crate::__random_cast!($ty,
+				$crate::entropy(concat!(file!(), ":", line!(), ":", column!() $(, ":", $seeds)*))) - 1.0;
+			// Interpolate without overflowing on ranges wider than the type
+			let value = _RANDOM_LO * (1.0 - unit) + _RANDOM_HI * unit;
+			// Rounding may land on the bounds
+			if value < _RANDOM_LO || !$inclusive && value >= _RANDOM_HI { _RANDOM_LO } else if value > _RANDOM_HI { _RANDOM_HI } else { value }
+		};
+		_RANDOM
+	}};
+	(@ $ty:ident, $inclusive:literal, $lo:expr, $hi:expr $(, $seeds:expr)*) => {{
+		const _RANDOM_LO: $ty = $crate::__random_int!($ty, $lo);
+		const _RANDOM_HI: $ty = $hi;
+		const _RANDOM: $ty = {
+			assert!(if $inclusive { _RANDOM_LO <= _RANDOM_HI } else { _RANDOM_LO < _RANDOM_HI }, "empty random range");
+			let span = (_RANDOM_HI as u128).wrapping_sub(_RANDOM_LO as u128) - !$inclusive as u128;
+			let seed = $crate::entropy(concat!(file!(), ":", line!(), ":", column!() $(, ":", $seeds)*));
+			(_RANDOM_LO as u128).wrapping_add($crate::random_range(seed, span)) as $ty
+		};
+		_RANDOM
+	}};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __random_bits {
+	(u8) => { 8 };
+	(u16) => { 16 };
+	(u32) => { 32 };
+	(u64) => { 64 };
+	(usize) => { usize::BITS };
+	(i8) => { 8 };
+	(i16) => { 16 };
+	(i32) => { 32 };
+	(i64) => { 64 };
+	(isize) => { isize::BITS };
+	($ty:ident) => { compile_error!(concat!("unsupported array type: ", stringify!($ty))) };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __random_int {
+	(u8, $e:expr) => { $e };
+	(u16, $e:expr) => { $e };
+	(u32, $e:expr) => { $e };
+	(u64, $e:expr) => { $e };
+	(u128, $e:expr) => { $e };
+	(usize, $e:expr) => { $e };
+	(i8, $e:expr) => { $e };
+	(i16, $e:expr) => { $e };
+	(i32, $e:expr) => { $e };
+	(i64, $e:expr) => { $e };
+	(i128, $e:expr) => { $e };
+	(isize, $e:expr) => { $e };
+	($ty:ident, $e:expr) => { compile_error!(concat!("unsupported range type: ", stringify!($ty))) };
 }
 
 #[doc(hidden)]
@@ -95,18 +308,23 @@ macro_rules! __random_cast {
 	(u16, $seed:expr) => { $seed as u16 };
 	(u32, $seed:expr) => { $seed as u32 };
 	(u64, $seed:expr) => { $seed };
+	(u128, $seed:expr) => { $crate::__random_cast!(@128 $crate::splitmix($seed)) };
 	(usize, $seed:expr) => { $seed as usize };
 	(i8, $seed:expr) => { $seed as i8 };
 	(i16, $seed:expr) => { $seed as i16 };
 	(i32, $seed:expr) => { $seed as i32 };
 	(i64, $seed:expr) => { $seed as i64 };
+	(i128, $seed:expr) => { $crate::__random_cast!(@128 $crate::splitmix($seed)) as i128 };
 	(isize, $seed:expr) => { $seed as isize };
 	(bool, $seed:expr) => { $seed as i64 >= 0 };
+	(char, $seed:expr) => { $crate::random_char($seed) };
 
 	// {f32, f64}::from_bits is unstable as const fn due to issues with NaN
 	(f32, $seed:expr) => { unsafe { ::core::mem::transmute::<u32, f32>(0b0_01111111 << (f32::MANTISSA_DIGITS - 1) | ($seed as u32 >> 9)) } };
 	(f64, $seed:expr) => { unsafe { ::core::mem::transmute::<u64, f64>(0b0_01111111111 << (f64::MANTISSA_DIGITS - 1) | ($seed >> 12)) } };
 
+	(@128 $hi:expr) => { ($hi as u128) << 64 | $crate::splitmix($hi) as u128 };
+
 	($ty:ident, $seed:expr) => { compile_error!(concat!("unsupported type: ", stringify!($ty))) };
 }
 
@@ -136,6 +354,180 @@ fn test_random_f64() {
 	t(r!(f64));t(r!(f64));t(r!(f64));t(r!(f64));t(r!(f64));t(r!(f64));t(r!(f64));t(r!(f64));
 }
 
+#[test]
+fn test_random_128() {
+	use random as r;
+	let unsigned = [r!(u128), r!(u128), r!(u128), r!(u128), r!(u128), r!(u128), r!(u128), r!(u128)];
+	let signed = [r!(i128), r!(i128), r!(i128), r!(i128), r!(i128), r!(i128), r!(i128), r!(i128)];
+	// Both halves are populated
+	assert!(unsigned.iter().any(|&v| v > u64::MAX as u128));
+	assert!(unsigned.iter().all(|&v| v >> 64 != 0 && v as u64 != 0));
+	// Both signs are generated
+	assert!(signed.iter().any(|&v| v < 0) && signed.iter().any(|&v| v > 0));
+	assert!(signed.iter().any(|&v| v > i64::MAX as i128 || v < i64::MIN as i128));
+	// The construction chains splitmix over the entropy
+	const SEED: u64 = 0x0123456789abcdef;
+	let hi = splitmix(SEED);
+	assert_eq!(__random_cast!(u128, SEED), (hi as u128) << 64 | splitmix(hi) as u128);
+	assert_eq!(__random_cast!(i128, SEED) as u128, __random_cast!(u128, SEED));
+}
+
+#[test]
+fn test_random_range() {
+	#[track_caller]
+	fn t<T: PartialOrd + core::fmt::Debug>(v: T, lo: T, hi: T) {
+		assert!(v >= lo && v <= hi, "{:?} not in {:?}..={:?}", v, lo, hi);
+	}
+	use random as r;
+	t(r!(u8 in 10..20), 10, 19);
+	t(r!(u8 in 10..20), 10, 19);
+	t(r!(u8 in 10..20), 10, 19);
+	t(r!(u8 in 10..=20), 10, 20);
+	t(r!(u8 in 10..=20), 10, 20);
+	t(r!(u8 in 0..=255), 0, 255);
+	t(r!(u16 in 1000..1001), 1000, 1000);
+	t(r!(u32 in 10..100), 10, 99);
+	t(r!(u32 in 10..100), 10, 99);
+	t(r!(u32 in 10..100), 10, 99);
+	t(r!(u32 in 7..=7), 7, 7);
+	t(r!(u64 in 1 << 40..1 << 41), 1 << 40, (1 << 41) - 1);
+	t(r!(u64 in 0..=u64::MAX), 0, u64::MAX);
+	t(r!(u128 in u128::MAX - 3..=u128::MAX), u128::MAX - 3, u128::MAX);
+	t(r!(usize in 1..=4), 1, 4);
+	t(r!(i8 in -128..=127), -128, 127);
+	t(r!(i8 in -4..=4), -4, 4);
+	t(r!(i8 in -4..=4), -4, 4);
+	t(r!(i16 in -3..-2), -3, -3);
+	t(r!(i32 in -1000..1000), -1000, 999);
+	t(r!(i32 in -1000..1000), -1000, 999);
+	t(r!(i64 in i64::MIN..0), i64::MIN, -1);
+	t(r!(i128 in i128::MIN..=i128::MAX), i128::MIN, i128::MAX);
+	t(r!(isize in -2..=-1, "seed"), -2, -1);
+
+	// Sample many call sites, every value in a small range shows up
+	let values = [
+		r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4),
+		r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4),
+		r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4),
+		r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4), r!(u8 in 0..4),
+	];
+	for i in 0..4 {
+		assert!(values.contains(&i), "{:?}", values);
+	}
+	assert!(values.iter().all(|&v| v < 4));
+
+	// The sampling itself stays within the span
+	for seed in 0..1000 {
+		assert!(random_range(seed, 0) == 0);
+		assert!(random_range(seed, 2) <= 2);
+		assert!(random_range(seed, 1 << 100) <= 1 << 100);
+	}
+}
+
+#[test]
+fn test_random_array() {
+	const BYTES: [u8; 16] = random!([u8; 16]);
+	let other: [u8; 16] = random!([u8; 16]);
+	for (i, a) in BYTES.iter().enumerate() {
+		assert!(!BYTES[i + 1..].contains(a), "{:?}", BYTES);
+	}
+	assert_ne!(BYTES, other);
+	assert_ne!(random!([u32; 8], "a"), random!([u32; 8], "b"));
+	assert_ne!(random!([u64; 2])[0], random!([u64; 2])[1]);
+	assert_eq!(random!([i16; 0]), []);
+
+	// Every value of a small type shows up exactly once
+	let mut all = random!([u8; 256]);
+	all.sort_unstable();
+	assert!(all.iter().enumerate().all(|(i, &v)| v as usize == i));
+	let mut all = random!([i8; 256]);
+	all.sort_unstable();
+	assert!(all.iter().enumerate().all(|(i, &v)| v as isize == i as isize - 128));
+}
+
+#[test]
+fn test_random_char() {
+	use random as r;
+	let chars = [r!(char), r!(char), r!(char), r!(char), r!(char), r!(char), r!(char), r!(char)];
+	for (i, chr) in chars.iter().enumerate() {
+		assert!(!chars[i + 1..].contains(chr), "{:?}", chars);
+	}
+	// The surrogate code points are skipped
+	let max = char::MAX as u128 - 0x800;
+	for seed in 0..10000 {
+		let chr = random_char(seed) as u32;
+		assert!(!(0xd800..0xe000).contains(&chr));
+		assert_eq!(chr as u128, random_range(seed, max) + if chr >= 0xe000 { 0x800 } else { 0 });
+	}
+}
+
+#[test]
+fn test_random_ascii() {
+	fn check(s: &str, len: usize) {
+		assert_eq!(s.len(), len);
+		assert!(s.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'_'), "{}", s);
+	}
+	let strings = [random_ascii!(12), random_ascii!(12), random_ascii!(12), random_ascii!(12, "seed")];
+	for (i, s) in strings.iter().enumerate() {
+		check(s, 12);
+		assert!(!strings[i + 1..].contains(s), "{:?}", strings);
+	}
+	check(random_ascii!(0), 0);
+	check(random_ascii!(1), 1);
+	// Every character of the alphabet shows up
+	let long = random_ascii!(4096);
+	check(long, 4096);
+	for c in b"AZaz09_".iter() {
+		assert!(long.as_bytes().contains(c));
+	}
+}
+
+#[test]
+fn test_shuffle() {
+	const INPUT: [u32; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+	const SHUFFLED: [u32; 16] = shuffle!(INPUT);
+	let other = shuffle!(INPUT);
+	assert_ne!(SHUFFLED, INPUT);
+	assert_ne!(SHUFFLED, other);
+	for shuffled in [SHUFFLED, other, shuffle!(INPUT, "seed")] {
+		let mut sorted = shuffled;
+		sorted.sort_unstable();
+		assert_eq!(sorted, INPUT);
+	}
+	assert_eq!(shuffle!([0u8; 0]), []);
+	assert_eq!(shuffle!(["single"]), ["single"]);
+	// Every permutation of a small array shows up
+	let mut seen = Vec::new();
+	for seed in 0..100 {
+		let p = shuffle(['a', 'b', 'c'], seed);
+		if !seen.contains(&p) {
+			seen.push(p);
+		}
+	}
+	assert_eq!(seen.len(), 6);
+}
+
+#[test]
+fn test_random_float_range() {
+	#[track_caller]
+	fn t<T: PartialOrd + core::fmt::Debug>(v: T, lo: T, hi: T, inclusive: bool) {
+		assert!(v >= lo && (v < hi || inclusive && v == hi), "{:?} not in {:?}..{:?}", v, lo, hi);
+	}
+	use random as r;
+	t(r!(f32 in 0.0..1.0), 0.0, 1.0, false);
+	t(r!(f32 in 0.0..1.0), 0.0, 1.0, false);
+	t(r!(f32 in 0.0..1.0), 0.0, 1.0, false);
+	t(r!(f64 in 0.0..1.0), 0.0, 1.0, false);
+	t(r!(f64 in 0.0..1.0), 0.0, 1.0, false);
+	t(r!(f64 in 0.0..1.0), 0.0, 1.0, false);
+	t(r!(f32 in -180.0..180.0), -180.0, 180.0, false);
+	t(r!(f64 in -1e-9..=1e-9), -1e-9, 1e-9, true);
+	t(r!(f32 in 2.5..=2.5), 2.5, 2.5, true);
+	t(r!(f32 in f32::MIN..f32::MAX), f32::MIN, f32::MAX, false);
+	t(r!(f64 in f64::MIN..=f64::MAX, "seed"), f64::MIN, f64::MAX, true);
+	t(r!(f64 in 1.0..1.0000000000000002), 1.0, 1.0000000000000002, false);
+}
+
 /// Compiletime bitmixing.
 ///
 /// Takes an intermediate hash that may not be thoroughly mixed and increase its entropy to obtain both better distribution.
@@ -149,12 +541,112 @@ pub const fn splitmix(seed: u64) -> u64 {
 	return z ^ (z >> 31);
 }
 
+/// Expands the seed into an array of random words with a splitmix chain.
+///
+/// For types of at most 16 bits the low `bits` of the words are distinct if there are enough values.
+#[doc(hidden)]
+pub const fn random_array<const LEN: usize>(seed: u64, bits: u32) -> [u64; LEN] {
+	let distinct = bits <= 16 && LEN <= 1 << bits;
+	let mut seen = [0u64; 1024];
+	let mut words = [0u64; LEN];
+	let mut state = seed;
+	let mut i = 0;
+	while i < LEN {
+		state = splitmix(state);
+		if distinct {
+			// Draw again if the value was already seen
+			let value = (state & ((1 << bits) - 1)) as usize;
+			if seen[value / 64] & 1 << (value % 64) != 0 {
+				continue;
+			}
+			seen[value / 64] |= 1 << (value % 64);
+		}
+		words[i] = state;
+		i += 1;
+	}
+	return words;
+}
+
+/// Maps the seed to a valid char, skipping over the surrogate code points.
+#[doc(hidden)]
+pub const fn random_char(seed: u64) -> char {
+	const SURROGATES: u32 = 0xe000 - 0xd800;
+	let mut value = random_range(seed, (char::MAX as u32 - SURROGATES) as u128) as u32;
+	if value >= 0xd800 {
+		value += SURROGATES;
+	}
+	match char::from_u32(value) {
+		Some(chr) => chr,
+		None => panic!("invalid char"),
+	}
+}
+
+/// Expands the seed into a string of random characters from `[A-Za-z0-9_]`.
+#[doc(hidden)]
+pub const fn random_ascii<const LEN: usize>(seed: u64) -> [u8; LEN] {
+	const ALPHABET: &[u8; 63] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789_";
+	let mut string = [0u8; LEN];
+	let mut state = seed;
+	let mut i = 0;
+	while i < LEN {
+		state = splitmix(state);
+		// Draw again for the 64th value of the top 6 bits
+		let index = (state >> 58) as usize;
+		if index < ALPHABET.len() {
+			string[i] = ALPHABET[index];
+			i += 1;
+		}
+	}
+	return string;
+}
+
+/// Shuffles the array with Fisher–Yates driven by a splitmix chain from the seed.
+#[doc(hidden)]
+pub const fn shuffle<T: Copy, const LEN: usize>(mut array: [T; LEN], seed: u64) -> [T; LEN] {
+	let mut state = seed;
+	let mut i = LEN;
+	while i > 1 {
+		state = splitmix(state);
+		let j = random_range(state, (i - 1) as u128) as usize;
+		i -= 1;
+		array.swap(i, j);
+	}
+	return array;
+}
+
+/// Draws a value in range `[0, span]` from the seed with rejection sampling.
+#[doc(hidden)]
+pub const fn random_range(seed: u64, span: u128) -> u128 {
+	let mut state = seed;
+	loop {
+		let hi = splitmix(state);
+		state = splitmix(hi);
+		let value = (hi as u128) << 64 | state as u128;
+		if span == u128::MAX {
+			return value;
+		}
+		// Reject the values in the incomplete last bucket
+		let n = span + 1;
+		if value <= u128::MAX - (u128::MAX - span) % n {
+			return value % n;
+		}
+	}
+}
+
 /// Compiletime string constant hash.
 ///
 /// Implemented using the [DJB2 hash function](http://www.cse.yorku.ca/~oz/hash.html#djb2) xor variation.
+/// See [`fnv1a32`](fnv1a32()) for a comparison of the hash functions.
 #[inline(always)]
 pub const fn hash(s: &str) -> u32 {
-	let s = s.as_bytes();
+	hash_bytes(s.as_bytes())
+}
+
+/// Compiletime byte string constant hash.
+///
+/// The DJB2 xor variation of [`hash`](hash()) over bytes.
+#[inline(always)]
+pub const fn hash_bytes(s: &[u8]) -> u32 {
 	let mut result = 3581u32;
 	let mut i = 0usize;
 	while i < s.len() {
@@ -164,6 +656,40 @@ pub const fn hash(s: &str) -> u32 {
 	return result;
 }
 
+/// Compiletime wide string constant hash.
+///
+/// The DJB2 xor variation of [`hash`](hash()) over words, every word is mixed in as a whole in a single step.
+/// The hash of a wide string equals the hash of the string if all its characters are ASCII.
+#[inline(always)]
+pub const fn hash_words(s: &[u16]) -> u32 {
+	let mut result = 3581u32;
+	let mut i = 0usize;
+	while i < s.len() {
+		result = result.wrapping_mul(33) ^ s[i] as u32;
+		i += 1;
+	}
+	return result;
+}
+
+/// Compiletime string constant 64-bit hash.
+///
+/// The DJB2 xor variation of [`hash`](hash()) with a 64-bit accumulator.
+///
+/// The low 32 bits are equal to [`hash`](hash()), strings which collide in the 32-bit hash only collide in the 64-bit hash if their high halves do too.
+/// The high half is only filled after the first four characters, short strings benefit little from the wider hash.
+/// About 2<sup>32</sup> strings are expected before the first collision instead of 2<sup>16</sup>, as long as the strings are not crafted to collide.
+#[inline(always)]
+pub const fn hash64(s: &str) -> u64 {
+	let s = s.as_bytes();
+	let mut result = 3581u64;
+	let mut i = 0usize;
+	while i < s.len() {
+		result = result.wrapping_mul(33) ^ s[i] as u64;
+		i += 1;
+	}
+	return result;
+}
+
 /// Compiletime string constant hash.
 ///
 /// Helper macro guarantees compiletime evaluation of the string constant hash.
@@ -172,9 +698,144 @@ pub const fn hash(s: &str) -> u32 {
 /// const STRING: &str = "Hello World";
 /// assert_eq!(obfstr::hash!(STRING), 0x6E4A573D);
 /// ```
+///
+/// Byte strings are hashed with [`hash_bytes`] and wide strings, either a `&[u16]` or a string literal encoded with [`wide!`], with [`hash_words`]:
+///
+/// ```
+/// assert_eq!(obfstr::hash!(b"\x00\xff\x10"), 0x07ABBA12);
+/// assert_eq!(obfstr::hash!(wide "Wide 🌍"), 0x2AA8F233);
+/// assert_eq!(obfstr::hash!(wide obfstr::wide!("Hello World")), 0x6E4A573D);
+/// ```
 #[macro_export]
 macro_rules! hash {
-	($s:expr) => {{ const _DJB2_HASH: u32 = $crate::hash($s); _DJB2_HASH }};
+	(wide $s:literal) => {
+		$crate::hash!(wide $crate::wide!($s))
+	};
+	(wide $s:expr) => {{ const _DJB2_HASH: u32 = $crate::hash_words($s); _DJB2_HASH }};
+	($s:expr) => {{ const _DJB2_HASH: u32 = $crate::hash_bytes($crate::AsBytes($s).bytes()); _DJB2_HASH }};
+}
+
+/// Compiletime string constant 64-bit hash.
+///
+/// Helper macro guarantees compiletime evaluation of the string constant hash.
+///
+/// ```
+/// const STRING: &str = "Hello World";
+/// assert_eq!(obfstr::hash64!(STRING), 0xD0E2CA0C6E4A573D);
+/// ```
+#[macro_export]
+macro_rules! hash64 {
+	($s:expr) => {{ const _DJB2_HASH: u64 = $crate::hash64($s); _DJB2_HASH }};
+}
+
+#[test]
+fn test_hash() {
+	// These values are stable across releases
+	assert_eq!(hash(""), 0x00000DFD);
+	assert_eq!(hash("Hello World"), 0x6E4A573D);
+	assert_eq!(hash_bytes(b"Hello World"), 0x6E4A573D);
+	assert_eq!(hash_bytes(&[0x00, 0xff, 0x10]), 0x07ABBA12);
+	assert_eq!(hash_words(&[]), 0x00000DFD);
+	assert_eq!(hash_words(wide!("Hello World")), 0x6E4A573D);
+	assert_eq!(hash_words(wide!("Wide 🌍")), 0x2AA8F233);
+	assert_eq!(hash!(b"\x00\xff\x10" as &[u8]), 0x07ABBA12);
+	assert_eq!(hash!(wide &[0x57, 0x69, 0x64, 0x65, 0x20, 0xd83c, 0xdf0d]), 0x2AA8F233);
+
+	assert_eq!(hash64(""), 0x0000000000000DFD);
+	assert_eq!(hash64("Hello World"), 0xD0E2CA0C6E4A573D);
+	assert_eq!(hash64!("Hello World") as u32, hash("Hello World"));
+}
+
+/// Converts strings and byte strings to bytes in the hash macros.
+#[doc(hidden)]
+pub struct AsBytes<T>(pub T);
+impl<'a> AsBytes<&'a str> {
+	#[inline(always)]
+	pub const fn bytes(self) -> &'a [u8] {
+		self.0.as_bytes()
+	}
+}
+impl<'a> AsBytes<&'a [u8]> {
+	#[inline(always)]
+	pub const fn bytes(self) -> &'a [u8] {
+		self.0
+	}
+}
+impl<'a, const N: usize> AsBytes<&'a [u8; N]> {
+	#[inline(always)]
+	pub const fn bytes(self) -> &'a [u8] {
+		self.0
+	}
+}
+
+/// Compares a string against the hash of a string constant.
+///
+/// Only the [`murmur3`](murmur3()) hash of the string constant is embedded, keyed with a per call site random key.
+/// The string constant itself does not exist in the binary in any form.
+///
+/// Different syntax forms are supported:
+///
+/// * `hash_eq!(s, "lit")` where `s` is a `&str`.
+/// * `hash_eq!(nocase s, "lit")` ignores ASCII case.
+/// * `hash_eq!(wide s, "lit")` where `s` is a `&[u16]`, hashed as if it were UTF-8 encoded.
+/// * `hash_eq!(wide nocase s, "lit")` combines both.
+/// * `hash_eq!(xxhash32 s, "lit")` hashes with [`xxhash32`](xxhash32()) instead, where `s` is a `&str`.
+///
+/// Since only a 32-bit hash is compared, there is a small chance of false positives (about 1 in 2<sup>32</sup> for unrelated inputs).
+/// Do not use this where an attacker controls the input and a false positive has consequences.
+///
+/// ```
+/// assert!(obfstr::hash_eq!("SeDebugPrivilege", "SeDebugPrivilege"));
+/// assert!(!obfstr::hash_eq!("SeShutdownPrivilege", "SeDebugPrivilege"));
+/// assert!(obfstr::hash_eq!(nocase "SEDEBUGPRIVILEGE", "SeDebugPrivilege"));
+/// assert!(obfstr::hash_eq!(wide obfstr::wide!("SeDebugPrivilege"), "SeDebugPrivilege"));
+/// ```
+#[macro_export]
+macro_rules! hash_eq {
+	(wide nocase $e:expr, $s:expr) => {
+		$crate::__hash_eq!(murmur3_utf16_nocase, murmur3_nocase, $e, $s)
+	};
+	(wide $e:expr, $s:expr) => {
+		$crate::__hash_eq!(murmur3_utf16, murmur3, $e, $s)
+	};
+	(xxhash32 $e:expr, $s:expr) => {
+		$crate::__hash_eq!(xxhash32, xxhash32, ::core::primitive::str::as_bytes($e), $s)
+	};
+	(nocase $e:expr, $s:expr) => {
+		$crate::__hash_eq!(murmur3_nocase, murmur3_nocase, ::core::primitive::str::as_bytes($e), $s)
+	};
+	($e:expr, $s:expr) => {
+		$crate::__hash_eq!(murmur3, murmur3, ::core::primitive::str::as_bytes($e), $s)
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __hash_eq {
+	($f:ident, $g:ident, $e:expr, $s:expr) => {{
+		const _HASH_EQ_KEY: u32 = $crate::random!(u32, "hash_eq", stringify!($s));
+		const _HASH_EQ_HASH: u32 = $crate::$g(::core::primitive::str::as_bytes($s), _HASH_EQ_KEY);
+		$crate::$f($e, _HASH_EQ_KEY) == _HASH_EQ_HASH
+	}};
+}
+
+#[test]
+fn test_hash_eq() {
+	static DICTIONARY: [&str; 16] = [
+		"SeAssignPrimaryTokenPrivilege", "SeAuditPrivilege", "SeBackupPrivilege", "SeChangeNotifyPrivilege",
+		"SeCreateGlobalPrivilege", "SeCreatePagefilePrivilege", "SeDebugprivilege", "SeDebugPrivilege",
+		"SeDebugPrivilegf", "SeImpersonatePrivilege", "SeLoadDriverPrivilege", "SeRestorePrivilege",
+		"SeSecurityPrivilege", "SeShutdownPrivilege", "SeTcbPrivilege", "",
+	];
+	let matches = DICTIONARY.iter().filter(|&&word| hash_eq!(word, "SeDebugPrivilege")).count();
+	assert_eq!(matches, 1);
+	let matches = DICTIONARY.iter().filter(|&&word| hash_eq!(nocase word, "SEDEBUGPRIVILEGE")).count();
+	assert_eq!(matches, 2);
+	for &word in DICTIONARY.iter() {
+		let wide: Vec<u16> = word.encode_utf16().collect();
+		assert_eq!(hash_eq!(wide &wide, "SeDebugPrivilege"), word == "SeDebugPrivilege");
+		assert_eq!(hash_eq!(wide nocase &wide, "sedebugprivilege"), word.eq_ignore_ascii_case("SeDebugPrivilege"));
+	}
 }
 
 /// Produces pseudorandom entropy from the given string.
@@ -190,6 +851,70 @@ pub const fn entropy(string: &str) -> u64 {
 /// If it changes all downstream dependents are recompiled automatically.
 pub const SEED: u64 = splitmix(hash(match option_env!("OBFSTR_SEED") { Some(seed) => seed, None => "FIXED" }) as u64);
 
+/// Number of mixing rounds of the key stream.
+///
+/// Only used with the `mixed-keystream` feature enabled.
+/// This value is derived from the environment variable `OBFSTR_ROUNDS` and defaults to `2` if absent.
+pub const ROUNDS: u32 = match option_env!("OBFSTR_ROUNDS") { Some(rounds) => parse_rounds(rounds), None => 2 };
+
+const fn parse_rounds(s: &str) -> u32 {
+	let s = s.as_bytes();
+	let mut result = 0u32;
+	let mut i = 0;
+	while i < s.len() {
+		if !s[i].is_ascii_digit() {
+			panic!("OBFSTR_ROUNDS must be a decimal number");
+		}
+		result = result * 10 + (s[i] - b'0') as u32;
+		i += 1;
+	}
+	if result < 2 || result > 64 {
+		panic!("OBFSTR_ROUNDS must be in range 2..=64");
+	}
+	return result;
+}
+
+/// Runtime key expected by the keyed obfuscation forms such as `obfstr!(rk => "literal")`.
+///
+/// The keyed strings are encrypted under both the compiletime key stream and this key, they only decode correctly if the same key is provided at runtime.
+/// This value is derived from the environment variable `OBFSTR_RUNTIME_KEY` as a decimal or `0x` prefixed hexadecimal number and defaults to `0` if absent.
+/// It is only used at compiletime, the binary does not contain it unless referenced elsewhere.
+pub const RUNTIME_KEY: u64 = match option_env!("OBFSTR_RUNTIME_KEY") { Some(key) => parse_runtime_key(key), None => 0 };
+
+const fn parse_runtime_key(s: &str) -> u64 {
+	let s = s.as_bytes();
+	let (radix, mut i) = if s.len() > 2 && s[0] == b'0' && (s[1] == b'x' || s[1] == b'X') { (16, 2) } else { (10, 0) };
+	if i >= s.len() {
+		panic!("OBFSTR_RUNTIME_KEY must not be empty");
+	}
+	let mut result = 0u64;
+	while i < s.len() {
+		let digit = match s[i] {
+			b'0'..=b'9' => s[i] - b'0',
+			b'a'..=b'f' if radix == 16 => s[i] - b'a' + 10,
+			b'A'..=b'F' if radix == 16 => s[i] - b'A' + 10,
+			_ => panic!("OBFSTR_RUNTIME_KEY must be a decimal or hexadecimal number"),
+		};
+		result = match result.checked_mul(radix) {
+			Some(result) => match result.checked_add(digit as u64) {
+				Some(result) => result,
+				None => panic!("OBFSTR_RUNTIME_KEY does not fit in 64 bits"),
+			},
+			None => panic!("OBFSTR_RUNTIME_KEY does not fit in 64 bits"),
+		};
+		i += 1;
+	}
+	return result;
+}
+
+#[test]
+fn test_parse_runtime_key() {
+	assert_eq!(parse_runtime_key("0"), 0);
+	assert_eq!(parse_runtime_key("12345"), 12345);
+	assert_eq!(parse_runtime_key("0xDEADbeef"), 0xdeadbeef);
+	assert_eq!(parse_runtime_key("18446744073709551615"), u64::MAX);
+}
+
 //----------------------------------------------------------------
 
 #[doc(hidden)]
@@ -198,6 +923,22 @@ pub mod bytes;
 #[doc(hidden)]
 pub mod words;
 
+#[doc(hidden)]
+pub mod lzss;
+
+mod obf;
+pub use self::obf::ObfStr;
+
+#[cfg(any(feature = "std", target_has_atomic = "8"))]
+mod lazy;
+#[cfg(any(feature = "std", target_has_atomic = "8"))]
+pub use self::lazy::ObfLazy;
+
+#[cfg(feature = "alloc")]
+mod obfbox;
+#[cfg(feature = "alloc")]
+pub use self::obfbox::ObfBox;
+
 #[doc(hidden)]
 #[inline(always)]
 pub const fn unsafe_as_str(bytes: &[u8]) -> &str {
@@ -0,0 +1,227 @@
+/*!
+Compressed string obfuscation
+=============================
+*/
+
+/// Compiletime string constant obfuscation with compression.
+///
+/// The string constant is compressed with a simple LZSS scheme before it is obfuscated, this reduces the size of long and repetitive strings such as help texts.
+/// At runtime the string is deobfuscated and decompressed, either into a temporary or into a caller provided buffer of at least [`decompressed_len`](crate::lzss::decompressed_len) bytes:
+///
+/// ```
+/// const HELP: &str = "usage: tool [options]\n  -h, --help     prints the help\n  -v, --version  prints the version\n";
+/// const HELP_LEN: usize = obfstr::lzss::decompressed_len(HELP.as_bytes());
+///
+/// assert_eq!(obfstr::obfstr_compressed!(HELP), HELP);
+///
+/// let mut buf = [0u8; HELP_LEN];
+/// assert_eq!(obfstr::obfstr_compressed!(buf <- HELP), HELP);
+/// ```
+#[macro_export]
+macro_rules! obfstr_compressed {
+	($buf:ident <- $s:expr) => {
+		$crate::unsafe_as_str($crate::lzss::decompress(&$crate::__obfbytes_compressed!(::core::primitive::str::as_bytes($s)), &mut $buf[..]))
+	};
+	($s:expr) => {
+		$crate::unsafe_as_str(&$crate::lzss::decompress_array::<{$crate::lzss::decompressed_len(::core::primitive::str::as_bytes($s))}>(&$crate::__obfbytes_compressed!(::core::primitive::str::as_bytes($s))))
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbytes_compressed {
+	($s:expr) => {{
+		// Compress once into a buffer large enough for any input and truncate it to the compressed length
+		const _OBFBYTES_PACKED: $crate::lzss::Packed<{$crate::lzss::compress_bound($s.len())}> = $crate::lzss::pack($s);
+		$crate::__obfbytes!(&$crate::lzss::unpack::<{_OBFBYTES_PACKED.len}, {$crate::lzss::compress_bound($s.len())}>(&_OBFBYTES_PACKED))
+	}};
+}
+
+// Matches are encoded as a distance in 1..=256 and a length in 3..=258, both fit in a byte.
+const WINDOW: usize = 256;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+// Every group of up to 8 tokens is preceded by a flags byte, a set bit marks a match token.
+// Writes the compressed bytes if the output is not empty and returns the compressed length.
+const fn encode(s: &[u8], out: &mut [u8]) -> usize {
+	let write = out.len() != 0;
+	let mut pos = 0;
+	let mut o = 0;
+	let mut flags = 0;
+	let mut bit = 8;
+	while pos < s.len() {
+		if bit == 8 {
+			flags = o;
+			if write {
+				out[o] = 0;
+			}
+			o += 1;
+			bit = 0;
+		}
+		// Find the longest match in the window
+		let mut best_len = 0;
+		let mut best_dist = 0;
+		let mut cand = pos.saturating_sub(WINDOW);
+		while cand < pos {
+			let mut len = 0;
+			while len < MAX_MATCH && pos + len < s.len() && s[cand + len] == s[pos + len] {
+				len += 1;
+			}
+			if len > best_len {
+				best_len = len;
+				best_dist = pos - cand;
+			}
+			cand += 1;
+		}
+		if best_len >= MIN_MATCH {
+			if write {
+				out[flags] |= 1 << bit;
+				out[o + 0] = (best_dist - 1) as u8;
+				out[o + 1] = (best_len - MIN_MATCH) as u8;
+			}
+			o += 2;
+			pos += best_len;
+		}
+		else {
+			if write {
+				out[o] = s[pos];
+			}
+			o += 1;
+			pos += 1;
+		}
+		bit += 1;
+	}
+	return o;
+}
+
+/// Returns the compressed length of the string.
+pub const fn compressed_len(s: &[u8]) -> usize {
+	return encode(s, &mut []);
+}
+
+/// Compresses the string, `CLEN` must equal [`compressed_len`].
+pub const fn compress<const CLEN: usize>(s: &[u8]) -> [u8; CLEN] {
+	let mut out = [0u8; CLEN];
+	if encode(s, &mut out) != CLEN {
+		panic!("compressed len mismatch");
+	}
+	return out;
+}
+
+/// Returns the upper bound of the compressed length of a string of the given length.
+///
+/// Every group of 8 bytes costs at most one extra flags byte.
+pub const fn compress_bound(len: usize) -> usize {
+	return len + len.div_ceil(8);
+}
+
+/// Compressed bytes in a buffer of [`compress_bound`] bytes.
+#[doc(hidden)]
+pub struct Packed<const CAP: usize> {
+	pub data: [u8; CAP],
+	pub len: usize,
+}
+
+/// Compresses the string in a single pass, `CAP` must be at least [`compress_bound`].
+#[doc(hidden)]
+pub const fn pack<const CAP: usize>(s: &[u8]) -> Packed<CAP> {
+	let mut data = [0u8; CAP];
+	let len = encode(s, &mut data);
+	return Packed { data, len };
+}
+
+/// Truncates the packed bytes to the compressed length, `CLEN` must equal `packed.len`.
+#[doc(hidden)]
+pub const fn unpack<const CLEN: usize, const CAP: usize>(packed: &Packed<CAP>) -> [u8; CLEN] {
+	if packed.len != CLEN {
+		panic!("compressed len mismatch");
+	}
+	let mut out = [0u8; CLEN];
+	let mut i = 0;
+	while i < CLEN {
+		out[i] = packed.data[i];
+		i += 1;
+	}
+	return out;
+}
+
+/// Returns the decompressed length of the string.
+///
+/// This is the size of the buffer required by the `buf <-` form of [`obfstr_compressed!`](crate::obfstr_compressed).
+pub const fn decompressed_len(s: &[u8]) -> usize {
+	return s.len();
+}
+
+/// Decompresses into the buffer and returns the decompressed bytes.
+///
+/// Panics if the buffer is too small.
+pub fn decompress<'a>(src: &[u8], buf: &'a mut [u8]) -> &'a [u8] {
+	let mut i = 0;
+	let mut o = 0;
+	while i < src.len() {
+		let flags = src[i];
+		i += 1;
+		let mut bit = 0;
+		while bit < 8 && i < src.len() {
+			if flags & (1 << bit) != 0 {
+				let dist = src[i + 0] as usize + 1;
+				let len = src[i + 1] as usize + MIN_MATCH;
+				// Copy byte by byte, the match may overlap the output
+				for j in o..o + len {
+					buf[j] = buf[j - dist];
+				}
+				o += len;
+				i += 2;
+			}
+			else {
+				buf[o] = src[i];
+				o += 1;
+				i += 1;
+			}
+			bit += 1;
+		}
+	}
+	return &buf[..o];
+}
+
+/// Decompresses into a temporary array of the decompressed length.
+#[inline(always)]
+pub fn decompress_array<const LEN: usize>(src: &[u8]) -> [u8; LEN] {
+	let mut buf = [0u8; LEN];
+	let len = decompress(src, &mut buf).len();
+	assert_eq!(len, LEN);
+	return buf;
+}
+
+#[test]
+fn test_roundtrip() {
+	fn test(s: &[u8]) {
+		let mut out = vec![0u8; compressed_len(s)];
+		assert_eq!(encode(s, &mut out), out.len());
+		assert!(out.len() <= compress_bound(s.len()));
+		let mut buf = vec![0u8; s.len()];
+		assert_eq!(decompress(&out, &mut buf), s);
+	}
+	test(b"");
+	test(b"a");
+	test(b"abcabcabcabcabcabc");
+	test(&[b'x'; 1000]);
+	test("Hello 🌍 Hello 🌍 Hello 🌍".as_bytes());
+	// Incompressible input grows by one flags byte per 8 bytes
+	let random = crate::bytes::keystream::<1000>(0x1234);
+	test(&random);
+}
+
+#[test]
+fn test_obfstr_compressed() {
+	const TEXT: &str = "SELECT id, name FROM users WHERE id = ?; SELECT id, name FROM groups WHERE id = ?; SELECT id, name FROM roles WHERE id = ?;";
+	assert!(compressed_len(TEXT.as_bytes()) < TEXT.len());
+	const PACKED: Packed<{compress_bound(TEXT.len())}> = pack(TEXT.as_bytes());
+	assert_eq!(unpack::<{compressed_len(TEXT.as_bytes())}, {compress_bound(TEXT.len())}>(&PACKED), compress::<{compressed_len(TEXT.as_bytes())}>(TEXT.as_bytes()));
+	assert_eq!(crate::obfstr_compressed!(TEXT), TEXT);
+	let mut buf = [0u8; decompressed_len(TEXT.as_bytes())];
+	assert_eq!(crate::obfstr_compressed!(buf <- TEXT), TEXT);
+	assert_eq!(crate::obfstr_compressed!(""), "");
+	assert_eq!(crate::obfstr_compressed!("no repeats"), "no repeats");
+}
@@ -1,23 +1,89 @@
 /*!
-Expose MurmurHash3, a keyed hash function. Not ready for public API.
+MurmurHash3, a keyed hash function.
 */
 
-use core::slice;
+use core::{hash, slice};
 
 /// MurmurHash3 (32-bit variant) keyed hash function.
-#[doc(hidden)]
+///
+/// Accepts a string or a byte string, the hash is computed at compiletime.
+/// Without a seed the hash is keyed with [`MURMUR3_SEED`](crate::MURMUR3_SEED), which differs per build.
+/// Pass `0` as the seed to produce the same hashes as other MurmurHash3 implementations:
+///
+/// ```
+/// const HASH: u32 = obfstr::murmur3!("Hello world", 0);
+/// assert_eq!(HASH, obfstr::murmur3(b"Hello world", 0));
+///
+/// let hash = obfstr::murmur3!(b"Hello world");
+/// assert_eq!(hash, obfstr::murmur3(b"Hello world", obfstr::MURMUR3_SEED));
+/// ```
 #[macro_export]
 macro_rules! murmur3 {
-	($s:expr, $seed:expr) => {{ const _MURMUR3_HASH: u32 = $crate::murmur3($s, $seed); _MURMUR3_HASH }};
-	($s:expr) => {{ const _MURMUR3_HASH: u32 = $crate::murmur3($s, 0); _MURMUR3_HASH }};
+	($s:expr, $seed:expr) => {{ const _MURMUR3_HASH: u32 = $crate::murmur3($crate::AsBytes($s).bytes(), $seed); _MURMUR3_HASH }};
+	($s:expr) => {{ const _MURMUR3_HASH: u32 = $crate::murmur3($crate::AsBytes($s).bytes(), $crate::MURMUR3_SEED); _MURMUR3_HASH }};
+}
+
+/// MurmurHash3 (32-bit variant) keyed hash function ignoring ASCII case.
+///
+/// Computes the hash of the ASCII lowercased string at compiletime, see [`murmur3!`](crate::murmur3!) for the seed.
+/// Compare against [`murmur3_nocase`](crate::murmur3_nocase) of the runtime input, which folds the case while hashing:
+///
+/// ```
+/// const HASH: u32 = obfstr::murmur3_nocase!("KERNEL32.DLL", 0);
+/// assert_eq!(obfstr::murmur3_nocase(b"kernel32.dll", 0), HASH);
+/// assert_eq!(obfstr::murmur3(b"kernel32.dll", 0), HASH);
+/// ```
+#[macro_export]
+macro_rules! murmur3_nocase {
+	($s:expr, $seed:expr) => {{ const _MURMUR3_HASH: u32 = $crate::murmur3_nocase($crate::AsBytes($s).bytes(), $seed); _MURMUR3_HASH }};
+	($s:expr) => {{ const _MURMUR3_HASH: u32 = $crate::murmur3_nocase($crate::AsBytes($s).bytes(), $crate::MURMUR3_SEED); _MURMUR3_HASH }};
+}
+
+/// MurmurHash3 (32-bit variant) keyed hash function of the UTF-16 encoding of a string ignoring ASCII case.
+///
+/// Computes the hash at compiletime, see [`murmur3!`](crate::murmur3!) for the seed.
+/// Compare against [`murmur3_words_nocase`](crate::murmur3_words_nocase) of the runtime wide input:
+///
+/// ```
+/// const HASH: u32 = obfstr::murmur3_wide_nocase!("KERNEL32.DLL", 0);
+/// let module: Vec<u16> = "kernel32.dll".encode_utf16().collect();
+/// assert_eq!(obfstr::murmur3_words_nocase(&module, 0), HASH);
+/// ```
+#[macro_export]
+macro_rules! murmur3_wide_nocase {
+	($s:expr, $seed:expr) => {{ const _MURMUR3_HASH: u32 = $crate::murmur3_wide_nocase($s, $seed); _MURMUR3_HASH }};
+	($s:expr) => {{ const _MURMUR3_HASH: u32 = $crate::murmur3_wide_nocase($s, $crate::MURMUR3_SEED); _MURMUR3_HASH }};
 }
 
+/// Seed of the [`murmur3!`](crate::murmur3!) macro without explicit seed.
+///
+/// Derived from [`SEED`](crate::SEED), hash runtime input with this seed to compare against the compiletime hashes.
+pub const MURMUR3_SEED: u32 = crate::entropy("murmur3") as u32;
+
 /// MurmurHash3 (32-bit variant) keyed hash function.
-#[doc(hidden)]
+///
+/// This is MurmurHash3_x86_32 by Austin Appleby, the input is read in little-endian 4-byte blocks on every target.
+/// The hash values are part of the public API and only change with a new major version.
+///
+/// ```
+/// use obfstr::murmur3;
+///
+/// assert_eq!(murmur3(b"", 0), 0); // with zero data and zero seed, everything becomes zero
+/// assert_eq!(murmur3(b"", 1), 0x514E28B7); // ignores nearly all the math
+/// assert_eq!(murmur3(b"", 0xffffffff), 0x81F16F39); // make sure your seed uses unsigned 32-bit math
+/// assert_eq!(murmur3(&[0xff, 0xff, 0xff, 0xff], 0), 0x76293B50); // make sure 4-byte chunks use unsigned math
+/// assert_eq!(murmur3(&[0x21, 0x43, 0x65, 0x87], 0), 0xF55B516B); // Endian order. UInt32 should end up as 0x87654321
+/// assert_eq!(murmur3(&[0x21, 0x43, 0x65, 0x87], 0x5082EDEE), 0x2362F9DE); // Special seed value eliminates initial key with xor
+/// assert_eq!(murmur3(&[0x21, 0x43, 0x65], 0), 0x7E4A8634); // Only three bytes. Should end up as 0x654321
+/// assert_eq!(murmur3(&[0x21, 0x43], 0), 0xA0F7B07A); // Only two bytes. Should end up as 0x4321
+/// assert_eq!(murmur3(&[0x21], 0), 0x72661CF4); // Only one byte. Should end up as 0x21
+/// assert_eq!(murmur3(&[0, 0, 0, 0], 0), 0x2362F9DE);
+/// assert_eq!(murmur3(&[0, 0, 0], 0), 0x85F0B427);
+/// assert_eq!(murmur3(&[0, 0], 0), 0x30F4C306);
+/// assert_eq!(murmur3(&[0], 0), 0x514E28B7);
+/// ```
 pub const fn murmur3(string: &[u8], seed: u32) -> u32 {
 	let mut h = seed;
-	const C1: u32 = 0xcc9e2d51;
-	const C2: u32 = 0x1b873593;
 
 	let mut s = string;
 	while s.len() >= 4 {
@@ -48,6 +114,263 @@ pub const fn murmur3(string: &[u8], seed: u32) -> u32 {
 	fmix32(h ^ string.len() as u32)
 }
 
+const C1: u32 = 0xcc9e2d51;
+const C2: u32 = 0x1b873593;
+
+/// MurmurHash3 state fed one byte at a time.
+///
+/// Produces the same result as [`murmur3`](murmur3()) for the same sequence of bytes.
+/// Used to hash input which is transformed on the fly without making a copy.
+#[derive(Copy, Clone)]
+struct Stream {
+	h: u32,
+	block: u32,
+	len: usize,
+}
+impl Stream {
+	#[inline(always)]
+	const fn new(seed: u32) -> Stream {
+		Stream { h: seed, block: 0, len: 0 }
+	}
+	#[inline(always)]
+	const fn push(mut self, byte: u8) -> Stream {
+		self.block |= (byte as u32) << ((self.len % 4) * 8);
+		self.len += 1;
+		if self.len % 4 == 0 {
+			self.h ^= self.block.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+			self.h = self.h.rotate_left(13).wrapping_mul(5).wrapping_add(0xe6546b64);
+			self.block = 0;
+		}
+		self
+	}
+	#[inline(always)]
+	const fn push_word(self, word: u16) -> Stream {
+		let [lo, hi] = word.to_le_bytes();
+		self.push(lo).push(hi)
+	}
+	#[inline(always)]
+	const fn push_char(mut self, chr: u32, nocase: bool) -> Stream {
+		// Encode as UTF-8, lone surrogates are encoded as WTF-8
+		if chr < 0x80 {
+			let byte = chr as u8;
+			self = self.push(if nocase { byte.to_ascii_lowercase() } else { byte });
+		}
+		else if chr < 0x800 {
+			self = self.push(0xc0 | (chr >> 6) as u8);
+			self = self.push(0x80 | (chr & 0x3f) as u8);
+		}
+		else if chr < 0x10000 {
+			self = self.push(0xe0 | (chr >> 12) as u8);
+			self = self.push(0x80 | (chr >> 6 & 0x3f) as u8);
+			self = self.push(0x80 | (chr & 0x3f) as u8);
+		}
+		else {
+			self = self.push(0xf0 | (chr >> 18) as u8);
+			self = self.push(0x80 | (chr >> 12 & 0x3f) as u8);
+			self = self.push(0x80 | (chr >> 6 & 0x3f) as u8);
+			self = self.push(0x80 | (chr & 0x3f) as u8);
+		}
+		self
+	}
+	#[inline(always)]
+	const fn finish(self) -> u32 {
+		let mut h = self.h;
+		if self.len % 4 != 0 {
+			h ^= self.block.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+		}
+		fmix32(h ^ self.len as u32)
+	}
+}
+
+/// MurmurHash3 (32-bit variant) keyed hasher.
+///
+/// Buffers partial blocks across calls to [`write`](hash::Hasher::write), the hash equals [`murmur3`](murmur3()) of the concatenated input.
+/// The `u32` hash is zero extended by [`finish`](hash::Hasher::finish), see [`finish32`](Murmur3Hasher::finish32).
+///
+/// Note that [`Hash`](hash::Hash) implementations may write more than the raw bytes, eg. `str` writes a trailing `0xff` byte.
+/// Call [`write`](hash::Hasher::write) directly to match the compiletime hashes:
+///
+/// ```
+/// use core::hash::Hasher;
+///
+/// let mut hasher = obfstr::Murmur3Hasher::new(42);
+/// hasher.write(b"Hello ");
+/// hasher.write(b"world");
+/// assert_eq!(hasher.finish32(), obfstr::murmur3(b"Hello world", 42));
+/// assert_eq!(hasher.finish(), obfstr::murmur3(b"Hello world", 42) as u64);
+/// ```
+#[derive(Copy, Clone)]
+pub struct Murmur3Hasher {
+	state: Stream,
+}
+impl Murmur3Hasher {
+	/// Constructs the hasher with the given seed.
+	#[inline]
+	pub const fn new(seed: u32) -> Murmur3Hasher {
+		Murmur3Hasher { state: Stream::new(seed) }
+	}
+	/// Returns the `u32` hash of the input written so far.
+	#[inline]
+	pub const fn finish32(&self) -> u32 {
+		self.state.finish()
+	}
+}
+impl hash::Hasher for Murmur3Hasher {
+	#[inline]
+	fn write(&mut self, bytes: &[u8]) {
+		for &byte in bytes {
+			self.state = self.state.push(byte);
+		}
+	}
+	#[inline]
+	fn finish(&self) -> u64 {
+		self.finish32() as u64
+	}
+}
+
+/// MurmurHash3 (32-bit variant) keyed hash function ignoring ASCII case.
+///
+/// Equivalent to hashing the ASCII lowercased input with [`murmur3`](murmur3()).
+#[doc(hidden)]
+pub const fn murmur3_nocase(string: &[u8], seed: u32) -> u32 {
+	let mut state = Stream::new(seed);
+	let mut i = 0;
+	while i < string.len() {
+		state = state.push(string[i].to_ascii_lowercase());
+		i += 1;
+	}
+	state.finish()
+}
+
+/// MurmurHash3 (32-bit variant) keyed hash function of the UTF-8 encoding of a wide string.
+///
+/// Equivalent to hashing the UTF-8 encoding of the wide string with [`murmur3`](murmur3()).
+/// Unpaired surrogates are encoded as WTF-8.
+#[doc(hidden)]
+pub const fn murmur3_utf16(string: &[u16], seed: u32) -> u32 {
+	utf16(string, seed, false)
+}
+
+/// MurmurHash3 (32-bit variant) keyed hash function of the UTF-8 encoding of a wide string ignoring ASCII case.
+#[doc(hidden)]
+pub const fn murmur3_utf16_nocase(string: &[u16], seed: u32) -> u32 {
+	utf16(string, seed, true)
+}
+
+/// MurmurHash3 (32-bit variant) keyed hash function of a wide string.
+///
+/// Hashes two words per block, equivalent to hashing the little-endian bytes of the words with [`murmur3`](murmur3()).
+/// Unlike [`murmur3_utf16`] the UTF-8 encoding is not hashed, use [`murmur3_wide`] to hash a string constant the same way.
+#[doc(hidden)]
+pub const fn murmur3_words(string: &[u16], seed: u32) -> u32 {
+	words(string, seed, false)
+}
+
+/// MurmurHash3 (32-bit variant) keyed hash function of a wide string ignoring ASCII case.
+///
+/// Equivalent to hashing the wide string with the ASCII range lowercased with [`murmur3_words`].
+#[doc(hidden)]
+pub const fn murmur3_words_nocase(string: &[u16], seed: u32) -> u32 {
+	words(string, seed, true)
+}
+
+#[inline(always)]
+const fn fold(word: u16, nocase: bool) -> u16 {
+	if nocase && word < 0x80 { (word as u8).to_ascii_lowercase() as u16 } else { word }
+}
+
+#[inline(always)]
+const fn words(string: &[u16], seed: u32, nocase: bool) -> u32 {
+	let mut h = seed;
+
+	let mut i = 0;
+	while i + 2 <= string.len() {
+		let mut k = fold(string[i], nocase) as u32 | (fold(string[i + 1], nocase) as u32) << 16;
+		k = k.wrapping_mul(C1);
+		k = k.rotate_left(15);
+		k = k.wrapping_mul(C2);
+
+		h ^= k;
+		h = h.rotate_left(13);
+		h = h.wrapping_mul(5).wrapping_add(0xe6546b64);
+
+		i += 2;
+	}
+
+	if i < string.len() {
+		let k = fold(string[i], nocase) as u32;
+		h ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+	}
+
+	fmix32(h ^ (string.len() * 2) as u32)
+}
+
+/// MurmurHash3 (32-bit variant) keyed hash function of the UTF-16 encoding of a string.
+///
+/// Equivalent to hashing the wide string with [`murmur3_words`], matches runtime wide input against a string constant:
+///
+/// ```
+/// const HASH: u32 = obfstr::murmur3_wide("kernel32.dll", 0);
+/// let module: Vec<u16> = "kernel32.dll".encode_utf16().collect();
+/// assert_eq!(obfstr::murmur3_words(&module, 0), HASH);
+/// ```
+#[doc(hidden)]
+pub const fn murmur3_wide(string: &str, seed: u32) -> u32 {
+	wide(string, seed, false)
+}
+
+/// MurmurHash3 (32-bit variant) keyed hash function of the UTF-16 encoding of a string ignoring ASCII case.
+///
+/// Equivalent to hashing the wide string with [`murmur3_words_nocase`].
+#[doc(hidden)]
+pub const fn murmur3_wide_nocase(string: &str, seed: u32) -> u32 {
+	wide(string, seed, true)
+}
+
+#[inline(always)]
+const fn wide(string: &str, seed: u32, nocase: bool) -> u32 {
+	let mut state = Stream::new(seed);
+	let mut bytes = string.as_bytes();
+	while let Some((chr, tail)) = crate::wide::next(bytes) {
+		bytes = tail;
+		if chr >= 0x10000 {
+			state = state.push_word((0xD800 + (chr - 0x10000) / 0x400) as u16);
+			state = state.push_word((0xDC00 + (chr - 0x10000) % 0x400) as u16);
+		}
+		else {
+			state = state.push_word(fold(chr as u16, nocase));
+		}
+	}
+	state.finish()
+}
+
+/// Two chained MurmurHash3 (32-bit variant) hashes forming a 64-bit keyed hash function.
+///
+/// The low half is keyed with both halves of the seed, the high half is keyed with the low half.
+#[doc(hidden)]
+pub const fn murmur3_64(string: &[u8], seed: u64) -> u64 {
+	let lo = murmur3(string, (seed ^ seed >> 32) as u32);
+	let hi = murmur3(string, (seed >> 32) as u32 ^ lo);
+	return (hi as u64) << 32 | lo as u64;
+}
+
+#[inline(always)]
+const fn utf16(string: &[u16], seed: u32, nocase: bool) -> u32 {
+	let mut state = Stream::new(seed);
+	let mut i = 0;
+	while i < string.len() {
+		let unit = string[i] as u32;
+		i += 1;
+		let mut chr = unit;
+		if unit & 0xfc00 == 0xd800 && i < string.len() && string[i] & 0xfc00 == 0xdc00 {
+			chr = 0x10000 + ((unit - 0xd800) << 10 | (string[i] as u32 - 0xdc00));
+			i += 1;
+		}
+		state = state.push_char(chr, nocase);
+	}
+	state.finish()
+}
+
 #[inline(always)]
 const fn fmix32(mut h: u32) -> u32 {
 	h ^= h >> 16;
@@ -59,24 +382,102 @@ const fn fmix32(mut h: u32) -> u32 {
 }
 
 #[test]
-fn test_vectors() {
-	static TEST_VECTORS: [(u32, u32, &[u8]); 13] = [
-		(0,          0,          b""), // with zero data and zero seed, everything becomes zero
-		(0x514E28B7, 1,          b""), // ignores nearly all the math
-		(0x81F16F39, 0xffffffff, b""), // make sure your seed uses unsigned 32-bit math
-		(0x76293B50, 0,          &[0xff, 0xff, 0xff, 0xff]), // make sure 4-byte chunks use unsigned math
-		(0xF55B516B, 0,          &[0x21, 0x43, 0x65, 0x87]), // Endian order. UInt32 should end up as 0x87654321
-		(0x2362F9DE, 0x5082EDEE, &[0x21, 0x43, 0x65, 0x87]), // Special seed value eliminates initial key with xor
-		(0x7E4A8634, 0,          &[0x21, 0x43, 0x65]), // Only three bytes. Should end up as 0x654321
-		(0xA0F7B07A, 0,          &[0x21, 0x43]), // Only two bytes. Should end up as 0x4321
-		(0x72661CF4, 0,          &[0x21]), // Only one byte. Should end up as 0x21
-		(0x2362F9DE, 0,          &[0, 0, 0, 0]),
-		(0x85F0B427, 0,          &[0, 0, 0]),
-		(0x30F4C306, 0,          &[0, 0]),
-		(0x514E28B7, 0,          &[0]),
+fn test_hasher() {
+	use core::hash::Hasher;
+	static INPUTS: [(u32, &[u8]); 14] = [
+		(0,          b""),
+		(1,          b""),
+		(0xffffffff, b""),
+		(0,          &[0xff, 0xff, 0xff, 0xff]),
+		(0,          &[0x21, 0x43, 0x65, 0x87]),
+		(0x5082EDEE, &[0x21, 0x43, 0x65, 0x87]),
+		(0,          &[0x21, 0x43, 0x65]),
+		(0,          &[0x21, 0x43]),
+		(0,          &[0x21]),
+		(0,          &[0, 0, 0, 0]),
+		(0,          &[0, 0, 0]),
+		(0,          &[0, 0]),
+		(0,          &[0]),
+		(42,         b"The quick brown fox jumps over the lazy dog"),
 	];
+	for &(seed, input) in INPUTS.iter() {
+		let expected = murmur3(input, seed);
+		for i in 0..=input.len() {
+			for j in i..=input.len() {
+				let mut hasher = Murmur3Hasher::new(seed);
+				hasher.write(&input[..i]);
+				hasher.write(&input[i..j]);
+				hasher.write(&input[j..]);
+				assert_eq!(hasher.finish32(), expected);
+				assert_eq!(hasher.finish(), expected as u64);
+			}
+		}
+	}
+}
+
+#[test]
+fn test_stream() {
+	static INPUTS: [&str; 6] = ["", "a", "ab", "abc", "Hello 🌍", "The quick brown fox jumps over the lazy dog"];
+	for &input in INPUTS.iter() {
+		let wide: Vec<u16> = input.encode_utf16().collect();
+		assert_eq!(murmur3(input.as_bytes(), 42), murmur3_utf16(&wide, 42));
+		assert_eq!(murmur3(input.to_ascii_lowercase().as_bytes(), 42), murmur3_nocase(input.to_ascii_uppercase().as_bytes(), 42));
+		let upper: Vec<u16> = input.to_ascii_uppercase().encode_utf16().collect();
+		assert_eq!(murmur3(input.to_ascii_lowercase().as_bytes(), 42), murmur3_utf16_nocase(&upper, 42));
+	}
+}
+
+#[test]
+fn test_murmur3_words() {
+	// Same as the little-endian bytes test vectors
+	assert_eq!(murmur3_words(&[], 0), 0);
+	assert_eq!(murmur3_words(&[], 1), 0x514E28B7);
+	assert_eq!(murmur3_words(&[0x4321, 0x8765], 0), 0xF55B516B);
+	assert_eq!(murmur3_words(&[0x4321], 0), 0xA0F7B07A);
+	assert_eq!(murmur3_words(&[0, 0], 0), 0x2362F9DE);
+	assert_eq!(murmur3_words(&[0], 0), 0x30F4C306);
 
-	for &(expected, seed, input) in TEST_VECTORS.iter() {
-		assert_eq!(expected, murmur3(input, seed));
+	static INPUTS: [&str; 6] = ["", "a", "ab", "abc", "Hello 🌍", "The quick brown fox jumps over the lazy dog"];
+	for &input in INPUTS.iter() {
+		let wide: Vec<u16> = input.encode_utf16().collect();
+		let bytes: Vec<u8> = wide.iter().flat_map(|w| w.to_le_bytes()).collect();
+		assert_eq!(murmur3_words(&wide, 42), murmur3(&bytes, 42));
+		assert_eq!(murmur3_words(&wide, 42), murmur3_wide(input, 42));
 	}
+
+	// Odd length and a surrogate pair split over two blocks
+	assert_eq!(murmur3_words(crate::wide!("abc"), 7), murmur3(b"a\0b\0c\0", 7));
+	assert_eq!(murmur3_words(crate::wide!("a\u{1F30D}"), 7), murmur3(b"a\0\x3c\xd8\x0d\xdf", 7));
+	assert_eq!(murmur3_wide("a\u{1F30D}", 7), murmur3(b"a\0\x3c\xd8\x0d\xdf", 7));
+	// Lone surrogates from the loader are hashed as is
+	assert_eq!(murmur3_words(&[0xd800], 7), murmur3(b"\0\xd8", 7));
+}
+
+#[test]
+fn test_nocase() {
+	assert_eq!(murmur3_nocase(b"KERNEL32.DLL", 42), murmur3_nocase(b"kernel32.dll", 42));
+	assert_eq!(murmur3_nocase(b"KERNEL32.DLL", 42), murmur3(b"kernel32.dll", 42));
+	assert_eq!(murmur3_nocase!("Kernel32.dll", 42), murmur3(b"kernel32.dll", 42));
+	// Non-ASCII bytes are not folded
+	assert_ne!(murmur3_nocase("\u{c9}".as_bytes(), 42), murmur3_nocase("\u{e9}".as_bytes(), 42));
+	assert_ne!(murmur3_nocase(&[0xc9], 42), murmur3_nocase(&[0xe9], 42));
+
+	let upper: Vec<u16> = "KERNEL32.DLL".encode_utf16().collect();
+	let lower: Vec<u16> = "kernel32.dll".encode_utf16().collect();
+	assert_eq!(murmur3_words_nocase(&upper, 42), murmur3_words(&lower, 42));
+	assert_eq!(murmur3_words_nocase(&upper, 42), murmur3_wide_nocase!("Kernel32.DLL", 42));
+	assert_eq!(murmur3_wide_nocase("NTDLL", 42), murmur3_words(crate::wide!("ntdll"), 42));
+	// Code units outside of the ASCII range are not folded, including ones with an ASCII low byte
+	assert_ne!(murmur3_words_nocase(&[0xc9], 42), murmur3_words_nocase(&[0xe9], 42));
+	assert_ne!(murmur3_words_nocase(&[0x141], 42), murmur3_words_nocase(&[0x161], 42));
+}
+
+#[test]
+fn test_murmur3_64() {
+	let h = murmur3_64(b"Hello", 0x0123456789abcdef);
+	assert_eq!(h as u32, murmur3(b"Hello", 0x89abcdef ^ 0x01234567));
+	assert_eq!((h >> 32) as u32, murmur3(b"Hello", 0x01234567 ^ h as u32));
+	// Both halves of the seed affect both halves of the hash
+	assert_ne!(h as u32, murmur3_64(b"Hello", 0x1123456789abcdef) as u32);
+	assert_ne!(h >> 32, murmur3_64(b"Hello", 0x0123456789abcdee) >> 32);
 }
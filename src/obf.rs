@@ -0,0 +1,197 @@
+use core::fmt;
+use crate::bytes;
+
+/// Obfuscated string constant which can be stored in statics.
+///
+/// Constructed with [`obf!`]. The reference to the ciphertext is obfuscated with [`xref!`] and only restored when used.
+///
+/// The string can be compared against without deobfuscating it, see [`obfeq!`].
+/// Use [`ObfStr::deobfuscate`] when access to the string itself is needed.
+///
+/// ```
+/// use obfstr::{obf, ObfStr};
+///
+/// static COMMANDS: [ObfStr<5>; 3] = [obf!("start"), obf!("pause"), obf!("abort")];
+///
+/// let input = "pause";
+/// assert_eq!(COMMANDS.iter().position(|cmd| cmd == input), Some(1));
+///
+/// let mut buf = [0u8; 5];
+/// assert_eq!(COMMANDS[2].deobfuscate(&mut buf), "abort");
+/// ```
+pub struct ObfStr<const LEN: usize> {
+	data: *const u8,
+	keys: &'static [u8; LEN],
+	xref: unsafe fn(*const u8) -> &'static [u8; LEN],
+}
+
+// Safety: the data pointer refers to an immutable static
+unsafe impl<const LEN: usize> Sync for ObfStr<LEN> {}
+unsafe impl<const LEN: usize> Send for ObfStr<LEN> {}
+
+impl<const LEN: usize> ObfStr<LEN> {
+	#[doc(hidden)]
+	#[inline(always)]
+	pub const unsafe fn from_raw_parts(data: *const u8, keys: &'static [u8; LEN], xref: unsafe fn(*const u8) -> &'static [u8; LEN]) -> ObfStr<LEN> {
+		ObfStr { data, keys, xref }
+	}
+
+	#[inline(always)]
+	fn data(&self) -> &'static [u8; LEN] {
+		unsafe { (self.xref)(self.data) }
+	}
+
+	/// Returns the length of the string in bytes.
+	#[inline(always)]
+	pub const fn len(&self) -> usize {
+		LEN
+	}
+
+	/// Returns `true` if the string is empty.
+	#[inline(always)]
+	pub const fn is_empty(&self) -> bool {
+		LEN == 0
+	}
+
+	/// Deobfuscates the string into the given buffer.
+	///
+	/// Panics if the buffer is too small.
+	#[inline(always)]
+	pub fn deobfuscate<'a>(&self, buf: &'a mut [u8]) -> &'a str {
+		let buf = &mut buf[..LEN];
+		buf.copy_from_slice(&bytes::deobfuscate::<LEN>(self.data(), self.keys));
+		crate::unsafe_as_str(buf)
+	}
+}
+
+impl<const LEN: usize> PartialEq<str> for ObfStr<LEN> {
+	#[inline]
+	fn eq(&self, other: &str) -> bool {
+		bytes::equals::<LEN>(self.data(), self.keys, other.as_bytes())
+	}
+}
+impl<const LEN: usize> PartialEq<&str> for ObfStr<LEN> {
+	#[inline]
+	fn eq(&self, other: &&str) -> bool {
+		bytes::equals::<LEN>(self.data(), self.keys, other.as_bytes())
+	}
+}
+impl<const LEN: usize> PartialEq<[u8]> for ObfStr<LEN> {
+	#[inline]
+	fn eq(&self, other: &[u8]) -> bool {
+		bytes::equals::<LEN>(self.data(), self.keys, other)
+	}
+}
+impl<const LEN: usize> PartialEq<ObfStr<LEN>> for str {
+	#[inline]
+	fn eq(&self, other: &ObfStr<LEN>) -> bool {
+		other == self
+	}
+}
+impl<const LEN: usize> PartialEq<ObfStr<LEN>> for &str {
+	#[inline]
+	fn eq(&self, other: &ObfStr<LEN>) -> bool {
+		other == self
+	}
+}
+
+impl<const LEN: usize> fmt::Debug for ObfStr<LEN> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		// Do not leak the string through debug formatting
+		f.debug_struct("ObfStr").field("len", &LEN).finish()
+	}
+}
+
+/// Compiletime string constant obfuscation for statics.
+///
+/// Constructs an [`ObfStr`] which can be used in statics and arrays.
+///
+/// ```
+/// static GREETING: obfstr::ObfStr<5> = obfstr::obf!("hello");
+/// assert!(GREETING == "hello");
+/// ```
+#[macro_export]
+macro_rules! obf {
+	($s:expr) => {{
+		use ::core::primitive::*;
+		const _OBF_STRING: &[u8] = ::core::primitive::str::as_bytes($s);
+		const _OBF_LEN: usize = _OBF_STRING.len();
+		const _OBF_OFFSET: u32 = $crate::random!(u32, "offset", stringify!($s));
+		const _OBF_SEED: u64 = $crate::random!(u64, "xref", stringify!($s));
+		static _OBF_KEYSTREAM: [u8; _OBF_LEN] = $crate::bytes::keys::<_OBF_LEN>($crate::random!(u64, "key", stringify!($s)));
+		static _OBF_SDATA: [u8; _OBF_LEN] = $crate::bytes::obfuscate::<_OBF_LEN>(_OBF_STRING, &_OBF_KEYSTREAM);
+		unsafe {
+			$crate::ObfStr::<_OBF_LEN>::from_raw_parts(
+				$crate::xref::mangle::<[u8; _OBF_LEN], _OBF_OFFSET, _OBF_SEED>(&_OBF_SDATA),
+				&_OBF_KEYSTREAM,
+				$crate::xref::unmangle::<[u8; _OBF_LEN], _OBF_OFFSET, _OBF_SEED>)
+		}
+	}};
+}
+
+/// Fetches a string from a pool obfuscated once in a shared static.
+///
+/// Every [`obfstr!`](crate::obfstr!) call site stores its own ciphertext, key stream and xref.
+/// Instead the strings can be collected in an [`obfpool!`](crate::obfpool!) which is obfuscated once with [`obf!`](crate::obf!).
+/// Each call site names the string it needs, the range is found in the pool at compiletime with [`position!`](crate::position!).
+/// With the `#[pack]` layout identical strings and strings contained in other strings share their bytes:
+///
+/// ```
+/// obfstr::obfpool! { #[pack] POOL: ERROR = "ERROR: ", IO = "ERROR: io", READ = "read", AGAIN = "read"; }
+/// static SHARED: obfstr::ObfStr<{POOL.len()}> = obfstr::obf!(POOL);
+///
+/// let mut buf = [0u8; POOL.len()];
+/// assert_eq!(obfstr::pooled!(SHARED, POOL, buf <- "ERROR: io"), "ERROR: io");
+/// assert_eq!(obfstr::pooled!(SHARED, POOL, buf <- "read"), "read");
+/// // Any substring of the pool can be fetched
+/// assert_eq!(obfstr::pooled!(SHARED, POOL, buf <- "io"), "io");
+/// ```
+///
+/// Strings which are not in the pool fail to compile:
+///
+/// ```compile_fail
+/// obfstr::obfpool! { POOL: FOO = "Foo"; }
+/// static SHARED: obfstr::ObfStr<{POOL.len()}> = obfstr::obf!(POOL);
+///
+/// let mut buf = [0u8; POOL.len()];
+/// let _ = obfstr::pooled!(SHARED, POOL, buf <- "Bar");
+/// ```
+///
+/// The whole pool is deobfuscated into the buffer on every call.
+#[macro_export]
+macro_rules! pooled {
+	($shared:path, $pool:path, $buf:ident <- $s:expr) => {
+		$crate::pool_get($shared.deobfuscate(&mut $buf), $crate::position!($pool, $s))
+	};
+}
+
+#[test]
+fn test_static_array() {
+	static TABLE: [ObfStr<6>; 4] = [obf!("alpha1"), obf!("bravo2"), obf!("charl3"), obf!("delta4")];
+	let inputs = ["bravo2", "delta4", "echo55", "alpha", "alpha1"];
+	let found: Vec<_> = inputs.iter().map(|input| TABLE.iter().position(|entry| entry == input)).collect();
+	assert_eq!(found, [Some(1), Some(3), None, None, Some(0)]);
+	assert!(TABLE[2] == *b"charl3".as_slice());
+	assert!("charl3" == TABLE[2]);
+
+	let mut buf = [0u8; 16];
+	assert_eq!(TABLE[0].deobfuscate(&mut buf), "alpha1");
+	assert_eq!(TABLE[3].len(), 6);
+}
+
+#[test]
+fn test_pooled() {
+	crate::obfpool! { #[pack] POOL: USAGE = "usage: ", HELP = "usage: help", VERSION = "version", HELP2 = "usage: help"; }
+	static SHARED: ObfStr<{POOL.len()}> = obf!(POOL);
+	// The duplicate entry shares the bytes of the first
+	assert_eq!(POOL, "usage: helpversion");
+	assert_eq!(HELP, HELP2);
+	let mut buf = [0u8; POOL.len()];
+	assert_eq!(crate::pooled!(SHARED, POOL, buf <- "usage: help"), "usage: help");
+	assert_eq!(crate::position!(POOL, "version"), VERSION);
+	assert_eq!(crate::pooled!(SHARED, POOL, buf <- "version"), "version");
+	assert_eq!(crate::pooled!(SHARED, POOL, buf <- "usage: "), &POOL[USAGE]);
+	assert_eq!(crate::pooled!(SHARED, POOL, buf <- ""), "");
+	// Only the pool is stored obfuscated
+	assert!(!crate::bytes::has_printable_run(SHARED.data(), 8));
+}
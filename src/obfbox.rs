@@ -0,0 +1,200 @@
+use core::{fmt, ptr};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use crate::bytes::{self, wipe, Wipe};
+
+/// Heap allocated string which stays obfuscated at rest.
+///
+/// Constructed with [`obfbox!`] from a string constant or with [`ObfBox::from_plaintext`] from runtime data.
+/// The heap buffer holds the ciphertext under a key generated at runtime, the string is only deobfuscated for the duration of [`ObfBox::with`].
+///
+/// There is no random number generator in `no_std`, the key is derived from entropy provided by the caller.
+/// This is obfuscation, not encryption. Do not rely on it to protect secrets.
+///
+/// ```
+/// use obfstr::{obfbox, ObfBox};
+///
+/// struct Config {
+/// 	password: ObfBox,
+/// }
+///
+/// let entropy = 0x243f6a8885a308d3; // Get this from a random number generator
+/// let config = Config { password: obfbox!(entropy => "hunter2") };
+/// assert_eq!(config.password.with(|s| s.len()), 7);
+/// assert!(config.password.with(|s| s == "hunter2"));
+/// ```
+pub struct ObfBox {
+	data: Box<[u8]>,
+	key: u64,
+}
+
+// Derives the runtime key from the caller's entropy and the address of the heap buffer.
+#[inline]
+fn runtime_key(entropy: u64, data: &[u8]) -> u64 {
+	crate::splitmix(entropy ^ data.as_ptr() as usize as u64)
+}
+
+impl ObfBox {
+	/// Obfuscates the runtime string under a key derived from the given entropy.
+	pub fn from_plaintext(s: &str, entropy: u64) -> ObfBox {
+		let mut data: Box<[u8]> = s.as_bytes().into();
+		let key = runtime_key(entropy, &data);
+		bytes::obfuscate_slice(&mut data, key);
+		ObfBox { data, key }
+	}
+
+	/// Constructs the box from the ciphertext obfuscated with [`bytes::keystream`] under the given key.
+	///
+	/// The ciphertext is re-keyed under a key derived from the given entropy without deobfuscating it in memory.
+	///
+	/// # Safety
+	///
+	/// The deobfuscated ciphertext must be valid UTF-8.
+	#[doc(hidden)]
+	#[inline(never)]
+	pub unsafe fn from_obfuscated(data: &[u8], key: u64, entropy: u64) -> ObfBox {
+		let mut data: Box<[u8]> = data.into();
+		let runtime_key = runtime_key(entropy, &data);
+		bytes::rekey_slice(&mut data, key, runtime_key);
+		ObfBox { data, key: runtime_key }
+	}
+
+	/// Returns the length of the string in bytes.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.data.len()
+	}
+
+	/// Returns `true` if the string is empty.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.data.is_empty()
+	}
+
+	/// Deobfuscates the string into a temporary buffer and passes it to the closure.
+	///
+	/// The temporary buffer is wiped after the closure returns.
+	pub fn with<R, F: FnOnce(&str) -> R>(&self, f: F) -> R {
+		let mut buf: Vec<u8> = self.data.to_vec();
+		let buf = Wipe(&mut buf);
+		bytes::deobfuscate_slice(buf.0, self.key);
+		f(crate::unsafe_as_str(buf.0))
+	}
+
+	// Wipes both the ciphertext and the key.
+	fn clear(&mut self) {
+		wipe(&mut self.data);
+		unsafe { ptr::write_volatile(&mut self.key, 0) };
+	}
+}
+
+impl Drop for ObfBox {
+	fn drop(&mut self) {
+		self.clear();
+	}
+}
+
+impl PartialEq<str> for ObfBox {
+	#[inline]
+	fn eq(&self, other: &str) -> bool {
+		bytes::equals_slice(&self.data, self.key, other.as_bytes())
+	}
+}
+impl PartialEq<&str> for ObfBox {
+	#[inline]
+	fn eq(&self, other: &&str) -> bool {
+		bytes::equals_slice(&self.data, self.key, other.as_bytes())
+	}
+}
+
+impl fmt::Debug for ObfBox {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		// Do not leak the string through debug formatting
+		f.debug_struct("ObfBox").field("len", &self.data.len()).finish()
+	}
+}
+
+/// Compiletime string constant obfuscation into an [`ObfBox`].
+///
+/// The runtime key is derived from the entropy provided by the caller:
+///
+/// ```
+/// let entropy = 0x13198a2e03707344; // Get this from a random number generator
+/// let secret = obfstr::obfbox!(entropy => "secret");
+/// assert!(secret == "secret");
+/// ```
+///
+/// Without entropy the runtime key is only derived from the address of the heap buffer:
+///
+/// ```
+/// let secret = obfstr::obfbox!("secret");
+/// assert!(secret.with(|s| s == "secret"));
+/// ```
+#[macro_export]
+macro_rules! obfbox {
+	($entropy:expr => $s:expr) => {
+		$crate::__obfbox!($entropy, $s)
+	};
+	($s:expr) => {
+		$crate::__obfbox!($crate::random!(u64, "entropy", stringify!($s)), $s)
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfbox {
+	($entropy:expr, $s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFBOX_STRING: &[u8] = ::core::primitive::str::as_bytes($s);
+		const _OBFBOX_LEN: usize = _OBFBOX_STRING.len();
+		const _OBFBOX_KEY: u64 = $crate::random!(u64, "key", stringify!($s));
+		static _OBFBOX_SDATA: [u8; _OBFBOX_LEN] = $crate::bytes::obfuscate::<_OBFBOX_LEN>(_OBFBOX_STRING, &$crate::bytes::keystream::<_OBFBOX_LEN>(_OBFBOX_KEY));
+		let sdata = $crate::xref::xref::<_,
+			{$crate::random!(u32, "offset", stringify!($s))},
+			{$crate::random!(u64, "xref", stringify!($s))}>
+			(&_OBFBOX_SDATA);
+		unsafe { $crate::ObfBox::from_obfuscated(sdata, _OBFBOX_KEY, $entropy) }
+	}};
+}
+
+#[test]
+fn test_roundtrip() {
+	let secret = obfbox!(0x1234 => "Hello world");
+	assert_eq!(secret.len(), 11);
+	assert_eq!(secret.with(|s| s.to_owned()), "Hello world");
+	assert!(secret == "Hello world");
+	assert!(secret != "Hello World");
+
+	let secret = obfbox!("");
+	assert!(secret.is_empty());
+	assert_eq!(secret.with(|s| s.len()), 0);
+
+	let secret = ObfBox::from_plaintext("Hello runtime 🌍", 0x5678);
+	assert_eq!(secret.with(|s| s.to_owned()), "Hello runtime 🌍");
+	assert_eq!(format!("{:?}", secret), "ObfBox { len: 18 }");
+}
+
+#[test]
+fn test_at_rest() {
+	const PLAINTEXT: &str = "The quick brown fox jumps over the lazy dog";
+	let a = obfbox!(0x1234 => "The quick brown fox jumps over the lazy dog");
+	let b = ObfBox::from_plaintext(PLAINTEXT, 0x1234);
+	for secret in [&a, &b] {
+		assert_ne!(&secret.data[..], PLAINTEXT.as_bytes());
+		assert!(secret.with(|s| s == PLAINTEXT));
+		// The heap buffer is still obfuscated after the access
+		assert_ne!(&secret.data[..], PLAINTEXT.as_bytes());
+		assert!(secret.data.windows(4).all(|w| !PLAINTEXT.as_bytes().windows(4).any(|p| p == w)));
+	}
+	// Different entropy produces a different ciphertext
+	assert_ne!(a.data, ObfBox::from_plaintext(PLAINTEXT, 0x5678).data);
+}
+
+#[test]
+fn test_clear() {
+	// Drop clears the box before the buffer is deallocated
+	let mut secret = ObfBox::from_plaintext("Hello world", 0x1234);
+	secret.clear();
+	assert_eq!(&secret.data[..], &[0u8; 11]);
+	assert_eq!(secret.key, 0);
+}
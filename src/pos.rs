@@ -12,21 +12,397 @@ use core::{ops, str};
 ///# assert_eq!(obfstr::position!("haystack", "ck"), 6..8);
 /// ```
 ///
+/// The needle may also be a char literal, the result is the byte range of the encoded character:
+///
+/// ```
+/// assert_eq!(obfstr::position!("ü=é", 'é'), 3..5);
+/// ```
+///
+/// When pooling strings in a single obfstr prefer [`position_in_segments!`](crate::position_in_segments!) or [`obfpool!`](crate::obfpool!).
+/// The needle may otherwise be found spanning two adjacent strings of the pool.
+#[macro_export]
+macro_rules! position {
+	// Char literals are converted to string literals
+	($haystack:expr, $needle:literal) => {{ const _POSITION_RANGE: ::core::ops::Range<usize> = $crate::position($haystack, ::core::concat!($needle)); _POSITION_RANGE }};
+	($haystack:expr, $needle:expr) => {{ const _POSITION_RANGE: ::core::ops::Range<usize> = $crate::position($haystack, $needle); _POSITION_RANGE }};
+}
+
+/// Finds the position of the needle in the concatenation of the segments at compiletime.
+///
+/// Only matches needles fully contained in a single segment.
+///
+/// Produces a const-eval error if the needle is not a substring of any segment.
+///
+/// # Examples
+///
 /// Use this API when pooling strings in a single obfstr:
 ///
 /// ```
 /// const POOL: &str = concat!("Foo", "Bar", "Baz");
+/// const SEGMENTS: &[&str] = &["Foo", "Bar", "Baz"];
 ///
 /// obfstr::obfstr! { let pool = POOL; }
 ///
 /// // Later, read strings from the pool
-/// let foo = &pool[obfstr::position!(POOL, "Foo")];
-/// let bar = &pool[obfstr::position!(POOL, "Bar")];
-/// let baz = &pool[obfstr::position!(POOL, "Baz")];
+/// let foo = &pool[obfstr::position_in_segments!(SEGMENTS, "Foo")];
+/// let bar = &pool[obfstr::position_in_segments!(SEGMENTS, "Bar")];
+/// let baz = &pool[obfstr::position_in_segments!(SEGMENTS, "Baz")];
+/// # assert_eq!([foo, bar, baz], ["Foo", "Bar", "Baz"]);
+/// ```
+///
+/// The needle `"oBa"` spans two segments and is not matched:
+///
+/// ```compile_fail
+/// let _ = obfstr::position_in_segments!(&["Foo", "Bar"], "oBa");
 /// ```
 #[macro_export]
-macro_rules! position {
-	($haystack:expr, $needle:expr) => {{ const _POSITION_RANGE: ::core::ops::Range<usize> = $crate::position($haystack, $needle); _POSITION_RANGE }};
+macro_rules! position_in_segments {
+	($segments:expr, $needle:expr) => {{ const _POSITION_RANGE: ::core::ops::Range<usize> = $crate::position_in_segments($segments, $needle); _POSITION_RANGE }};
+}
+
+/// Finds the position of the last occurrence of the needle in the haystack at compiletime.
+///
+/// Produces a const-eval error if the needle is not a substring of the haystack.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(obfstr::rposition!("abXab", "ab"), 3..5);
+///# assert_eq!(obfstr::rposition!("haystack", "haystack"), 0..8);
+///# assert_eq!(obfstr::rposition!("haystack", "hay"), 0..3);
+/// ```
+///
+/// Use this API when a pooled string also appears as part of an earlier pooled string:
+///
+/// ```
+/// const POOL: &str = concat!("FooBar", "Bar");
+///
+/// // The first occurrence of "Bar" is the suffix of "FooBar"
+/// assert_eq!(obfstr::position!(POOL, "Bar"), 3..6);
+/// assert_eq!(obfstr::rposition!(POOL, "Bar"), 6..9);
+/// ```
+#[macro_export]
+macro_rules! rposition {
+	($haystack:expr, $needle:expr) => {{ const _POSITION_RANGE: ::core::ops::Range<usize> = $crate::rposition($haystack, $needle); _POSITION_RANGE }};
+}
+
+/// Finds the position of the needle in the haystack at compiletime if it is present.
+///
+/// Unlike [`position!`](crate::position!) a missing needle is not an error, the result is `None` instead.
+///
+/// # Examples
+///
+/// ```
+/// use std::ops::Range;
+///
+/// const POOL: &str = concat!("Foo", "Bar");
+/// const MAYBE: Option<Range<usize>> = obfstr::try_position!(POOL, "Baz");
+///
+/// obfstr::obfstr! { let pool = POOL; }
+///
+/// assert_eq!(obfstr::try_position!(POOL, "Bar"), Some(3..6));
+/// if let Some(range) = MAYBE {
+/// 	println!("{}", &pool[range]);
+/// }
+/// ```
+#[macro_export]
+macro_rules! try_position {
+	($haystack:expr, $needle:expr) => {{ const _POSITION_RANGE: ::core::option::Option<::core::ops::Range<usize>> = $crate::try_position($haystack, $needle); _POSITION_RANGE }};
+}
+
+/// Finds the positions of every occurrence of the needle in the haystack at compiletime.
+///
+/// Returns a fixed capacity array of ranges and the number of occurrences found, the unused ranges are `0..0`.
+/// Occurrences may overlap, `"aaa"` is found at offsets 0, 1 and 2 in `"aaaaa"`.
+///
+/// Produces a const-eval error if there are more than the given maximum number of occurrences.
+///
+/// # Examples
+///
+/// ```
+/// const POOL: &str = "abXabYab";
+/// const ALL: ([std::ops::Range<usize>; 4], usize) = obfstr::position_all!(POOL, "ab", 4);
+///
+/// assert_eq!(ALL.1, 3);
+/// assert_eq!(&ALL.0[..ALL.1], &[0..2, 3..5, 6..8]);
+/// ```
+///
+/// Too many occurrences fail to compile:
+///
+/// ```compile_fail
+/// let _ = obfstr::position_all!("aaaaa", "a", 4);
+/// ```
+#[macro_export]
+macro_rules! position_all {
+	($haystack:expr, $needle:expr, $max:expr) => {{
+		const _POSITION_ALL: ([::core::ops::Range<usize>; $max], usize) = $crate::position_all::<{$max}>($haystack, $needle);
+		_POSITION_ALL
+	}};
+}
+
+/// Pools string constants in a single string constant.
+///
+/// Defines the pool as the concatenation of the entries and a `Range<usize>` constant per entry.
+/// The ranges are computed from the offsets of the entries in the pool, a string which also appears earlier in the pool or spanning two adjacent entries is never matched by mistake.
+///
+/// Deobfuscate the pool once with [`obfstr!`](crate::obfstr!) and fetch the entries with [`pool_get`](crate::pool_get):
+///
+/// ```
+/// obfstr::obfpool! { POOL: FOO = "Foo", BAR = "Bar", BAZ = "Baz"; }
+///
+/// obfstr::obfstr! { let pool = POOL; }
+///
+/// assert_eq!(POOL, "FooBarBaz");
+/// assert_eq!(BAR, 3..6);
+/// assert_eq!(obfstr::pool_get(pool, FOO), "Foo");
+/// assert_eq!(obfstr::pool_get(pool, BAR), "Bar");
+/// assert_eq!(obfstr::pool_get(pool, BAZ), "Baz");
+/// ```
+///
+/// The constants take an optional visibility, eg. `obfpool! { pub POOL: pub FOO = "Foo"; }`.
+///
+/// The `#[pack]` form lays out the entries with [`pool_pack`](crate::pool_pack) to share their common substrings.
+/// Entries contained in another entry reuse its bytes and an entry starting with the end of the pool only appends the remainder:
+///
+/// ```
+/// obfstr::obfpool! { #[pack] POOL: ERROR = "ERROR: ", IO = "ERROR: io", READ = "read", IO_READ = "io read"; }
+///
+/// obfstr::obfstr! { let pool = POOL; }
+///
+/// assert_eq!(POOL, "ERROR: io read");
+/// assert_eq!(obfstr::pool_get(pool, ERROR), "ERROR: ");
+/// assert_eq!(obfstr::pool_get(pool, IO), "ERROR: io");
+/// assert_eq!(obfstr::pool_get(pool, READ), "read");
+/// assert_eq!(obfstr::pool_get(pool, IO_READ), "io read");
+/// ```
+///
+/// The `#[shuffle]` form lays out the entries in a per build random order with [`shuffle!`](crate::shuffle!):
+///
+/// ```
+/// obfstr::obfpool! { #[shuffle] POOL: FOO = "Foo", BAR = "Bar", BAZ = "Baz"; }
+///
+/// obfstr::obfstr! { let pool = POOL; }
+///
+/// assert_eq!(POOL.len(), 9);
+/// assert_eq!(obfstr::pool_get(pool, FOO), "Foo");
+/// assert_eq!(obfstr::pool_get(pool, BAR), "Bar");
+/// assert_eq!(obfstr::pool_get(pool, BAZ), "Baz");
+/// ```
+#[macro_export]
+macro_rules! obfpool {
+	(#[shuffle] $vis:vis $pool:ident: $($entry_vis:vis $name:ident = $s:expr),* $(,)? $(;)?) => {
+		$vis const $pool: &str = {
+			const _OBFPOOL_LEN: usize = [$(::core::stringify!($name)),*].len();
+			const _OBFPOOL_ENTRIES: [&str; _OBFPOOL_LEN] = $crate::shuffle!([$($s),*], ::core::stringify!($pool));
+			const _OBFPOOL_CAP: usize = ::core::concat!($($s),*).len();
+			const _OBFPOOL_POOL: [u8; _OBFPOOL_CAP] = $crate::pool_concat::<_OBFPOOL_CAP>(&_OBFPOOL_ENTRIES);
+			$crate::pool_str(&_OBFPOOL_POOL)
+		};
+		$(
+			#[allow(non_upper_case_globals)]
+			$entry_vis const $name: ::core::ops::Range<usize> = $crate::position($pool, $s);
+		)*
+	};
+	(#[pack] $vis:vis $pool:ident: $($entry_vis:vis $name:ident = $s:expr),* $(,)? $(;)?) => {
+		$vis const $pool: &str = {
+			const _OBFPOOL_ENTRIES: &[&str] = &[$($s),*];
+			const _OBFPOOL_CAP: usize = ::core::concat!($($s),*).len();
+			const _OBFPOOL_PACKED: ([u8; _OBFPOOL_CAP], usize) = $crate::pool_pack::<_OBFPOOL_CAP>(_OBFPOOL_ENTRIES);
+			$crate::pool_str(_OBFPOOL_PACKED.0.split_at(_OBFPOOL_PACKED.1).0)
+		};
+		$(
+			#[allow(non_upper_case_globals)]
+			$entry_vis const $name: ::core::ops::Range<usize> = $crate::position($pool, $s);
+		)*
+	};
+	($vis:vis $pool:ident: $($entry_vis:vis $name:ident = $s:expr),* $(,)? $(;)?) => {
+		$vis const $pool: &str = ::core::concat!($($s),*);
+		$crate::__obfpool!($pool, 0usize; $($entry_vis $name = $s,)*);
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfpool {
+	($pool:ident, $offset:expr; $vis:vis $name:ident = $s:expr, $($tail:tt)*) => {
+		#[allow(non_upper_case_globals)]
+		$vis const $name: ::core::ops::Range<usize> = $crate::pool_range($pool, $offset, $s);
+		$crate::__obfpool!($pool, $name.end; $($tail)*);
+	};
+	($pool:ident, $offset:expr;) => {};
+}
+
+/// Returns the range of the pool entry at the given offset.
+///
+/// Produces a const-eval error if the entry does not match the pool at the offset.
+#[doc(hidden)]
+pub const fn pool_range(pool: &str, offset: usize, entry: &str) -> ops::Range<usize> {
+	let pool = pool.as_bytes();
+	let entry = entry.as_bytes();
+	if offset + entry.len() > pool.len() || !check(pool, entry, offset) {
+		panic!("Pool entry does not match the pool");
+	}
+	offset..offset + entry.len()
+}
+
+/// Lays out the pool entries sharing their common substrings.
+///
+/// Entries which are a substring of another entry are not appended, the other entries are appended in order.
+/// An appended entry overlaps with the longest suffix of the pool which is a prefix of the entry.
+/// Finding the shortest common superstring is not attempted.
+///
+/// Returns the packed pool in a buffer of `CAP` bytes and its length, `CAP` must be at least the sum of the lengths of the entries.
+/// The entries are found in the packed pool with [`position`](crate::position).
+///
+/// ```
+/// const PACKED: ([u8; 12], usize) = obfstr::pool_pack::<12>(&["abc", "b", "cde", "efg"]);
+/// assert_eq!(&PACKED.0[..PACKED.1], b"abcdefg");
+/// ```
+#[doc(hidden)]
+pub const fn pool_pack<const CAP: usize>(entries: &[&str]) -> ([u8; CAP], usize) {
+	let mut pool = [0u8; CAP];
+	let mut len = 0;
+	let mut i = 0;
+	while i < entries.len() {
+		let entry = entries[i].as_bytes();
+		if !pool_contained(entries, i) {
+			// Longest suffix of the pool which is a prefix of the entry
+			let mut overlap = if entry.len() > len { len } else { entry.len() };
+			while overlap > 0 && !check(pool.split_at(len).0, entry.split_at(overlap).0, len - overlap) {
+				overlap -= 1;
+			}
+			let mut j = overlap;
+			while j < entry.len() {
+				pool[len] = entry[j];
+				len += 1;
+				j += 1;
+			}
+		}
+		i += 1;
+	}
+	(pool, len)
+}
+
+// Checks if the entry is a substring of a longer entry or equal to an earlier entry.
+const fn pool_contained(entries: &[&str], i: usize) -> bool {
+	let entry = entries[i].as_bytes();
+	let mut j = 0;
+	while j < entries.len() {
+		let other = entries[j].as_bytes();
+		if (other.len() > entry.len() || other.len() == entry.len() && j < i) && search(other, entry) >= 0 {
+			return true;
+		}
+		j += 1;
+	}
+	return false;
+}
+
+/// Concatenates the pool entries in order.
+///
+/// `CAP` must be the sum of the lengths of the entries.
+#[doc(hidden)]
+pub const fn pool_concat<const CAP: usize>(entries: &[&str]) -> [u8; CAP] {
+	let mut pool = [0u8; CAP];
+	let mut len = 0;
+	let mut i = 0;
+	while i < entries.len() {
+		let entry = entries[i].as_bytes();
+		let mut j = 0;
+		while j < entry.len() {
+			pool[len] = entry[j];
+			len += 1;
+			j += 1;
+		}
+		i += 1;
+	}
+	if len != CAP {
+		panic!("Pool capacity does not match the entries");
+	}
+	pool
+}
+
+/// Converts the packed pool to a string.
+///
+/// Overlapping entries start with the same byte so the pool is valid UTF-8.
+#[doc(hidden)]
+pub const fn pool_str(pool: &[u8]) -> &str {
+	match str::from_utf8(pool) {
+		Ok(pool) => pool,
+		Err(_) => panic!("Packed pool is not valid UTF-8"),
+	}
+}
+
+/// Returns the pool entry from the deobfuscated pool.
+///
+/// See [`obfpool!`](crate::obfpool!) for more information.
+#[inline]
+pub fn pool_get(pool: &str, range: ops::Range<usize>) -> &str {
+	&pool[range]
+}
+
+/// Finds the position of the byte needle in the byte haystack at compiletime.
+///
+/// Produces a const-eval error if the needle is not a subslice of the haystack.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(obfstr::position_bytes!(b"\x00\xffhay\xff\x00", b"\xff\x00"), 5..7);
+/// ```
+///
+/// Use this API when pooling binary blobs in a single obfuscated byte string:
+///
+/// ```
+/// const POOL: &[u8] = b"\x7fELF\x00\x01\xff\xfe";
+///
+/// let pool = obfstr::obfbytes!(POOL);
+///
+/// // Later, read the blobs from the pool
+/// assert_eq!(&pool[obfstr::position_bytes!(POOL, b"\x00\x01")], b"\x00\x01");
+/// assert_eq!(&pool[obfstr::position_bytes!(POOL, b"\xff\xfe")], b"\xff\xfe");
+/// ```
+#[macro_export]
+macro_rules! position_bytes {
+	($haystack:expr, $needle:expr) => {{ const _POSITION_RANGE: ::core::ops::Range<usize> = $crate::position_bytes($haystack, $needle); _POSITION_RANGE }};
+}
+
+/// Finds the position of the wide needle in the wide haystack at compiletime.
+///
+/// Either argument is a `&[u16]` constant or a string literal which is encoded with [`wide!`](crate::wide!).
+///
+/// Produces a const-eval error if the needle is not a subslice of the haystack.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(obfstr::position_wide!("haystack", "st"), 3..5);
+/// ```
+///
+/// Use this API when pooling wide strings in a single obfuscated wide string:
+///
+/// ```
+/// const POOL: &[u16] = obfstr::wide!(concat!("Foo\0", "Bar\0", "Baz\0"));
+///
+/// obfstr::obfwide! { let pool = concat!("Foo\0", "Bar\0", "Baz\0"); }
+///
+/// // Later, read the wide strings from the pool
+/// let foo = &pool[obfstr::position_wide!(POOL, "Foo\0")];
+/// let bar = &pool[obfstr::position_wide!(POOL, obfstr::wide!("Bar\0"))];
+/// assert_eq!(foo, obfstr::wide!("Foo\0"));
+/// assert_eq!(bar, obfstr::wide!("Bar\0"));
+/// ```
+#[macro_export]
+macro_rules! position_wide {
+	($haystack:literal, $needle:literal) => {
+		$crate::position_wide!($crate::wide!($haystack), $crate::wide!($needle))
+	};
+	($haystack:literal, $needle:expr) => {
+		$crate::position_wide!($crate::wide!($haystack), $needle)
+	};
+	($haystack:expr, $needle:literal) => {
+		$crate::position_wide!($haystack, $crate::wide!($needle))
+	};
+	($haystack:expr, $needle:expr) => {{ const _POSITION_RANGE: ::core::ops::Range<usize> = $crate::position_wide($haystack, $needle); _POSITION_RANGE }};
 }
 
 /// Finds the position of the needle in the haystack at compiletime.
@@ -40,6 +416,134 @@ macro_rules! position {
 #[doc(hidden)]
 #[inline(always)]
 pub const fn position(haystack: &str, needle: &str) -> ops::Range<usize> {
+	match try_position(haystack, needle) {
+		Some(range) => range,
+		// Panic if substring not found
+		None => panic!("Needle not found in the haystack"),
+	}
+}
+
+/// Finds the position of the needle in the concatenation of the segments at compiletime.
+///
+/// Produces a const-eval error if the needle is not a substring of any segment.
+///
+/// ```
+/// const POSITION: std::ops::Range<usize> = obfstr::position_in_segments(&["FooB", "arX", "Bar"], "Bar");
+/// assert_eq!(POSITION, 7..10);
+/// ```
+#[doc(hidden)]
+pub const fn position_in_segments(segments: &[&str], needle: &str) -> ops::Range<usize> {
+	let mut offset = 0;
+	let mut i = 0;
+	while i < segments.len() {
+		let start = search(segments[i].as_bytes(), needle.as_bytes());
+		if start >= 0 {
+			let start = offset + start as usize;
+			return start..start + needle.len();
+		}
+		offset += segments[i].len();
+		i += 1;
+	}
+	panic!("Needle not found in the segments");
+}
+
+/// Finds the position of the last occurrence of the needle in the haystack at compiletime.
+///
+/// Produces a const-eval error if the needle is not a substring of the haystack.
+///
+/// ```
+/// const POSITION: std::ops::Range<usize> = obfstr::rposition("abXab", "ab");
+/// assert_eq!(POSITION, 3..5);
+/// ```
+#[doc(hidden)]
+#[inline(always)]
+pub const fn rposition(haystack: &str, needle: &str) -> ops::Range<usize> {
+	let start = rsearch(haystack.as_bytes(), needle.as_bytes());
+	// Panic if substring not found
+	if start < 0 {
+		panic!("Needle not found in the haystack");
+	}
+	let start = start as usize;
+	start..start + needle.len()
+}
+
+/// Finds the position of the char needle in the haystack at compiletime.
+///
+/// Returns the byte range of the encoded character, produces a const-eval error if the character is not in the haystack.
+///
+/// ```
+/// const POSITION: std::ops::Range<usize> = obfstr::position_char("Hello 🌍!", '🌍');
+/// assert_eq!(POSITION, 6..10);
+/// ```
+#[doc(hidden)]
+#[inline(always)]
+pub const fn position_char(haystack: &str, needle: char) -> ops::Range<usize> {
+	let mut buf = [0u8; 4];
+	let needle = needle.encode_utf8(&mut buf);
+	position(haystack, needle)
+}
+
+/// Finds the position of the needle in the haystack at compiletime if it is present.
+///
+/// ```
+/// const POSITION: Option<std::ops::Range<usize>> = obfstr::try_position("haystack", "needle");
+/// assert_eq!(POSITION, None);
+/// ```
+#[doc(hidden)]
+#[inline(always)]
+pub const fn try_position(haystack: &str, needle: &str) -> Option<ops::Range<usize>> {
+	let start = search(haystack.as_bytes(), needle.as_bytes());
+	if start < 0 {
+		return None;
+	}
+	let start = start as usize;
+	Some(start..start + needle.len())
+}
+
+/// Finds the positions of every occurrence of the needle in the haystack at compiletime.
+///
+/// Produces a const-eval error if there are more than `MAX` occurrences.
+///
+/// ```
+/// const ALL: ([std::ops::Range<usize>; 4], usize) = obfstr::position_all::<4>("aaaaa", "aaa");
+/// assert_eq!(ALL, ([0..3, 1..4, 2..5, 0..0], 3));
+/// ```
+#[doc(hidden)]
+pub const fn position_all<const MAX: usize>(haystack: &str, needle: &str) -> ([ops::Range<usize>; MAX], usize) {
+	const EMPTY: ops::Range<usize> = 0..0;
+	let haystack = haystack.as_bytes();
+	let needle = needle.as_bytes();
+	let mut ranges = [EMPTY; MAX];
+	let mut count = 0;
+	let mut offset = 0;
+	// Resume the search one past the previous occurrence to find overlapping occurrences
+	while offset <= haystack.len() {
+		let start = search(haystack.split_at(offset).1, needle);
+		if start < 0 {
+			break;
+		}
+		if count >= MAX {
+			panic!("Too many occurrences of the needle in the haystack");
+		}
+		let start = offset + start as usize;
+		ranges[count] = start..start + needle.len();
+		count += 1;
+		offset = start + 1;
+	}
+	(ranges, count)
+}
+
+/// Finds the position of the byte needle in the byte haystack at compiletime.
+///
+/// Produces a const-eval error if the needle is not a subslice of the haystack.
+///
+/// ```
+/// const POSITION: std::ops::Range<usize> = obfstr::position_bytes(b"\x00\xff\x00", b"\xff\x00");
+/// assert_eq!(POSITION, 1..3);
+/// ```
+#[doc(hidden)]
+#[inline(always)]
+pub const fn position_bytes(haystack: &[u8], needle: &[u8]) -> ops::Range<usize> {
 	let start = search(haystack, needle);
 	// Panic if substring not found
 	if start < 0 {
@@ -49,22 +553,39 @@ pub const fn position(haystack: &str, needle: &str) -> ops::Range<usize> {
 	start..start + needle.len()
 }
 
-const fn search(haystack: &str, needle: &str) -> isize {
+/// Finds the position of the wide needle in the wide haystack at compiletime.
+///
+/// Produces a const-eval error if the needle is not a subslice of the haystack.
+///
+/// ```
+/// const POSITION: std::ops::Range<usize> = obfstr::position_wide(obfstr::wide!("haystack"), obfstr::wide!("st"));
+/// assert_eq!(POSITION, 3..5);
+/// ```
+#[doc(hidden)]
+#[inline(always)]
+pub const fn position_wide(haystack: &[u16], needle: &[u16]) -> ops::Range<usize> {
+	let start = search_wide(haystack, needle);
+	// Panic if substring not found
+	if start < 0 {
+		panic!("Needle not found in the haystack");
+	}
+	let start = start as usize;
+	start..start + needle.len()
+}
+
+const fn search(haystack: &[u8], needle: &[u8]) -> isize {
 	// Short-circuit empty needles
 	if needle.len() == 0 {
 		return 0;
 	}
 
-	let haystack = haystack.as_bytes();
-	let needle = needle.as_bytes();
-
 	// Avoid overflow checks later
 	if needle.len() <= haystack.len() {
 		// Special case for needle length of 1
 		if needle.len() == 1 {
 			let needle = needle[0];
 			let mut offset = 0;
-			while offset <= haystack.len() {
+			while offset < haystack.len() {
 				if haystack[offset] == needle {
 					return offset as isize;
 				}
@@ -100,7 +621,66 @@ const fn search(haystack: &str, needle: &str) -> isize {
 	return -1;
 }
 
+// Simple backward scan, empty needles are found at the end of the haystack.
+const fn rsearch(haystack: &[u8], needle: &[u8]) -> isize {
+	if needle.len() <= haystack.len() {
+		let mut offset = haystack.len() - needle.len();
+		loop {
+			if check(haystack, needle, offset) {
+				return offset as isize;
+			}
+			if offset == 0 {
+				break;
+			}
+			offset -= 1;
+		}
+	}
+	return -1;
+}
+
+// Quicksearch with the jump table indexed by the low byte of the words.
+// Words sharing the low byte share the smallest jump, which keeps the search correct.
+const fn search_wide(haystack: &[u16], needle: &[u16]) -> isize {
+	// Short-circuit empty needles
+	if needle.len() == 0 {
+		return 0;
+	}
 
+	// Avoid overflow checks later
+	if needle.len() <= haystack.len() {
+		// Initialize the jump table
+		let mut jumps = [max(needle.len()); 256];
+		let tail = needle.len() - 1;
+		let mut i = 0;
+		while i < tail {
+			jumps[needle[i] as u8 as usize] = max(tail - i);
+			i += 1;
+		}
+		// Find the needle
+		let sentinel = needle[tail];
+		let mut offset = 0;
+		while offset < haystack.len() - tail {
+			let chr = haystack[offset + tail];
+			if chr == sentinel && check_wide(haystack, needle, offset) {
+				return offset as isize;
+			}
+			offset += jumps[chr as u8 as usize] as usize;
+		}
+	}
+	return -1;
+}
+
+#[inline(always)]
+const fn check_wide(haystack: &[u16], needle: &[u16], offset: usize) -> bool {
+	let mut i = 0;
+	while i < needle.len() {
+		if haystack[offset + i] != needle[i] {
+			return false;
+		}
+		i += 1;
+	}
+	return true;
+}
 #[inline(always)]
 const fn check(haystack: &[u8], needle: &[u8], offset: usize) -> bool {
 	let mut i = 0;
@@ -123,8 +703,219 @@ fn test_position() {
 	assert_eq!(position("ABCBC", "ABCBC"), 0..5);
 }
 
+#[test]
+fn test_position_char() {
+	assert_eq!(position_char("a,b", ','), 1..2);
+	assert_eq!(position_char("caf\u{e9}", '\u{e9}'), 3..5);
+	// The leading byte 0xc3 of 'ü' and 'é' is shared
+	assert_eq!(position_char("\u{fc}ber caf\u{e9}", '\u{e9}'), 9..11);
+	// The leading bytes 0xf0 0x9f of '😀' and '🌍' are shared
+	assert_eq!(position_char("\u{1F600} \u{1F30D}", '\u{1F30D}'), 5..9);
+	assert_eq!(position!("\u{1F600} \u{1F30D}", '\u{1F30D}'), 5..9);
+	assert_eq!(position!("\u{fc}ber caf\u{e9}", '\u{e9}'), 9..11);
+	assert_eq!(position!("a,b", ','), 1..2);
+	assert_eq!(position!("a,b", "b"), 2..3);
+}
+
+#[test]
+#[should_panic]
+fn test_position_char_not_found() {
+	let _ = position_char("caf\u{e9}", '\u{e8}');
+}
+
+#[test]
+fn test_position_single_byte() {
+	assert_eq!(position("ABCBC", "C"), 2..3);
+	assert_eq!(position("ABCBX", "X"), 4..5);
+	assert_eq!(position("X", "X"), 0..1);
+	assert_eq!(try_position("ABCBC", "X"), None);
+	assert_eq!(try_position("A", "X"), None);
+	assert_eq!(position_bytes(b"\x00\x01\xff", b"\xff"), 2..3);
+	assert_eq!(try_position!("ABCBC", "X"), None);
+}
+
+#[test]
+#[should_panic(expected = "Needle not found in the haystack")]
+fn test_position_single_byte_not_found() {
+	let _ = position("ABCBC", "X");
+}
+
+#[test]
+#[should_panic(expected = "Needle not found in the haystack")]
+fn test_position_single_byte_haystack_not_found() {
+	let _ = position("A", "X");
+}
+
+#[test]
+fn test_position_in_segments() {
+	const SEGMENTS: &[&str] = &["FooB", "arX", "Bar"];
+	const POOL: &str = concat!("FooB", "arX", "Bar");
+	// The plain search finds "Bar" spanning the first two segments
+	assert_eq!(position(POOL, "Bar"), 3..6);
+	assert_eq!(position_in_segments(SEGMENTS, "Bar"), 7..10);
+	assert_eq!(&POOL[position_in_segments!(SEGMENTS, "Bar")], "Bar");
+	assert_eq!(position_in_segments(SEGMENTS, "oB"), 2..4);
+	assert_eq!(position_in_segments(SEGMENTS, "rX"), 5..7);
+	assert_eq!(position_in_segments(&["", "Foo"], "Foo"), 0..3);
+}
+
+#[test]
+#[should_panic]
+fn test_position_in_segments_straddling() {
+	let _ = position_in_segments(&["FooB", "arX"], "Bar");
+}
+
+#[test]
+fn test_rposition() {
+	assert_eq!(rposition("ABCBC", "BC"), 3..5);
+	assert_eq!(rposition("ABCBC", "ABCBC"), 0..5);
+	assert_eq!(rposition("ABCBC", "AB"), 0..2);
+	assert_eq!(rposition("aaaaa", "aa"), 3..5);
+	assert_eq!(rposition("FooBarBar", "Bar"), 6..9);
+	assert_eq!(rposition("haystack", ""), 8..8);
+	assert_eq!(rposition!("FooBarBar", "oBa"), 2..5);
+}
+
+#[test]
+#[should_panic]
+fn test_rposition_not_found() {
+	let _ = rposition("haystack", "needleneedleneedle");
+}
+
+#[test]
+fn test_obfpool() {
+	// "Bar" is a suffix of "FooBar" and "oBa" spans two entries
+	obfpool! { POOL: FOOBAR = "FooBar", BAR = "Bar", OBA = "oBa", EMPTY = "", Baz = "Baz", }
+	crate::obfstr! { let pool = POOL; }
+	assert_eq!(POOL, "FooBarBaroBaBaz");
+	assert_eq!(pool_get(pool, FOOBAR), "FooBar");
+	assert_eq!(pool_get(pool, BAR), "Bar");
+	assert_eq!(pool_get(pool, OBA), "oBa");
+	assert_eq!(pool_get(pool, EMPTY), "");
+	assert_eq!(pool_get(pool, Baz), "Baz");
+	assert_eq!((BAR, OBA, EMPTY), (6..9, 9..12, 12..12));
+}
+
+#[test]
+fn test_pool_pack() {
+	const ENTRIES: [&str; 8] = ["ERROR: ", "ERROR: file not found", "file", "not found: ", "found: path", "WARN: ", "", "path"];
+	const NAIVE: &str = concat!("ERROR: ", "ERROR: file not found", "file", "not found: ", "found: path", "WARN: ", "", "path");
+	const PACKED: ([u8; NAIVE.len()], usize) = pool_pack::<{NAIVE.len()}>(&ENTRIES);
+	let pool = pool_str(&PACKED.0[..PACKED.1]);
+	assert_eq!(pool, "ERROR: file not found: pathWARN: ");
+	assert!(pool.len() < NAIVE.len());
+	for entry in ENTRIES {
+		assert_eq!(pool_get(pool, position(pool, entry)), entry);
+	}
+
+	// Overlapping multibyte characters stay intact
+	assert_eq!(pool_pack::<7>(&["\u{e9}t\u{e9}", "\u{e9}x"]), (*b"\xc3\xa9t\xc3\xa9x\0", 6));
+
+	obfpool! { #[pack] POOL: A = "abc", B = "b", C = "cde", D = "abc", E = "efg"; }
+	crate::obfstr! { let pool = POOL; }
+	assert_eq!(POOL, "abcdefg");
+	assert_eq!((A, B, C, D, E), (0..3, 1..2, 2..5, 0..3, 4..7));
+	assert_eq!([pool_get(pool, A), pool_get(pool, C), pool_get(pool, E)], ["abc", "cde", "efg"]);
+}
+
+#[test]
+fn test_pool_shuffle() {
+	obfpool! { #[shuffle] POOL: A = "alpha", B = "beta", C = "gamma", D = "delta", E = "epsilon", F = "zeta", G = "eta", H = "theta"; }
+	crate::obfstr! { let pool = POOL; }
+	assert_eq!(POOL.len(), "alphabetagammadeltaepsilonzetaetatheta".len());
+	assert_ne!(POOL, "alphabetagammadeltaepsilonzetaetatheta");
+	for (range, entry) in [(A, "alpha"), (B, "beta"), (C, "gamma"), (D, "delta"), (E, "epsilon"), (F, "zeta"), (G, "eta"), (H, "theta")] {
+		assert_eq!(pool_get(pool, range), entry);
+	}
+	assert_eq!(pool_concat::<6>(&["ab", "", "cde", "f"]), *b"abcdef");
+}
+
+#[test]
+#[should_panic]
+fn test_pool_range_mismatch() {
+	let _ = pool_range("FooBar", 2, "Bar");
+}
+
+#[test]
+fn test_try_position() {
+	assert_eq!(try_position("ABCBC", "CBC"), Some(2..5));
+	assert_eq!(try_position("ABCBC", "CBA"), None);
+	assert_eq!(try_position("haystack", "needleneedleneedle"), None);
+	assert_eq!(try_position("haystack", ""), Some(0..0));
+	assert_eq!(try_position("", ""), Some(0..0));
+	assert_eq!(try_position!("haystack", "k"), Some(7..8));
+	assert_eq!(try_position!("haystack", "x"), None);
+	const MAYBE: Option<ops::Range<usize>> = try_position!("haystack", "hay");
+	const LEN: usize = if let Some(range) = MAYBE { range.end - range.start } else { 0 };
+	assert_eq!(LEN, 3);
+}
+
+#[test]
+fn test_position_all() {
+	// Overlapping occurrences
+	assert_eq!(position_all::<4>("aaaaa", "aaa"), ([0..3, 1..4, 2..5, 0..0], 3));
+	assert_eq!(position_all::<2>("abab", "abab"), ([0..4, 0..0], 1));
+	// Non-overlapping occurrences
+	assert_eq!(position_all::<3>("Foo.Bar.Foo", "Foo"), ([0..3, 8..11, 0..0], 2));
+	assert_eq!(position_all::<3>("a.b.c", "."), ([1..2, 3..4, 0..0], 2));
+	assert_eq!(position_all::<2>("haystack", "needle"), ([0..0, 0..0], 0));
+	assert_eq!(position_all!("abXabYab", "ab", 3), ([0..2, 3..5, 6..8], 3));
+}
+
+#[test]
+#[should_panic]
+fn test_position_all_overflow() {
+	let _ = position_all::<2>("aaaaa", "aaa");
+}
+
 #[test]
 #[should_panic]
 fn test_position_needle_longer_than_haystack() {
 	let _ = position("haystack", "needleneedleneedle");
 }
+
+#[test]
+fn test_position_bytes() {
+	assert_eq!(position_bytes(b"ABCBC", b"CBC"), 2..5);
+	assert_eq!(position_bytes(b"ABCBC", b"ABCBC"), 0..5);
+	assert_eq!(position_bytes(b"\x00\xff\x00\xff\xff", b"\xff\xff"), 3..5);
+	assert_eq!(position_bytes(b"\xff\xff\x00", b"\x00"), 2..3);
+	assert_eq!(position_bytes(b"\x01\x00\x00\xff", b"\x00\xff"), 2..4);
+	assert_eq!(position_bytes!(b"\x7fELF\x00", b"\x00"), 4..5);
+}
+
+#[test]
+#[should_panic]
+fn test_position_bytes_not_found() {
+	let _ = position_bytes(b"\x00\xff\x00", b"\xff\xff");
+}
+
+#[test]
+fn test_position_wide() {
+	assert_eq!(position_wide(crate::wide!("ABCBC"), crate::wide!("CBC")), 2..5);
+	assert_eq!(position_wide(crate::wide!("ABCBC"), crate::wide!("ABCBC")), 0..5);
+	assert_eq!(position_wide(&[0x0141, 0x0241, 0x0141], &[0x0141]), 0..1);
+	// Words sharing the low byte with the needle do not confuse the jump table
+	assert_eq!(position_wide(&[0x0141, 0x0241, 0x0142, 0x0141, 0x0142], &[0x0141, 0x0142]), 3..5);
+	assert_eq!(position_wide!("haystack", "ck"), 6..8);
+	assert_eq!(position_wide!(crate::wide!("haystack"), "hay"), 0..3);
+	assert_eq!(position_wide!("haystack", crate::wide!("k")), 7..8);
+}
+
+#[test]
+fn test_position_wide_surrogates() {
+	// Needles from valid strings never match half of a surrogate pair
+	const POOL: &[u16] = crate::wide!("a\u{1F600}\u{1F601}b\u{1F600}");
+	assert_eq!(position_wide!(POOL, "\u{1F601}"), 3..5);
+	assert_eq!(position_wide!(POOL, "\u{1F601}b\u{1F600}"), 3..8);
+	assert_eq!(position_wide!(POOL, "\u{1F600}"), 1..3);
+	assert_eq!(position_wide!(POOL, "b\u{1F600}"), 5..8);
+	let range = position_wide!(POOL, "\u{1F600}\u{1F601}b");
+	assert_eq!(String::from_utf16(&POOL[range]).unwrap(), "\u{1F600}\u{1F601}b");
+}
+
+#[test]
+#[should_panic]
+fn test_position_wide_not_found() {
+	let _ = position_wide(crate::wide!("haystack"), crate::wide!("needle"));
+}
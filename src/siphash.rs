@@ -0,0 +1,145 @@
+/*!
+SipHash, a keyed hash function resistant to crafted collisions.
+*/
+
+/// Keys of the [`siphash!`](crate::siphash!) macro without explicit keys.
+///
+/// Derived from [`SEED`](crate::SEED), hash runtime input with these keys to compare against the compiletime hashes.
+pub const SIPHASH_KEYS: (u64, u64) = (crate::entropy("siphash k0"), crate::entropy("siphash k1"));
+
+/// Compiletime SipHash-1-3 hash.
+///
+/// Accepts a string or a byte string, guarantees compiletime evaluation of the hash.
+/// Without keys the hash is keyed with [`SIPHASH_KEYS`](crate::SIPHASH_KEYS), which differ per build.
+///
+/// ```
+/// const HASH: u64 = obfstr::siphash!("user input");
+/// let (k0, k1) = obfstr::SIPHASH_KEYS;
+/// assert_eq!(obfstr::siphash13(k0, k1, b"user input"), HASH);
+///
+/// assert_eq!(obfstr::siphash!(b"", 0x0706050403020100, 0x0f0e0d0c0b0a0908), 0xabac0158050fc4dc);
+/// ```
+#[macro_export]
+macro_rules! siphash {
+	($s:expr, $k0:expr, $k1:expr) => {{ const _SIPHASH_HASH: u64 = $crate::siphash13($k0, $k1, $crate::AsBytes($s).bytes()); _SIPHASH_HASH }};
+	($s:expr) => {{ const _SIPHASH_HASH: u64 = $crate::siphash13($crate::SIPHASH_KEYS.0, $crate::SIPHASH_KEYS.1, $crate::AsBytes($s).bytes()); _SIPHASH_HASH }};
+}
+
+/// SipHash-1-3 keyed hash function.
+///
+/// Prefer this over [`murmur3`](crate::murmur3()) when an attacker controls the input and must not be able to craft collisions with the embedded hashes.
+/// Collisions can only be crafted with knowledge of the keys, murmur3 and DJB2 collide regardless of their key.
+/// The keys are embedded in the binary, this only helps against attackers who do not have access to it.
+///
+/// This is the variant currently used by the hasher of the standard library's `HashMap`.
+///
+/// ```
+/// assert_eq!(obfstr::siphash13(0, 0, b""), 0xd1fba762150c532c);
+/// ```
+#[inline]
+pub const fn siphash13(k0: u64, k1: u64, data: &[u8]) -> u64 {
+	siphash::<1, 3>(k0, k1, data)
+}
+
+/// SipHash-2-4 keyed hash function.
+///
+/// The original and more conservative variant of [`siphash13`].
+///
+/// ```
+/// assert_eq!(obfstr::siphash24(0x0706050403020100, 0x0f0e0d0c0b0a0908, b""), 0x726fdb47dd0e0e31);
+/// ```
+#[inline]
+pub const fn siphash24(k0: u64, k1: u64, data: &[u8]) -> u64 {
+	siphash::<2, 4>(k0, k1, data)
+}
+
+#[inline(always)]
+const fn sipround(v: &mut [u64; 4]) {
+	v[0] = v[0].wrapping_add(v[1]); v[1] = v[1].rotate_left(13); v[1] ^= v[0]; v[0] = v[0].rotate_left(32);
+	v[2] = v[2].wrapping_add(v[3]); v[3] = v[3].rotate_left(16); v[3] ^= v[2];
+	v[0] = v[0].wrapping_add(v[3]); v[3] = v[3].rotate_left(21); v[3] ^= v[0];
+	v[2] = v[2].wrapping_add(v[1]); v[1] = v[1].rotate_left(17); v[1] ^= v[2]; v[2] = v[2].rotate_left(32);
+}
+
+#[inline(always)]
+const fn compress<const ROUNDS: usize>(v: &mut [u64; 4], m: u64) {
+	v[3] ^= m;
+	let mut i = 0;
+	while i < ROUNDS {
+		sipround(v);
+		i += 1;
+	}
+	v[0] ^= m;
+}
+
+const fn siphash<const C: usize, const D: usize>(k0: u64, k1: u64, data: &[u8]) -> u64 {
+	let mut v = [
+		k0 ^ 0x736f6d6570736575,
+		k1 ^ 0x646f72616e646f6d,
+		k0 ^ 0x6c7967656e657261,
+		k1 ^ 0x7465646279746573,
+	];
+
+	let len = data.len();
+	let mut i = 0;
+	while i + 8 <= len {
+		let m = u64::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3], data[i + 4], data[i + 5], data[i + 6], data[i + 7]]);
+		compress::<C>(&mut v, m);
+		i += 8;
+	}
+
+	// The last block holds the remaining bytes and the length
+	let mut m = (len as u64) << 56;
+	let mut j = 0;
+	while i + j < len {
+		m |= (data[i + j] as u64) << (j * 8);
+		j += 1;
+	}
+	compress::<C>(&mut v, m);
+
+	v[2] ^= 0xff;
+	let mut i = 0;
+	while i < D {
+		sipround(&mut v);
+		i += 1;
+	}
+	return v[0] ^ v[1] ^ v[2] ^ v[3];
+}
+
+#[test]
+fn test_vectors() {
+	// Reference test vectors, key 00..0f and message 00..len
+	static SIPHASH13: [u64; 16] = [
+		0xabac0158050fc4dc, 0xc9f49bf37d57ca93, 0x82cb9b024dc7d44d, 0x8bf80ab8e7ddf7fb,
+		0xcf75576088d38328, 0xdef9d52f49533b67, 0xc50d2b50c59f22a7, 0xd3927d989bb11140,
+		0x369095118d299a8e, 0x25a48eb36c063de4, 0x79de85ee92ff097f, 0x70c118c1f94dc352,
+		0x78a384b157b4d9a2, 0x306f760c1229ffa7, 0x605aa111c0f95d34, 0xd320d86d2a519956,
+	];
+	static SIPHASH24: [u64; 16] = [
+		0x726fdb47dd0e0e31, 0x74f839c593dc67fd, 0x0d6c8009d9a94f5a, 0x85676696d7fb7e2d,
+		0xcf2794e0277187b7, 0x18765564cd99a68d, 0xcbc9466e58fee3ce, 0xab0200f58b01d137,
+		0x93f5f5799a932462, 0x9e0082df0ba9e4b0, 0x7a5dbbc594ddb9f3, 0xf4b32f46226bada7,
+		0x751e8fbc860ee5fb, 0x14ea5627c0843d90, 0xf723ca908e7af2ee, 0xa129ca6149be45e5,
+	];
+	let k0 = 0x0706050403020100;
+	let k1 = 0x0f0e0d0c0b0a0908;
+	let message: [u8; 16] = core::array::from_fn(|i| i as u8);
+	for len in 0..16 {
+		assert_eq!(siphash13(k0, k1, &message[..len]), SIPHASH13[len]);
+		assert_eq!(siphash24(k0, k1, &message[..len]), SIPHASH24[len]);
+	}
+}
+
+#[test]
+fn test_default_hasher() {
+	// The standard library hashes with SipHash-1-3 keyed with zeros
+	use std::hash::Hasher;
+	let data: Vec<u8> = (0..100).map(|i| crate::splitmix(i) as u8).collect();
+	for len in 0..data.len() {
+		let mut hasher = std::hash::DefaultHasher::new();
+		hasher.write(&data[..len]);
+		assert_eq!(siphash13(0, 0, &data[..len]), hasher.finish());
+	}
+	let (k0, k1) = SIPHASH_KEYS;
+	assert_eq!(siphash!("Hello world"), siphash13(k0, k1, b"Hello world"));
+}
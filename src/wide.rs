@@ -20,7 +20,7 @@ macro_rules! wide {
 	}};
 }
 
-const fn next(bytes: &[u8]) -> Option<(u32, &[u8])> {
+pub(crate) const fn next(bytes: &[u8]) -> Option<(u32, &[u8])> {
 	match bytes {
 		&[a, ref tail @ ..] if a & 0x80 == 0x00 =>
 			Some((a as u32, tail)),
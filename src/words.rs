@@ -3,9 +3,20 @@ Wide string obfuscation
 =======================
 */
 
-use core::ptr::{read_volatile, write};
+use core::hint;
+use core::mem::MaybeUninit;
+use core::ptr::{read_volatile, write, write_volatile};
+use crate::bytes::{assume_init, Buffer};
 
 /// Compiletime wide string constant obfuscation.
+///
+/// The keyed form `obfwide!(rk => s)` decodes to garbage unless `rk` equals [`RUNTIME_KEY`](crate::RUNTIME_KEY).
+///
+/// The `#[verified]` form verifies the deobfuscated string against a checksum of its plaintext, see [`obfstr!`](crate::obfstr):
+///
+/// ```
+/// assert_eq!(obfstr::obfwide!(#[verified] "Hello"), obfstr::wide!("Hello"));
+/// ```
 #[macro_export]
 macro_rules! obfwide {
 	($(let $name:ident = $s:expr;)*) => {
@@ -16,33 +27,259 @@ macro_rules! obfwide {
 		&$name
 	}};
 	($buf:ident <- $s:expr) => {{
-		let buf = &mut $buf[..$s.len()];
-		buf.copy_from_slice(&$crate::__obfwide!($s));
-		buf
+		let buf = &mut $buf[..$crate::wide!($s).len()];
+		$crate::__obfwide!(buf <- $s)
 	}};
+	(#[selfkey] $s:expr) => {
+		&$crate::__obfwide_selfkeyed!($s)
+	};
+	(#[padded] $s:expr) => {
+		&*$crate::__obfwide_padded!($s)
+	};
+	(#[verified] $s:expr) => {
+		&$crate::__obfwide_verified!($crate::bytes::verify_failed; $s)
+	};
+	(#[verified($hook:expr)] $s:expr) => {
+		&$crate::__obfwide_verified!($hook; $s)
+	};
+	($rk:expr => $s:expr) => {
+		&$crate::__obfwide_keyed!($rk, $s)
+	};
 	($s:expr) => {
 		&$crate::__obfwide!($s)
 	};
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfwide_keyed {
+	($rk:expr, $s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFWIDE_STRING: &[u16] = $crate::wide!($s);
+		const _OBFWIDE_LEN: usize = _OBFWIDE_STRING.len();
+		const _OBFWIDE_KEYSTREAM: [u16; _OBFWIDE_LEN] = $crate::words::keys::<_OBFWIDE_LEN>($crate::random!(u64, "key", stringify!($s)));
+		static _OBFWIDE_SDATA: [u16; _OBFWIDE_LEN] = $crate::words::obfuscate::<_OBFWIDE_LEN>(_OBFWIDE_STRING,
+			&$crate::words::fold_key::<_OBFWIDE_LEN>(&_OBFWIDE_KEYSTREAM, $crate::RUNTIME_KEY));
+		$crate::words::deobfuscate_keyed::<_OBFWIDE_LEN>(
+			$crate::xref::xref::<_,
+				{$crate::random!(u32, "offset", stringify!($s))},
+				{$crate::random!(u64, "xref", stringify!($s))}>
+				(&_OBFWIDE_SDATA),
+			&_OBFWIDE_KEYSTREAM,
+			$rk)
+	}};
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __obfwide {
+	($buf:ident <- $s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFWIDE_STRING: &[u16] = $crate::wide!($s);
+		const _OBFWIDE_LEN: usize = _OBFWIDE_STRING.len();
+		const _OBFWIDE_MASK: u64 = if $crate::bytes::MASKED { $crate::random!(u64, "mask", stringify!($s)) } else { 0 };
+		const _OBFWIDE_KEYS: [u16; _OBFWIDE_LEN] = $crate::words::keys::<_OBFWIDE_LEN>($crate::random!(u64, "key", stringify!($s)));
+		const _OBFWIDE_KEYSTREAM: [u16; _OBFWIDE_LEN] = $crate::words::fold_key::<_OBFWIDE_LEN>(&_OBFWIDE_KEYS, _OBFWIDE_MASK);
+		const _OBFWIDE_CIPHER2: bool = $crate::random!(bool, "cipher", stringify!($s));
+		static _OBFWIDE_SDATA: [u16; _OBFWIDE_LEN] = if _OBFWIDE_CIPHER2 {
+			$crate::words::obfuscate2::<_OBFWIDE_LEN>(_OBFWIDE_STRING, &_OBFWIDE_KEYS)
+		}
+		else {
+			$crate::words::obfuscate::<_OBFWIDE_LEN>(_OBFWIDE_STRING, &_OBFWIDE_KEYS)
+		};
+		let sdata = $crate::xref::xref::<_,
+			{$crate::random!(u32, "offset", stringify!($s))},
+			{$crate::random!(u64, "xref", stringify!($s))}>
+			(&_OBFWIDE_SDATA);
+		let keys = $crate::words::unmask_keys::<_OBFWIDE_LEN>(&_OBFWIDE_KEYSTREAM, $crate::bytes::mask_parts(_OBFWIDE_MASK));
+		if _OBFWIDE_CIPHER2 {
+			$crate::words::deobfuscate2_into::<_OBFWIDE_LEN>(sdata, &keys, $buf)
+		}
+		else {
+			$crate::words::deobfuscate_into::<_OBFWIDE_LEN>(sdata, &keys, $buf)
+		}
+	}};
 	($s:expr) => {{
 		use ::core::primitive::*;
 		const _OBFWIDE_STRING: &[u16] = $crate::wide!($s);
 		const _OBFWIDE_LEN: usize = _OBFWIDE_STRING.len();
-		const _OBFWIDE_KEYSTREAM: [u16; _OBFWIDE_LEN] = $crate::words::keystream::<_OBFWIDE_LEN>($crate::random!(u32, "key", stringify!($s)));
-		static _OBFWIDE_SDATA: [u16; _OBFWIDE_LEN] = $crate::words::obfuscate::<_OBFWIDE_LEN>(_OBFWIDE_STRING, &_OBFWIDE_KEYSTREAM);
-		$crate::words::deobfuscate::<_OBFWIDE_LEN>(
+		const _OBFWIDE_MASK: u64 = if $crate::bytes::MASKED { $crate::random!(u64, "mask", stringify!($s)) } else { 0 };
+		const _OBFWIDE_MASK_PARTS: [u16; 4] = $crate::bytes::mask_parts(_OBFWIDE_MASK);
+		const _OBFWIDE_KEYS: [u16; _OBFWIDE_LEN] = $crate::words::keys::<_OBFWIDE_LEN>($crate::random!(u64, "key", stringify!($s)));
+		const _OBFWIDE_KEYSTREAM: [u16; _OBFWIDE_LEN] = $crate::words::fold_key::<_OBFWIDE_LEN>(&_OBFWIDE_KEYS, _OBFWIDE_MASK);
+		const _OBFWIDE_CIPHER2: bool = $crate::random!(bool, "cipher", stringify!($s));
+		static _OBFWIDE_SDATA: [u16; _OBFWIDE_LEN] = if _OBFWIDE_CIPHER2 {
+			$crate::words::obfuscate2::<_OBFWIDE_LEN>(_OBFWIDE_STRING, &_OBFWIDE_KEYS)
+		}
+		else {
+			$crate::words::obfuscate::<_OBFWIDE_LEN>(_OBFWIDE_STRING, &_OBFWIDE_KEYS)
+		};
+		let sdata = $crate::xref::xref::<_,
+			{$crate::random!(u32, "offset", stringify!($s))},
+			{$crate::random!(u64, "xref", stringify!($s))}>
+			(&_OBFWIDE_SDATA);
+		if _OBFWIDE_CIPHER2 {
+			$crate::words::deobfuscate2::<_OBFWIDE_LEN>(sdata, &$crate::words::unmask_keys::<_OBFWIDE_LEN>(&_OBFWIDE_KEYSTREAM, _OBFWIDE_MASK_PARTS))
+		}
+		else if $crate::bytes::MASKED {
+			$crate::words::deobfuscate_masked::<_OBFWIDE_LEN>(sdata, &_OBFWIDE_KEYSTREAM, _OBFWIDE_MASK_PARTS)
+		}
+		else {
+			$crate::words::deobfuscate::<_OBFWIDE_LEN>(sdata, &_OBFWIDE_KEYSTREAM)
+		}
+	}};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfwide_selfkeyed {
+	($s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFWIDE_STRING: &[u16] = $crate::wide!($s);
+		const _OBFWIDE_LEN: usize = _OBFWIDE_STRING.len();
+		const _OBFWIDE_KEYSTREAM: [u16; _OBFWIDE_LEN] = $crate::words::keys::<_OBFWIDE_LEN>($crate::random!(u64, "key", stringify!($s)));
+		const _OBFWIDE_BIAS: usize = $crate::random!(u32, "bias", stringify!($s)) as usize;
+		static _OBFWIDE_SDATA: [u16; _OBFWIDE_LEN] = $crate::words::obfuscate::<_OBFWIDE_LEN>(_OBFWIDE_STRING,
+			&$crate::words::fold_key::<_OBFWIDE_LEN>(&_OBFWIDE_KEYSTREAM, $crate::bytes::selfkey(_OBFWIDE_BIAS)));
+		static _OBFWIDE_ANCHOR: $crate::bytes::Anchor = $crate::bytes::Anchor((_OBFWIDE_SDATA.as_ptr() as *const u8).wrapping_add(_OBFWIDE_BIAS));
+		$crate::words::deobfuscate_selfkeyed::<_OBFWIDE_LEN>(
 			$crate::xref::xref::<_,
 				{$crate::random!(u32, "offset", stringify!($s))},
 				{$crate::random!(u64, "xref", stringify!($s))}>
 				(&_OBFWIDE_SDATA),
+			&_OBFWIDE_ANCHOR,
 			&_OBFWIDE_KEYSTREAM)
 	}};
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfwide_padded {
+	($s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFWIDE_STRING: &[u16] = $crate::wide!($s);
+		const _OBFWIDE_PADDED: usize = $crate::bytes::padded_len(_OBFWIDE_STRING.len(), $crate::random!(u64, "padding", stringify!($s)));
+		const _OBFWIDE_LEN_KEY: u64 = $crate::random!(u64, "len", stringify!($s));
+		const _OBFWIDE_KEYSTREAM: [u16; _OBFWIDE_PADDED] = $crate::words::keys::<_OBFWIDE_PADDED>($crate::random!(u64, "key", stringify!($s)));
+		static _OBFWIDE_SDATA: $crate::bytes::Padded<[u16; _OBFWIDE_PADDED]> = $crate::bytes::Padded {
+			data: $crate::words::obfuscate::<_OBFWIDE_PADDED>(
+				&$crate::words::pad::<_OBFWIDE_PADDED>(_OBFWIDE_STRING, $crate::random!(u64, "filler", stringify!($s))),
+				&_OBFWIDE_KEYSTREAM),
+			len: _OBFWIDE_STRING.len() as u64 ^ _OBFWIDE_LEN_KEY,
+		};
+		$crate::words::deobfuscate_padded::<_OBFWIDE_PADDED>(
+			$crate::xref::xref::<_,
+				{$crate::random!(u32, "offset", stringify!($s))},
+				{$crate::random!(u64, "xref", stringify!($s))}>
+				(&_OBFWIDE_SDATA),
+			&_OBFWIDE_KEYSTREAM,
+			_OBFWIDE_LEN_KEY)
+	}};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfwide_verified {
+	($hook:expr; $s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFWIDE_STRING: &[u16] = $crate::wide!($s);
+		const _OBFWIDE_SEED: u32 = $crate::random!(u32, "check seed", stringify!($s));
+		const _OBFWIDE_MASK: u32 = $crate::random!(u32, "check mask", stringify!($s));
+		static _OBFWIDE_CHECK: u32 = $crate::murmur3_words(_OBFWIDE_STRING, _OBFWIDE_SEED) ^ _OBFWIDE_MASK;
+		let mut data = $crate::__obfwide!($s);
+		$crate::words::verify(&mut data, &_OBFWIDE_CHECK, _OBFWIDE_SEED, _OBFWIDE_MASK, $hook);
+		data
+	}};
+}
+
+/// Checks if a wide string starts with an obfuscated wide string constant.
+///
+/// ```
+/// let path = obfstr::wide!("C:\\Windows\\System32\\ntdll.dll");
+/// assert!(obfstr::obfwide_starts_with!(path, "C:\\Windows\\"));
+/// ```
+#[macro_export]
+macro_rules! obfwide_starts_with {
+	($e:expr, $s:expr) => {
+		$crate::__obfwide_cmp!(starts_with, $e, $s)
+	};
+}
+
+/// Checks if a wide string ends with an obfuscated wide string constant.
+///
+/// ```
+/// let path = obfstr::wide!("C:\\Windows\\System32\\ntdll.dll");
+/// assert!(obfstr::obfwide_ends_with!(path, "\\ntdll.dll"));
+/// ```
+#[macro_export]
+macro_rules! obfwide_ends_with {
+	($e:expr, $s:expr) => {
+		$crate::__obfwide_cmp!(ends_with, $e, $s)
+	};
+}
+
+/// Checks if a wide string contains an obfuscated wide string constant.
+///
+/// See [`obfcontains!`] for more information.
+///
+/// ```
+/// let path = obfstr::wide!("C:\\Windows\\System32\\ntdll.dll");
+/// assert!(obfstr::obfwide_contains!(path, "System32"));
+/// ```
+#[macro_export]
+macro_rules! obfwide_contains {
+	($e:expr, $s:expr) => {
+		$crate::__obfwide_cmp!(contains, $e, $s)
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfwide_cmp {
+	(equals_str, $other:expr, $s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFWIDE_STRING: &[u16] = $crate::wide!($s);
+		const _OBFWIDE_LEN: usize = _OBFWIDE_STRING.len();
+		const _OBFWIDE_CIPHER2: bool = $crate::random!(bool, "cipher", stringify!($s));
+		const _OBFWIDE_MASK: u64 = if $crate::bytes::MASKED { $crate::random!(u64, "mask", stringify!($s)) } else { 0 };
+		const _OBFWIDE_KEYS: [u16; _OBFWIDE_LEN] = $crate::words::keys::<_OBFWIDE_LEN>($crate::random!(u64, "key", stringify!($s)));
+		const _OBFWIDE_KEYSTREAM: [u16; _OBFWIDE_LEN] = $crate::words::fold_key::<_OBFWIDE_LEN>(&_OBFWIDE_KEYS, _OBFWIDE_MASK);
+		static _OBFWIDE_SDATA: [u16; _OBFWIDE_LEN] = if _OBFWIDE_CIPHER2 {
+			$crate::words::obfuscate2::<_OBFWIDE_LEN>(_OBFWIDE_STRING, &_OBFWIDE_KEYS)
+		}
+		else {
+			$crate::words::obfuscate::<_OBFWIDE_LEN>(_OBFWIDE_STRING, &_OBFWIDE_KEYS)
+		};
+		let sdata = $crate::xref::xref::<_,
+			{$crate::random!(u32, "offset", stringify!($s))},
+			{$crate::random!(u64, "xref", stringify!($s))}>
+			(&_OBFWIDE_SDATA);
+		let keys = $crate::words::unmask_keys::<_OBFWIDE_LEN>(&_OBFWIDE_KEYSTREAM, $crate::bytes::mask_parts(_OBFWIDE_MASK));
+		if _OBFWIDE_CIPHER2 {
+			$crate::words::equals_str2::<_OBFWIDE_LEN>(sdata, &keys, $other)
+		}
+		else {
+			$crate::words::equals_str::<_OBFWIDE_LEN>(sdata, &keys, $other)
+		}
+	}};
+	($f:ident, $other:expr, $s:expr) => {{
+		use ::core::primitive::*;
+		const _OBFWIDE_STRING: &[u16] = $crate::wide!($s);
+		const _OBFWIDE_LEN: usize = _OBFWIDE_STRING.len();
+		const _OBFWIDE_MASK: u64 = if $crate::bytes::MASKED { $crate::random!(u64, "mask", stringify!($s)) } else { 0 };
+		const _OBFWIDE_KEYS: [u16; _OBFWIDE_LEN] = $crate::words::keys::<_OBFWIDE_LEN>($crate::random!(u64, "key", stringify!($s)));
+		const _OBFWIDE_KEYSTREAM: [u16; _OBFWIDE_LEN] = $crate::words::fold_key::<_OBFWIDE_LEN>(&_OBFWIDE_KEYS, _OBFWIDE_MASK);
+		static _OBFWIDE_SDATA: [u16; _OBFWIDE_LEN] = $crate::words::obfuscate::<_OBFWIDE_LEN>(_OBFWIDE_STRING, &_OBFWIDE_KEYS);
+		$crate::words::$f::<_OBFWIDE_LEN>(
+			$crate::xref::xref::<_,
+				{$crate::random!(u32, "offset", stringify!($s))},
+				{$crate::random!(u64, "xref", stringify!($s))}>
+				(&_OBFWIDE_SDATA),
+			&$crate::words::unmask_keys::<_OBFWIDE_LEN>(&_OBFWIDE_KEYSTREAM, $crate::bytes::mask_parts(_OBFWIDE_MASK)),
+			$other)
+	}};
+}
+
 // Simple XorShift to generate the key stream.
 // Security doesn't matter, we just want a number of random-looking bytes.
 #[inline(always)]
@@ -55,16 +292,39 @@ const fn next_round(mut x: u32) -> u32 {
 
 /// Generate the key stream for array of given length.
 #[inline(always)]
-pub const fn keystream<const LEN: usize>(key: u32) -> [u16; LEN] {
+pub const fn keystream<const LEN: usize>(key: u64) -> [u16; LEN] {
+	let mut keys = [0u16; LEN];
+	let mut round_key = key;
+	let mut i = 0;
+	// Calculate the key stream in chunks of 8 bytes
+	while i < LEN {
+		round_key = crate::bytes::next_round64(round_key);
+		let kb = round_key.to_le_bytes();
+		let mut j = 0;
+		while j < 4 && i + j < LEN {
+			keys[i + j] = u16::from_le_bytes([kb[j * 2 + 0], kb[j * 2 + 1]]);
+			j += 1;
+		}
+		i += 4;
+	}
+	return keys;
+}
+
+/// Generate the key stream for array of given length from a 32-bit key.
+///
+/// This is the key stream used before the keys were widened to 64 bits.
+#[deprecated(note = "use keystream with a 64-bit key")]
+#[inline(always)]
+pub const fn keystream_u32<const LEN: usize>(key: u32) -> [u16; LEN] {
 	let mut keys = [0u16; LEN];
 	let mut round_key = key;
 	let mut i = 0;
 	// Calculate the key stream in chunks of 4 bytes
 	while i < LEN & !1 {
 		round_key = next_round(round_key);
-		let kb = round_key.to_ne_bytes();
-		keys[i + 0] = u16::from_ne_bytes([kb[0], kb[1]]);
-		keys[i + 1] = u16::from_ne_bytes([kb[2], kb[3]]);
+		let kb = round_key.to_le_bytes();
+		keys[i + 0] = u16::from_le_bytes([kb[0], kb[1]]);
+		keys[i + 1] = u16::from_le_bytes([kb[2], kb[3]]);
 		i += 2;
 	}
 	// Calculate the remaining words of the key stream
@@ -75,6 +335,37 @@ pub const fn keystream<const LEN: usize>(key: u32) -> [u16; LEN] {
 	return keys;
 }
 
+/// Generate the mixed key stream for array of given length.
+///
+/// See [`bytes::keystream_mixed`](crate::bytes::keystream_mixed) for more information.
+#[inline(always)]
+pub const fn keystream_mixed<const LEN: usize>(key: u64, rounds: u32) -> [u16; LEN] {
+	let mut keys = [0u16; LEN];
+	let mut i = 0;
+	while i < LEN {
+		let kb = crate::bytes::mix_word(key, (i / 4) as u64, rounds).to_le_bytes();
+		let mut j = 0;
+		while j < 4 && i + j < LEN {
+			keys[i + j] = u16::from_le_bytes([kb[j * 2 + 0], kb[j * 2 + 1]]);
+			j += 1;
+		}
+		i += 4;
+	}
+	return keys;
+}
+
+/// Generate the key stream used by the obfuscation macros.
+///
+/// With the `mixed-keystream` feature enabled this is [`keystream_mixed`] with [`ROUNDS`](crate::ROUNDS) rounds.
+#[doc(hidden)]
+#[inline(always)]
+pub const fn keys<const LEN: usize>(key: u64) -> [u16; LEN] {
+	#[cfg(feature = "mixed-keystream")]
+	return keystream_mixed::<LEN>(key, crate::ROUNDS);
+	#[cfg(not(feature = "mixed-keystream"))]
+	return keystream::<LEN>(key);
+}
+
 /// Obfuscates the input string and given key stream.
 pub const fn obfuscate<const LEN: usize>(s: &[u16], k: &[u16; LEN]) -> [u16; LEN] {
 	if s.len() != LEN {
@@ -89,10 +380,53 @@ pub const fn obfuscate<const LEN: usize>(s: &[u16], k: &[u16; LEN]) -> [u16; LEN
 	return data;
 }
 
+/// Obfuscates the input string padded to the key stream length with the pad word.
+///
+/// Panics if the input string is longer than the key stream, which is a compile error in const context.
+#[inline(always)]
+pub const fn obfuscate_padded<const LEN: usize>(s: &[u16], k: &[u16; LEN], pad: u16) -> [u16; LEN] {
+	if s.len() > LEN {
+		panic!("input string len larger than key stream len");
+	}
+	let mut data = [pad; LEN];
+	let mut i = 0;
+	while i < s.len() {
+		data[i] = s[i];
+		i += 1;
+	}
+	return obfuscate::<LEN>(&data, k);
+}
+
+/// Verifies the deobfuscated wide string against the murmur3 checksum of its plaintext.
+///
+/// See [`bytes::verify`](crate::bytes::verify).
+#[inline]
+pub fn verify<F: FnOnce()>(s: &mut [u16], check: &u32, seed: u32, mask: u32, hook: F) {
+	let expected = unsafe { read_volatile(check) } ^ mask;
+	if crate::murmur3_words(s, seed) != expected {
+		hook();
+		for w in s.iter_mut() {
+			unsafe { write_volatile(w, 0) };
+		}
+	}
+}
+
 /// Deobfuscates the obfuscated input string and given key stream.
 #[inline(always)]
 pub fn deobfuscate<const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN]) -> [u16; LEN] {
 	let mut buf = [0u16; LEN];
+	deobfuscate_into::<LEN>(s, k, &mut buf);
+	return buf;
+}
+
+/// Deobfuscates the obfuscated input string and given key stream directly into the destination.
+///
+/// The destination may be uninitialized, returns the deobfuscated string.
+/// Panics if the destination is shorter than the string before anything is written.
+#[inline(always)]
+pub fn deobfuscate_into<'a, const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN], dst: &'a mut (impl Buffer<u16> + ?Sized)) -> &'a mut [u16] {
+	// Safety: only initialized values are written to the destination
+	let dst = unsafe { dst.uninit_prefix(LEN) };
 	let mut i = 0;
 	// Try to tickle the LLVM optimizer in _just_ the right way
 	// Use `read_volatile` to avoid constant folding a specific read and optimize the rest
@@ -100,7 +434,7 @@ pub fn deobfuscate<const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN]) -> [u16; LE
 	// Hand optimize in chunks of 8 and 4 bytes to avoid this
 	unsafe {
 		let src = s.as_ptr();
-		let dest = buf.as_mut_ptr();
+		let dest = dst.as_mut_ptr() as *mut u16;
 		// Process in chunks of 8 bytes on 64-bit targets
 		#[cfg(target_pointer_width = "64")]
 		while i < LEN & !3 {
@@ -129,15 +463,439 @@ pub fn deobfuscate<const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN]) -> [u16; LE
 			let ct = read_volatile(src.offset(i as isize));
 			write(dest.offset(i as isize), ct ^ k[i]);
 		}
+		return assume_init(dst);
+	}
+}
+
+/// Folds a runtime key into the key stream.
+#[inline(always)]
+pub const fn fold_key<const LEN: usize>(k: &[u16; LEN], rk: u64) -> [u16; LEN] {
+	let mut keys = *k;
+	let mut i = 0;
+	while i < LEN {
+		keys[i] ^= (rk >> (i % 4 * 16)) as u16;
+		i += 1;
+	}
+	return keys;
+}
+
+/// Deobfuscates the input string obfuscated with the key stream folded with a runtime key, see [`fold_key`].
+///
+/// With the wrong runtime key the result is garbage.
+#[inline(always)]
+pub fn deobfuscate_keyed<const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN], rk: u64) -> [u16; LEN] {
+	// The transform is linear, fold the runtime key into the deobfuscated string
+	let buf = deobfuscate::<LEN>(s, k);
+	return fold_key::<LEN>(&buf, rk);
+}
+
+/// Unmasks the key stream masked with [`fold_key`].
+///
+/// Returns the key stream unchanged without the `masked-keystream` feature.
+#[doc(hidden)]
+#[inline(always)]
+pub fn unmask_keys<const LEN: usize>(k: &[u16; LEN], parts: [u16; 4]) -> [u16; LEN] {
+	if !crate::bytes::MASKED {
+		return *k;
+	}
+	return fold_key::<LEN>(k, crate::bytes::unmask(parts));
+}
+
+/// Deobfuscates the obfuscated input string with the key stream masked with [`fold_key`].
+///
+/// The mask is reconstructed from its parts and removed on the fly.
+#[inline(always)]
+pub fn deobfuscate_masked<const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN], parts: [u16; 4]) -> [u16; LEN] {
+	// The transform is linear, remove the mask from the deobfuscated string
+	let buf = deobfuscate::<LEN>(s, k);
+	return fold_key::<LEN>(&buf, crate::bytes::unmask(parts));
+}
+
+/// Pads the string with filler words derived from the seed.
+#[inline(always)]
+pub const fn pad<const PADDED: usize>(s: &[u16], seed: u64) -> [u16; PADDED] {
+	if s.len() > PADDED {
+		panic!("input string len larger than the padded len");
+	}
+	let mut data = keystream::<PADDED>(seed);
+	let mut i = 0;
+	while i < s.len() {
+		data[i] = s[i];
+		i += 1;
+	}
+	return data;
+}
+
+/// Deobfuscates the padded input string, the result dereferences to the true length.
+#[inline(always)]
+pub fn deobfuscate_padded<const PADDED: usize>(s: &crate::bytes::Padded<[u16; PADDED]>, k: &[u16; PADDED], len_key: u64) -> crate::bytes::Unpadded<u16, PADDED> {
+	let len = crate::bytes::unpad_len(s, len_key);
+	let buf = deobfuscate::<PADDED>(&s.data, k);
+	return crate::bytes::Unpadded { buf, len };
+}
+
+/// Compares the padded obfuscated string with the other string.
+///
+/// Only the true length is compared, the padding is never decoded.
+#[inline(always)]
+pub fn equals_padded<const PADDED: usize>(s: &crate::bytes::Padded<[u16; PADDED]>, k: &[u16; PADDED], len_key: u64, other: &[u16]) -> bool {
+	let len = crate::bytes::unpad_len(s, len_key);
+	if other.len() != len {
+		return false;
+	}
+	let src = s.data.as_ptr();
+	let mut i = 0;
+	while i < len {
+		let ct = unsafe { read_volatile(src.offset(i as isize)) };
+		if ct ^ k[i] != other[i] {
+			return false;
+		}
+		i += 1;
 	}
+	return true;
+}
+
+/// Deobfuscates the input string bound to its own address, see [`bytes::deobfuscate_selfkeyed`](crate::bytes::deobfuscate_selfkeyed).
+///
+/// Decoding a copy of the data anywhere else produces garbage.
+#[inline(always)]
+pub fn deobfuscate_selfkeyed<const LEN: usize>(s: &[u16; LEN], anchor: &crate::bytes::Anchor, k: &[u16; LEN]) -> [u16; LEN] {
+	let delta = crate::bytes::anchor_delta(anchor, s.as_ptr() as *const u8);
+	let buf = deobfuscate::<LEN>(s, k);
+	return fold_key::<LEN>(&buf, crate::bytes::selfkey(delta));
+}
+
+/// Compares the obfuscated string with the other string.
+#[inline(always)]
+pub fn equals<const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN], other: &[u16]) -> bool {
+	if other.len() != LEN {
+		return false;
+	}
+	let mut i = 0;
+	unsafe {
+		let src = s.as_ptr();
+		// Process in chunks of 8 bytes on 64-bit targets
+		#[cfg(target_pointer_width = "64")]
+		while i < LEN & !3 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u16; 4]);
+			let tmp = [
+				ct[0] ^ k[i + 0],
+				ct[1] ^ k[i + 1],
+				ct[2] ^ k[i + 2],
+				ct[3] ^ k[i + 3],
+			];
+			if tmp != [other[i + 0], other[i + 1], other[i + 2], other[i + 3]] {
+				return false;
+			}
+			i += 4;
+		}
+		// Process in chunks of 4 bytes
+		while i < LEN & !1 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u16; 2]);
+			let tmp = [
+				ct[0] ^ k[i + 0],
+				ct[1] ^ k[i + 1],
+			];
+			if tmp != [other[i + 0], other[i + 1]] {
+				return false;
+			}
+			i += 2;
+		}
+		// Process the remaining words
+		if LEN % 2 != 0 {
+			let ct = read_volatile(src.offset(i as isize));
+			return ct ^ k[i] == other[i];
+		}
+	}
+	return true;
+}
+
+// Add/rotate transform: each word is offset by its key word and then rotated by the top bits of that key word.
+#[inline(always)]
+const fn encode2(p: u16, k: u16) -> u16 {
+	return p.wrapping_add(k).rotate_left((k >> 12) as u32);
+}
+#[inline(always)]
+const fn decode2(c: u16, k: u16) -> u16 {
+	return c.rotate_right((k >> 12) as u32).wrapping_sub(k);
+}
+
+/// Obfuscates the input string and given key stream with the add/rotate transform.
+pub const fn obfuscate2<const LEN: usize>(s: &[u16], k: &[u16; LEN]) -> [u16; LEN] {
+	if s.len() != LEN {
+		panic!("input string len not equal to key stream len");
+	}
+	let mut data = [0u16; LEN];
+	let mut i = 0usize;
+	while i < LEN {
+		data[i] = encode2(s[i], k[i]);
+		i += 1;
+	}
+	return data;
+}
+
+/// Deobfuscates the input string obfuscated with [`obfuscate2`].
+#[inline(always)]
+pub fn deobfuscate2<const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN]) -> [u16; LEN] {
+	let mut buf = [0u16; LEN];
+	deobfuscate2_into::<LEN>(s, k, &mut buf);
 	return buf;
 }
 
+/// Deobfuscates the string obfuscated with [`obfuscate2`] directly into the destination.
+///
+/// The destination may be uninitialized, returns the deobfuscated string.
+/// Panics if the destination is shorter than the string before anything is written.
+#[inline(always)]
+pub fn deobfuscate2_into<'a, const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN], dst: &'a mut (impl Buffer<u16> + ?Sized)) -> &'a mut [u16] {
+	// Safety: only initialized values are written to the destination
+	let buf = unsafe { dst.uninit_prefix(LEN) };
+	let mut i = 0;
+	unsafe {
+		let src = s.as_ptr();
+		// Read in chunks of 8 bytes, the transform itself is wordwise
+		while i < LEN & !3 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u16; 4]);
+			buf[i + 0] = MaybeUninit::new(decode2(ct[0], k[i + 0]));
+			buf[i + 1] = MaybeUninit::new(decode2(ct[1], k[i + 1]));
+			buf[i + 2] = MaybeUninit::new(decode2(ct[2], k[i + 2]));
+			buf[i + 3] = MaybeUninit::new(decode2(ct[3], k[i + 3]));
+			i += 4;
+		}
+		// Process the remaining words
+		while i < LEN {
+			let ct = read_volatile(src.offset(i as isize));
+			buf[i] = MaybeUninit::new(decode2(ct, k[i]));
+			i += 1;
+		}
+		return assume_init(buf);
+	}
+}
+
+/// Compares the string obfuscated with [`obfuscate2`] with the other string.
+#[inline(always)]
+pub fn equals2<const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN], other: &[u16]) -> bool {
+	if other.len() != LEN {
+		return false;
+	}
+	let mut i = 0;
+	unsafe {
+		let src = s.as_ptr();
+		while i < LEN {
+			let ct = read_volatile(src.offset(i as isize));
+			if decode2(ct, k[i]) != other[i] {
+				return false;
+			}
+			i += 1;
+		}
+	}
+	return true;
+}
+
+/// String types which can be compared against obfuscated wide strings.
+#[doc(hidden)]
+pub trait WideStr {
+	fn equals<const LEN: usize>(&self, s: &[u16; LEN], k: &[u16; LEN]) -> bool;
+	fn equals2<const LEN: usize>(&self, s: &[u16; LEN], k: &[u16; LEN]) -> bool;
+}
+impl WideStr for [u16] {
+	#[inline(always)]
+	fn equals<const LEN: usize>(&self, s: &[u16; LEN], k: &[u16; LEN]) -> bool {
+		equals::<LEN>(s, k, self)
+	}
+	#[inline(always)]
+	fn equals2<const LEN: usize>(&self, s: &[u16; LEN], k: &[u16; LEN]) -> bool {
+		equals2::<LEN>(s, k, self)
+	}
+}
+impl<const N: usize> WideStr for [u16; N] {
+	#[inline(always)]
+	fn equals<const LEN: usize>(&self, s: &[u16; LEN], k: &[u16; LEN]) -> bool {
+		equals::<LEN>(s, k, self)
+	}
+	#[inline(always)]
+	fn equals2<const LEN: usize>(&self, s: &[u16; LEN], k: &[u16; LEN]) -> bool {
+		equals2::<LEN>(s, k, self)
+	}
+}
+impl WideStr for str {
+	#[inline(always)]
+	fn equals<const LEN: usize>(&self, s: &[u16; LEN], k: &[u16; LEN]) -> bool {
+		equals_iter::<LEN, _>(s, k, self.encode_utf16(), decode)
+	}
+	#[inline(always)]
+	fn equals2<const LEN: usize>(&self, s: &[u16; LEN], k: &[u16; LEN]) -> bool {
+		equals_iter::<LEN, _>(s, k, self.encode_utf16(), decode2)
+	}
+}
+#[cfg(all(windows, any(test, feature = "std")))]
+impl WideStr for std::ffi::OsStr {
+	#[inline(always)]
+	fn equals<const LEN: usize>(&self, s: &[u16; LEN], k: &[u16; LEN]) -> bool {
+		use std::os::windows::ffi::OsStrExt;
+		equals_iter::<LEN, _>(s, k, self.encode_wide(), decode)
+	}
+	#[inline(always)]
+	fn equals2<const LEN: usize>(&self, s: &[u16; LEN], k: &[u16; LEN]) -> bool {
+		use std::os::windows::ffi::OsStrExt;
+		equals_iter::<LEN, _>(s, k, self.encode_wide(), decode2)
+	}
+}
+impl<T: ?Sized + WideStr> WideStr for &T {
+	#[inline(always)]
+	fn equals<const LEN: usize>(&self, s: &[u16; LEN], k: &[u16; LEN]) -> bool {
+		(**self).equals::<LEN>(s, k)
+	}
+	#[inline(always)]
+	fn equals2<const LEN: usize>(&self, s: &[u16; LEN], k: &[u16; LEN]) -> bool {
+		(**self).equals2::<LEN>(s, k)
+	}
+}
+
+#[inline(always)]
+const fn decode(c: u16, k: u16) -> u16 {
+	return c ^ k;
+}
+
+#[inline(always)]
+fn equals_iter<const LEN: usize, I: Iterator<Item = u16> + Clone>(s: &[u16; LEN], k: &[u16; LEN], other: I, decode: fn(u16, u16) -> u16) -> bool {
+	// Check the length first without touching the keystream
+	if other.clone().count() != LEN {
+		return false;
+	}
+	let src = s.as_ptr();
+	for (i, unit) in other.enumerate() {
+		let ct = unsafe { read_volatile(src.offset(i as isize)) };
+		if decode(ct, k[i]) != unit {
+			return false;
+		}
+	}
+	return true;
+}
+
+/// Compares the obfuscated string with the other string.
+///
+/// The other string may be any of `[u16]`, `str` or on Windows `OsStr`.
+#[inline(always)]
+pub fn equals_str<const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN], other: &(impl ?Sized + WideStr)) -> bool {
+	other.equals::<LEN>(s, k)
+}
+
+/// Compares the string obfuscated with [`obfuscate2`] with the other string.
+///
+/// The other string may be any of `[u16]`, `str` or on Windows `OsStr`.
+#[inline(always)]
+pub fn equals_str2<const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN], other: &(impl ?Sized + WideStr)) -> bool {
+	other.equals2::<LEN>(s, k)
+}
+
+/// Checks if the haystack starts with the obfuscated string.
+///
+/// Returns `false` if the haystack is shorter than the obfuscated string.
+#[inline(always)]
+pub fn starts_with<const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN], haystack: &[u16]) -> bool {
+	if haystack.len() < LEN {
+		return false;
+	}
+	equals::<LEN>(s, k, &haystack[..LEN])
+}
+
+/// Checks if the haystack ends with the obfuscated string.
+///
+/// Returns `false` if the haystack is shorter than the obfuscated string.
+#[inline(always)]
+pub fn ends_with<const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN], haystack: &[u16]) -> bool {
+	if haystack.len() < LEN {
+		return false;
+	}
+	equals::<LEN>(s, k, &haystack[haystack.len() - LEN..])
+}
+
+/// Checks if the haystack contains the obfuscated string.
+///
+/// See [`bytes::contains`](crate::bytes::contains) for more information.
+/// The jump table is indexed by the low byte of every word.
+#[inline(always)]
+pub fn contains<const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN], haystack: &[u16]) -> bool {
+	// Short-circuit empty needles
+	if LEN == 0 {
+		return true;
+	}
+	if LEN > haystack.len() {
+		return false;
+	}
+	let src = s.as_ptr();
+	let needle = |i: usize| unsafe { read_volatile(src.offset(i as isize)) } ^ k[i];
+	// Initialize the jump table, words sharing the low byte get the smallest jump
+	let mut jumps = [if LEN > 255 { 255 } else { LEN as u8 }; 256];
+	let tail = LEN - 1;
+	let mut i = 0;
+	while i < tail {
+		jumps[needle(i) as u8 as usize] = if tail - i > 255 { 255 } else { (tail - i) as u8 };
+		i += 1;
+	}
+	// Find the needle
+	let sentinel = needle(tail);
+	let mut offset = 0;
+	while offset < haystack.len() - tail {
+		let chr = haystack[offset + tail];
+		if chr == sentinel {
+			let mut i = 0;
+			while i < tail && haystack[offset + i] == needle(i) {
+				i += 1;
+			}
+			if i == tail {
+				return true;
+			}
+		}
+		offset += jumps[chr as u8 as usize] as usize;
+	}
+	return false;
+}
+
+/// Compares the obfuscated string with the other string in constant time.
+///
+/// See [`bytes::ct_equals`](crate::bytes::ct_equals) for the timing guarantees provided.
+#[inline(always)]
+pub fn ct_equals<const LEN: usize>(s: &[u16; LEN], k: &[u16; LEN], other: &[u16]) -> bool {
+	if other.len() != LEN {
+		return false;
+	}
+	let mut diff = 0u16;
+	let mut i = 0;
+	unsafe {
+		let src = s.as_ptr();
+		// Process in chunks of 8 bytes on 64-bit targets
+		#[cfg(target_pointer_width = "64")]
+		while i < LEN & !3 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u16; 4]);
+			diff |= ct[0] ^ k[i + 0] ^ other[i + 0];
+			diff |= ct[1] ^ k[i + 1] ^ other[i + 1];
+			diff |= ct[2] ^ k[i + 2] ^ other[i + 2];
+			diff |= ct[3] ^ k[i + 3] ^ other[i + 3];
+			i += 4;
+		}
+		// Process in chunks of 4 bytes
+		while i < LEN & !1 {
+			let ct = read_volatile(src.offset(i as isize) as *const [u16; 2]);
+			diff |= ct[0] ^ k[i + 0] ^ other[i + 0];
+			diff |= ct[1] ^ k[i + 1] ^ other[i + 1];
+			i += 2;
+		}
+		// Process the remaining words
+		if LEN % 2 != 0 {
+			let ct = read_volatile(src.offset(i as isize));
+			diff |= ct ^ k[i] ^ other[i];
+		}
+	}
+	hint::black_box(diff) == 0
+}
+
 // Test correct processing of less than multiple of 8 lengths
 #[test]
 fn test_remaining_bytes() {
 	const STRING: &[u16] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
-	fn test<const LEN: usize>(key: u32) {
+	fn test<const LEN: usize>(key: u64) {
 		let keys = keystream::<LEN>(key);
 		let data = obfuscate::<LEN>(&STRING[..LEN], &keys);
 		let buffer = deobfuscate::<LEN>(&data, &keys);
@@ -145,6 +903,9 @@ fn test_remaining_bytes() {
 		assert_ne!(&data[..], &STRING[..LEN]);
 		// Deobfuscated result should equal input string
 		assert_eq!(&buffer[..], &STRING[..LEN]);
+		// Specialized equals check should succeed
+		assert!(equals::<LEN>(&data, &keys, &STRING[..LEN]));
+		assert!(ct_equals::<LEN>(&data, &keys, &STRING[..LEN]));
 	}
 	test::<8>(0x1111);
 	test::<9>(0x2222);
@@ -157,6 +918,206 @@ fn test_remaining_bytes() {
 	test::<16>(0x9999);
 }
 
+#[test]
+fn test_keystream_key_width() {
+	// Keys differing only in the upper 32 bits produce different key streams
+	assert_ne!(keystream::<8>(0x0000_0001_1234_5678), keystream::<8>(0x0000_0002_1234_5678));
+	#[allow(deprecated)]
+	let keys = keystream_u32::<2>(0x10203040);
+	let round = next_round(0x10203040).to_le_bytes();
+	assert_eq!(keys, [u16::from_le_bytes([round[0], round[1]]), u16::from_le_bytes([round[2], round[3]])]);
+}
+
+#[test]
+fn test_keystream_mixed() {
+	const STRING: &[u16] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+	fn test<const LEN: usize>(key: u64, rounds: u32) {
+		let keys = keystream_mixed::<LEN>(key, rounds);
+		let data = obfuscate::<LEN>(&STRING[..LEN], &keys);
+		let buffer = deobfuscate::<LEN>(&data, &keys);
+		assert_ne!(&data[..], &STRING[..LEN]);
+		assert_eq!(&buffer[..], &STRING[..LEN]);
+		assert!(equals::<LEN>(&data, &keys, &STRING[..LEN]));
+	}
+	test::<3>(0x1111, 2);
+	test::<4>(0x2222, 2);
+	test::<5>(0x3333, 3);
+	test::<16>(0x4444, 4);
+}
+
+#[test]
+fn test_deobfuscate_keyed() {
+	const STRING: &[u16] = crate::wide!("Hello world");
+	const LEN: usize = STRING.len();
+	let keys = keystream::<LEN>(0x1234);
+	let data = obfuscate::<LEN>(STRING, &fold_key::<LEN>(&keys, 0xfeedface_01020304));
+	assert_eq!(&deobfuscate_keyed::<LEN>(&data, &keys, 0xfeedface_01020304)[..], STRING);
+	assert_ne!(&deobfuscate_keyed::<LEN>(&data, &keys, 0xfeedfacf_01020304)[..], STRING);
+
+	let rk = crate::RUNTIME_KEY;
+	assert_eq!(crate::obfwide!(rk => "keyed"), crate::wide!("keyed"));
+	assert_ne!(crate::obfwide!(rk ^ 1 => "keyed"), crate::wide!("keyed"));
+}
+
+#[test]
+fn test_deobfuscate_selfkeyed() {
+	const STRING: &[u16] = crate::wide!("selfkeyed");
+	const KEYS: [u16; 9] = keystream::<9>(0x1234);
+	static SDATA: [u16; 9] = obfuscate::<9>(STRING, &fold_key::<9>(&KEYS, crate::bytes::selfkey(0x1000)));
+	static ANCHOR: crate::bytes::Anchor = crate::bytes::Anchor((SDATA.as_ptr() as *const u8).wrapping_add(0x1000));
+	assert_eq!(deobfuscate_selfkeyed::<9>(&SDATA, &ANCHOR, &KEYS), STRING);
+	let copy = SDATA;
+	assert_ne!(deobfuscate_selfkeyed::<9>(&copy, &ANCHOR, &KEYS), STRING);
+
+	assert_eq!(crate::obfwide!(#[selfkey] "selfkeyed"), crate::wide!("selfkeyed"));
+}
+
+#[test]
+fn test_masked() {
+	const STRING: &[u16] = crate::wide!("Hello world");
+	const LEN: usize = STRING.len();
+	const MASK: u64 = 0x0123_4567_89ab_cdef;
+	let keys = keystream::<LEN>(0x1234);
+	let masked = fold_key::<LEN>(&keys, MASK);
+	assert!(keys.iter().zip(masked.iter()).all(|(a, b)| a != b));
+	let data = obfuscate::<LEN>(STRING, &keys);
+	assert_eq!(&deobfuscate_masked::<LEN>(&data, &masked, crate::bytes::mask_parts(MASK))[..], STRING);
+}
+
+#[test]
+fn test_padded() {
+	const STRING: &[u16] = crate::wide!("Hello world");
+	const LEN: usize = STRING.len();
+	const PADDED: usize = crate::bytes::padded_len(LEN, 0x1234);
+	const KEYS: [u16; PADDED] = keystream::<PADDED>(0x5678);
+	static SDATA: crate::bytes::Padded<[u16; PADDED]> = crate::bytes::Padded {
+		data: obfuscate::<PADDED>(&pad::<PADDED>(STRING, 0x9abc), &KEYS),
+		len: LEN as u64 ^ 0xabcdef,
+	};
+	assert_ne!(SDATA.data.len(), LEN);
+	assert_eq!(&*deobfuscate_padded::<PADDED>(&SDATA, &KEYS, 0xabcdef), STRING);
+	assert!(equals_padded::<PADDED>(&SDATA, &KEYS, 0xabcdef, STRING));
+	assert!(!equals_padded::<PADDED>(&SDATA, &KEYS, 0xabcdef, &STRING[..LEN - 1]));
+
+	assert_eq!(crate::obfwide!(#[padded] "padded"), crate::wide!("padded"));
+}
+
+#[test]
+fn test_deobfuscate_into() {
+	const STRING: &[u16] = crate::wide!("0123456789ABCDEFG");
+	fn test<const N: usize>() {
+		let keys = keystream::<N>(0x1234);
+		let data = obfuscate::<N>(&STRING[..N], &keys);
+		let mut buf = [0xffffu16; 20];
+		deobfuscate_into::<N>(&data, &keys, &mut buf);
+		assert_eq!(&buf[..N], &deobfuscate::<N>(&data, &keys));
+		assert!(buf[N..].iter().all(|&w| w == 0xffff));
+		let data = obfuscate2::<N>(&STRING[..N], &keys);
+		deobfuscate2_into::<N>(&data, &keys, &mut buf);
+		assert_eq!(&buf[..N], &deobfuscate2::<N>(&data, &keys));
+
+		let mut uninit = [MaybeUninit::<u16>::uninit(); 20];
+		assert_eq!(deobfuscate2_into::<N>(&data, &keys, &mut uninit), &deobfuscate2::<N>(&data, &keys));
+		let data = obfuscate::<N>(&STRING[..N], &keys);
+		assert_eq!(deobfuscate_into::<N>(&data, &keys, &mut uninit[..]), &STRING[..N]);
+	}
+	test::<1>(); test::<2>(); test::<3>(); test::<4>(); test::<5>(); test::<6>();
+	test::<7>(); test::<8>(); test::<9>(); test::<10>(); test::<11>(); test::<12>();
+	test::<13>(); test::<14>(); test::<15>(); test::<16>(); test::<17>();
+
+	let mut buf = [MaybeUninit::<u16>::uninit(); 17];
+	assert_eq!(crate::obfwide!(buf <- "0123456789ABCDEFG"), STRING);
+
+	let mut buf = [0u16; 17];
+	assert_eq!(crate::obfwide!(buf <- "0123456789ABCDEFG"), STRING);
+}
+
+#[test]
+fn test_obfuscate_padded() {
+	const KEYS: [u16; 8] = keystream::<8>(0x1234);
+	const SHORT: [u16; 8] = obfuscate_padded::<8>(&[0x41, 0x42, 0x43], &KEYS, 0);
+	assert_eq!(deobfuscate::<8>(&SHORT, &KEYS), [0x41, 0x42, 0x43, 0, 0, 0, 0, 0]);
+	const EXACT: [u16; 3] = obfuscate_padded::<3>(&[0x41, 0x42, 0x43], &[1, 2, 3], 0);
+	assert_eq!(EXACT, obfuscate::<3>(&[0x41, 0x42, 0x43], &[1, 2, 3]));
+}
+
+#[test]
+fn test_cipher2() {
+	const STRING: &[u16] = crate::wide!("Hello 🌍 world");
+	const LEN: usize = STRING.len();
+	let keys = keystream::<LEN>(0x1234);
+	let data = obfuscate2::<LEN>(STRING, &keys);
+	assert_eq!(&deobfuscate2::<LEN>(&data, &keys), STRING);
+	assert!(equals2::<LEN>(&data, &keys, STRING));
+	assert!(equals_str2::<LEN>(&data, &keys, "Hello 🌍 world"));
+	assert!(!equals_str2::<LEN>(&data, &keys, "Hello 🌍 World"));
+	assert!(!equals2::<LEN>(&data, &keys, &STRING[..LEN - 1]));
+}
+
+#[test]
+fn test_ct_equals() {
+	const STRING: &[u16] = crate::wide!("C:\\Windows\\System32");
+	const LEN: usize = STRING.len();
+	const KEYSTREAM: [u16; LEN] = keystream::<LEN>(0x10203040);
+	const OBFSTRING: [u16; LEN] = obfuscate::<LEN>(STRING, &KEYSTREAM);
+	assert!(ct_equals::<LEN>(&OBFSTRING, &KEYSTREAM, crate::wide!("C:\\Windows\\System32")));
+	assert!(!ct_equals::<LEN>(&OBFSTRING, &KEYSTREAM, crate::wide!("D:\\Windows\\System32")));
+	assert!(!ct_equals::<LEN>(&OBFSTRING, &KEYSTREAM, crate::wide!("C:\\Windows\\System33")));
+	assert!(!ct_equals::<LEN>(&OBFSTRING, &KEYSTREAM, crate::wide!("C:\\Windows")));
+}
+
+#[test]
+fn test_starts_ends_with() {
+	const STRING: &[u16] = crate::wide!("System32");
+	const LEN: usize = STRING.len();
+	const KEYSTREAM: [u16; LEN] = keystream::<LEN>(0x10203040);
+	const OBFSTRING: [u16; LEN] = obfuscate::<LEN>(STRING, &KEYSTREAM);
+	// Haystack shorter than the needle
+	assert!(!starts_with::<LEN>(&OBFSTRING, &KEYSTREAM, crate::wide!("System")));
+	assert!(!ends_with::<LEN>(&OBFSTRING, &KEYSTREAM, crate::wide!("System")));
+	// Haystack equal to the needle
+	assert!(starts_with::<LEN>(&OBFSTRING, &KEYSTREAM, crate::wide!("System32")));
+	assert!(ends_with::<LEN>(&OBFSTRING, &KEYSTREAM, crate::wide!("System32")));
+	// Haystack longer than the needle
+	assert!(starts_with::<LEN>(&OBFSTRING, &KEYSTREAM, crate::wide!("System32\\drivers")));
+	assert!(ends_with::<LEN>(&OBFSTRING, &KEYSTREAM, crate::wide!("C:\\Windows\\System32")));
+	assert!(!starts_with::<LEN>(&OBFSTRING, &KEYSTREAM, crate::wide!("C:\\Windows\\System32")));
+
+	assert!(obfwide_starts_with!(crate::wide!("System32\\drivers"), "System32"));
+	assert!(!obfwide_ends_with!(crate::wide!("32"), "System32"));
+}
+
+#[test]
+fn test_contains() {
+	assert!(obfwide_contains!(crate::wide!("needle in a haystack"), "needle"));
+	assert!(obfwide_contains!(crate::wide!("haystack with a needle"), "needle"));
+	assert!(!obfwide_contains!(crate::wide!("haystack with needl e"), "needle"));
+	assert!(obfwide_contains!(crate::wide!("aaaaab"), "aab"));
+	// Words sharing the low byte with the needle
+	assert!(obfwide_contains!(&[0x161, 0x61, 0x261, 0x61, 0x62], "ab"));
+	assert!(obfwide_contains!(crate::wide!("🌍🌎🌏"), "🌎"));
+}
+
+#[test]
+fn test_equals_str() {
+	let module: &[u16] = crate::wide!("ntdll.dll");
+	assert!(crate::obfeq!(wide module, "ntdll.dll"));
+	assert!(crate::obfeq!(wide crate::wide!("ntdll.dll"), "ntdll.dll"));
+	assert!(!crate::obfeq!(wide &module[..8], "ntdll.dll"));
+	assert!(!crate::obfeq!(wide crate::wide!("ntdll.dlL"), "ntdll.dll"));
+	assert!(crate::obfeq!(wide "🌍.dll", "🌍.dll"));
+	assert!(!crate::obfeq!(wide "ntdll.dl", "ntdll.dll"));
+	assert!(!crate::obfeq!(wide "ntdll.dlll", "ntdll.dll"));
+}
+
+#[cfg(windows)]
+#[test]
+fn test_equals_osstr() {
+	let module = std::ffi::OsString::from("ntdll.dll");
+	assert!(crate::obfeq!(wide module.as_os_str(), "ntdll.dll"));
+	assert!(!crate::obfeq!(wide module.as_os_str(), "kernel32.dll"));
+}
+
 #[test]
 fn test_obfstr_let() {
 	obfwide! {
@@ -178,3 +1139,18 @@ fn test_obfstr_const() {
 	assert_eq!(obfwide!(ABC), &[b'A' as u16, b'B' as u16, b'C' as u16]);
 	assert_eq!(obfwide!(WORLD), &[0xd83c, 0xdf0d]);
 }
+
+#[test]
+fn test_verified() {
+	assert_eq!(obfwide!(#[verified] "Hello 🌍"), crate::wide!("Hello 🌍"));
+	let mut fired = false;
+	let mut s = *obfwide!(#[verified(|| fired = true)] "Hello");
+	assert!(!fired);
+	static CHECK: u32 = crate::murmur3_words(crate::wide!("Hello"), 7) ^ 0xaaaa;
+	verify(&mut s, &CHECK, 7, 0xaaaa, || fired = true);
+	assert!(!fired);
+	s[0] = 0;
+	verify(&mut s, &CHECK, 7, 0xaaaa, || fired = true);
+	assert!(fired);
+	assert_eq!(s, [0; 5]);
+}
@@ -1,4 +1,5 @@
-use core::hint;
+use core::{hint, mem};
+use core::marker::PhantomData;
 
 /// Obfuscates the xref to data reference.
 ///
@@ -16,8 +17,88 @@ use core::hint;
 /// assert_eq!(obfstr::xref!("Hello world!"), "Hello world!");
 /// assert_eq!(obfstr::xref!(b"Byte array"), b"Byte array");
 /// ```
+///
+/// The reference can be obfuscated in a chain of up to 4 stages, each with its own displacement and out-of-line inner function.
+/// The default depth is 1:
+///
+/// ```
+/// static FOO: i32 = 42;
+/// assert_eq!(obfstr::xref!(3; &FOO) as *const _, &FOO as *const _);
+/// ```
+///
+/// Depths outside of the supported range fail to compile:
+///
+/// ```compile_fail
+/// static FOO: i32 = 42;
+/// let _ = obfstr::xref!(5; &FOO);
+/// ```
+///
+/// Element and field projections of statics are obfuscated as a reference to the projected place:
+///
+/// ```
+/// struct Config { name: &'static str, ports: [u16; 3] }
+/// static CONFIG: Config = Config { name: "config", ports: [80, 443, 8080] };
+/// static LUT: [Config; 1] = [Config { name: "lut", ports: [1, 2, 3] }];
+///
+/// assert_eq!(obfstr::xref!(&CONFIG.name) as *const _, &CONFIG.name as *const _);
+/// assert_eq!(obfstr::xref!(&CONFIG.ports[2]) as *const _, &CONFIG.ports[2] as *const _);
+/// assert_eq!(obfstr::xref!(&LUT[0].ports[1..]), &[2, 3]);
+/// ```
+///
+/// The `extern` form obfuscates the reference to a static declared in an `extern` block, the entropy is derived from the symbol path.
+/// Taking the reference is unsafe, the caller writes the `unsafe` block around it:
+///
+/// ```
+/// # mod c { #[no_mangle] static OBFSTR_DOC_TABLE: [u8; 4] = [1, 2, 3, 4]; }
+/// extern "C" {
+/// 	static OBFSTR_DOC_TABLE: [u8; 4];
+/// }
+/// // Safety: the table is initialized by the C side and never mutated
+/// let table: &'static [u8; 4] = obfstr::xref!(extern unsafe { &OBFSTR_DOC_TABLE });
+/// assert_eq!(table, &[1, 2, 3, 4]);
+/// ```
+///
+/// Without the `unsafe` block the `extern` form fails to compile:
+///
+/// ```compile_fail
+/// # mod c { #[no_mangle] static OBFSTR_DOC_TABLE2: [u8; 4] = [1, 2, 3, 4]; }
+/// extern "C" {
+/// 	static OBFSTR_DOC_TABLE2: [u8; 4];
+/// }
+/// let table: &'static [u8; 4] = obfstr::xref!(extern &OBFSTR_DOC_TABLE2);
+/// ```
+///
+/// With the `no-xref` feature enabled the xref functions return their input unchanged, only laundered through [`black_box`](core::hint::black_box).
+/// String obfuscation is not affected.
+///
+/// # Safety
+///
+/// The `extern` form creates a `&'static` reference to the static, the `unsafe` block written by the caller asserts that:
+///
+/// * The static is initialized with a valid value of its declared type.
+/// * The static is never mutated while the reference is alive, which for a `'static` reference means never again.
+///   This includes `static mut` items and writes from foreign code.
 #[macro_export]
 macro_rules! xref {
+	(extern unsafe { &$path:path }) => {
+		$crate::xref::xref::<_,
+			{$crate::random!(u32, "extern", module_path!(), stringify!($path), "OFFSET")},
+			{$crate::random!(u64, "extern", module_path!(), stringify!($path), "SEED")}>
+			(unsafe { &$path })
+	};
+	(&$root:ident $($proj:tt)+) => {
+		$crate::xref::xref::<_,
+			{$crate::random!(u32, stringify!($root), stringify!($($proj)+), "OFFSET")},
+			{$crate::random!(u64, stringify!($root), stringify!($($proj)+), "SEED")}>
+			($crate::xref::place(&$root $($proj)+))
+	};
+	($depth:literal; $e:expr) => {
+		$crate::xref::xref_chain::<_, $depth,
+			{$crate::random!(u32, stringify!($e), "OFFSET1")}, {$crate::random!(u64, stringify!($e), "SEED1")},
+			{$crate::random!(u32, stringify!($e), "OFFSET2")}, {$crate::random!(u64, stringify!($e), "SEED2")},
+			{$crate::random!(u32, stringify!($e), "OFFSET3")}, {$crate::random!(u64, stringify!($e), "SEED3")},
+			{$crate::random!(u32, stringify!($e), "OFFSET4")}, {$crate::random!(u64, stringify!($e), "SEED4")}>($e)
+	};
 	($e:expr) => {
 		$crate::xref::xref::<_,
 			{$crate::random!(u32, stringify!($e), "OFFSET")},
@@ -30,25 +111,50 @@ const fn non_zero(rand: u32) -> u32 {
 	if rand == 0 { 1 } else { rand }
 }
 
+// Odd multiplier other than one.
+#[inline(always)]
+const fn odd(rand: u32) -> u32 {
+	if rand | 1 == 1 { 0x9e3779b9 } else { rand | 1 }
+}
+
+// None of the operations is the identity for any random value.
 #[inline(always)]
 const fn obfchoice(v: u32, seed: u64) -> u32 {
 	let rand = (seed >> 32) as u32;
-	match seed & 7 {
-		0 => v.wrapping_add(rand),
+	match seed & 15 {
+		0 => v.wrapping_add(non_zero(rand)),
 		1 => rand.wrapping_sub(v),
-		2 => v ^ rand,
-		3 => v ^ v.rotate_left(non_zero(rand & 7)),
-		4 => !v,
+		2 => v ^ non_zero(rand),
+		3 => v ^ v.rotate_left(non_zero(rand & 31)),
+		4 => (!v).rotate_left(rand & 31),
 		5 => v ^ (v >> non_zero(rand & 31)),
-		6 => v.wrapping_mul(non_zero(rand)),
-		7 => v.wrapping_neg(),
+		6 => v.wrapping_mul(odd(rand)),
+		7 => v.wrapping_neg() ^ rand,
+		8 => v.swap_bytes().wrapping_add(rand),
+		9 => v.rotate_left(non_zero(rand & 31)) ^ rand,
+		10 => v.wrapping_add(rand).rotate_left(non_zero(rand >> 27)).wrapping_add(rand >> 8),
+		11 => ((v & 0x0f0f0f0f) << 4 | (v >> 4) & 0x0f0f0f0f) ^ rand,
+		12 => v ^ (v << non_zero(rand & 31)),
+		13 => v.reverse_bits().wrapping_add(rand),
+		14 => v.wrapping_add(v << non_zero(rand & 31)),
+		15 => (v ^ (v >> 16)).wrapping_mul(odd(rand)),
 		_ => unsafe { hint::unreachable_unchecked() }
 	}
 }
 
 #[inline(always)]
-const fn obfuscate<const SEED: u64>(mut v: u32) -> usize {
-	let mut seed = SEED;
+const fn obfuscate<const SEED: u64>(v: u32) -> usize {
+	obfuscate_with(v, SEED)
+}
+
+#[inline(always)]
+const fn obfuscate_with(v: u32, key: u64) -> usize {
+	return (obfchain(v, key) & offset_mask(key)) as usize
+}
+
+#[inline(always)]
+const fn obfchain(mut v: u32, key: u64) -> u32 {
+	let mut seed = key;
 	use crate::splitmix;
 	seed = splitmix(seed);
 	v = obfchoice(v, seed);
@@ -60,25 +166,359 @@ const fn obfuscate<const SEED: u64>(mut v: u32) -> usize {
 	v = obfchoice(v, seed);
 	seed = splitmix(seed);
 	v = obfchoice(v, seed);
-	return (v & 0xffff) as usize
+	return v;
 }
 
+// Limits the displacement of the obfuscated references.
+// The width of the displacement is picked per call site from the seed, a fixed 64K displacement is easily pattern matched.
+// References are displaced upwards so the displaced address does not wrap below zero in small address spaces such as wasm32.
+// On 32-bit targets the width is limited to 24 bits to keep the displaced address from wrapping through null.
+// On 16-bit targets a 64K displacement would span the whole address space.
+const OFFSET_BITS_MIN: u32 = if usize::BITS <= 16 { 8 } else { 16 };
+const OFFSET_BITS_MAX: u32 = if usize::BITS <= 16 { 8 } else if usize::BITS <= 32 { 24 } else { 28 };
+
+#[inline(always)]
+const fn offset_mask(seed: u64) -> u32 {
+	let bits = OFFSET_BITS_MIN + (crate::splitmix(!seed) % (OFFSET_BITS_MAX - OFFSET_BITS_MIN + 1) as u64) as u32;
+	return ((1u64 << bits) - 1) as u32;
+}
+
+// Restores the displaced address, the mangling happens in integer space.
 #[inline(never)]
-fn inner<const SEED: u64>(p: *const u8, offset: u32) -> *const u8 {
-	p.wrapping_add(obfuscate::<SEED>(offset))
+fn inner<const SEED: u64>(addr: usize, offset: u32) -> usize {
+	addr.wrapping_sub(obfuscate::<SEED>(offset))
+}
+
+// Verifies the xref round-trips in debug builds.
+// Without debug assertions the check is not compiled and no copy of the true pointer is kept.
+#[cfg(debug_assertions)]
+#[track_caller]
+fn check<T: ?Sized, const SEED: u64>(original: *const T, recovered: *const T) {
+	if !core::ptr::eq(original, recovered) {
+		panic!("obfstr: xref with SEED {:#018x} did not round-trip", SEED);
+	}
+}
+
+/// Evaluates the projection of a static once, the place must live for `'static`.
+#[doc(hidden)]
+#[inline(always)]
+pub const fn place<T: ?Sized>(p: &'static T) -> &'static T {
+	p
 }
 
 /// Obfuscates the xref to data reference.
 #[inline(always)]
 pub fn xref<T: ?Sized, const OFFSET: u32, const SEED: u64>(p: &'static T) -> &'static T {
-	unsafe {
-		let mut p: *const T = p;
-		// Launder the values through black_box to prevent LLVM from optimizing away the obfuscation
-		let val = inner::<SEED>(hint::black_box((p as *const u8).wrapping_sub(obfuscate::<SEED>(OFFSET))), hint::black_box(OFFSET));
-		// set_ptr_value
-		*(&mut p as *mut *const T as *mut *const u8) = val;
-		&*p
+	// Still launder the reference to keep the optimizer from reading through it
+	if cfg!(feature = "no-xref") {
+		return hint::black_box(p);
+	}
+	let p: *const T = p;
+	#[cfg(debug_assertions)]
+	let original = p;
+	// Launder the values through black_box to prevent LLVM from optimizing away the obfuscation
+	let p = hint::black_box(p.wrapping_byte_add(obfuscate::<SEED>(OFFSET)));
+	// Only convert back to a pointer at the end, this keeps the provenance and the metadata of the pointer
+	let p = p.with_addr(inner::<SEED>(p.addr(), hint::black_box(OFFSET)));
+	#[cfg(debug_assertions)]
+	check::<T, SEED>(original, p);
+	unsafe { &*p }
+}
+
+/// Obfuscates the xref to slice data and its length.
+///
+/// The length of a slice is otherwise a plain constant next to the obfuscated pointer and identifies the static by its size.
+/// Only the length masked with a per call site random value is embedded, the true length is restored out-of-line with the pointer.
+///
+/// ```
+/// static TABLE: [u16; 5] = [1, 2, 3, 4, 5];
+/// let table = obfstr::xref_slice!(&TABLE[..]);
+/// assert_eq!(table, &TABLE[..]);
+/// assert_eq!(obfstr::xref_slice!("Hello world!"), "Hello world!");
+/// ```
+///
+/// Supports `[T]` and `str` slices, see [`SliceLike`](crate::xref::SliceLike).
+#[macro_export]
+macro_rules! xref_slice {
+	($e:expr) => {
+		$crate::xref::xref_slice::<_,
+			{$crate::random!(u32, stringify!($e), "OFFSET")},
+			{$crate::random!(u64, stringify!($e), "SEED")}>($e)
+	};
+}
+
+/// Slice types supported by [`xref_slice`].
+///
+/// # Safety
+///
+/// [`SliceLike::from_raw_parts`] must rebuild the slice from the parts returned by [`SliceLike::to_raw_parts`].
+pub unsafe trait SliceLike {
+	/// Returns the data pointer and the length of the slice.
+	fn to_raw_parts(&self) -> (*const u8, usize);
+	/// Rebuilds the slice from its data pointer and length.
+	///
+	/// # Safety
+	///
+	/// The parts must have been returned by [`SliceLike::to_raw_parts`].
+	unsafe fn from_raw_parts<'a>(p: *const u8, len: usize) -> &'a Self;
+}
+unsafe impl<T> SliceLike for [T] {
+	#[inline(always)]
+	fn to_raw_parts(&self) -> (*const u8, usize) {
+		(self.as_ptr() as *const u8, self.len())
+	}
+	#[inline(always)]
+	unsafe fn from_raw_parts<'a>(p: *const u8, len: usize) -> &'a [T] {
+		core::slice::from_raw_parts(p as *const T, len)
+	}
+}
+unsafe impl SliceLike for str {
+	#[inline(always)]
+	fn to_raw_parts(&self) -> (*const u8, usize) {
+		(self.as_ptr(), self.len())
+	}
+	#[inline(always)]
+	unsafe fn from_raw_parts<'a>(p: *const u8, len: usize) -> &'a str {
+		core::str::from_utf8_unchecked(core::slice::from_raw_parts(p, len))
+	}
+}
+
+#[inline(always)]
+const fn len_mask(seed: u64) -> usize {
+	crate::splitmix(seed ^ 0x6c656e677468) as usize
+}
+
+// Restores both the displaced address and the masked length.
+#[inline(never)]
+fn inner_slice<const SEED: u64>(addr: usize, offset: u32, len: usize) -> (usize, usize) {
+	(addr.wrapping_sub(obfuscate::<SEED>(offset)), len ^ len_mask(SEED))
+}
+
+/// Obfuscates the xref to slice data and its length.
+#[inline(always)]
+pub fn xref_slice<T: ?Sized + SliceLike, const OFFSET: u32, const SEED: u64>(s: &'static T) -> &'static T {
+	if cfg!(feature = "no-xref") {
+		return hint::black_box(s);
+	}
+	let (p, len) = s.to_raw_parts();
+	#[cfg(debug_assertions)]
+	let original = core::ptr::slice_from_raw_parts(p, len);
+	// Launder the values through black_box to prevent LLVM from optimizing away the obfuscation
+	let p = hint::black_box(p.wrapping_byte_add(obfuscate::<SEED>(OFFSET)));
+	let len = hint::black_box(len ^ len_mask(SEED));
+	let (addr, len) = inner_slice::<SEED>(p.addr(), hint::black_box(OFFSET), len);
+	#[cfg(debug_assertions)]
+	check::<[u8], SEED>(original, core::ptr::slice_from_raw_parts(p.with_addr(addr), len));
+	unsafe { T::from_raw_parts(p.with_addr(addr), len) }
+}
+
+/// Obfuscates the constant index into a lookup table.
+///
+/// Constant indices give away which entry of a table is used.
+/// The index is displaced like an xref and restored out-of-line before the normal bounds-checked access:
+///
+/// ```
+/// static HANDLERS: [&str; 4] = ["open", "read", "write", "close"];
+/// assert_eq!(obfstr::obfindex!(HANDLERS, 2), "write");
+/// assert_eq!(&obfstr::obfindex!(HANDLERS[..], 3) as *const _, &HANDLERS[3] as *const _);
+/// ```
+///
+/// The `get` form returns `None` instead of panicking when the index is out of bounds:
+///
+/// ```
+/// static PRIMES: [u8; 4] = [2, 3, 5, 7];
+/// assert_eq!(obfstr::obfindex!(get PRIMES, 1), Some(&3));
+/// assert_eq!(obfstr::obfindex!(get PRIMES, 4), None);
+/// ```
+#[macro_export]
+macro_rules! obfindex {
+	(get $t:expr, $i:expr) => {
+		$t.get($crate::__obfindex!($t, $i))
+	};
+	($t:expr, $i:expr) => {
+		$t[$crate::__obfindex!($t, $i)]
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __obfindex {
+	($t:expr, $i:expr) => {
+		$crate::xref::obfindex::<{$i},
+			{$crate::random!(u32, stringify!($t), stringify!($i), "OFFSET")},
+			{$crate::random!(u64, stringify!($t), stringify!($i), "SEED")}>()
+	};
+}
+
+/// Obfuscates the constant index.
+#[inline(always)]
+pub fn obfindex<const INDEX: usize, const OFFSET: u32, const SEED: u64>() -> usize {
+	// Launder the values through black_box to prevent LLVM from optimizing away the obfuscation
+	let index = hint::black_box(INDEX.wrapping_add(obfuscate::<SEED>(OFFSET)));
+	inner::<SEED>(index, hint::black_box(OFFSET))
+}
+
+/// Obfuscates integer and float constants.
+///
+/// Magic numbers are as revealing as strings.
+/// The value is stored masked with a pad which is recomputed at runtime through the xref operations from a laundered key.
+///
+/// ```
+/// assert_eq!(obfstr::obfconst!(0xDEADBEEFu32), 0xDEADBEEF);
+/// assert_eq!(obfstr::obfconst!(i64::MIN), i64::MIN);
+/// assert_eq!(obfstr::obfconst!(1.5f32), 1.5);
+///
+/// const PORT: u16 = 8080;
+/// let port: u16 = obfstr::obfconst!(PORT);
+/// assert_eq!(port, 8080);
+/// ```
+///
+/// Supports the integer types up to 64 bits, `bool`, `f32` and `f64`, see [`Bits`](crate::xref::Bits).
+/// Unlike [`random!`](crate::random) the 128-bit integers are not supported, the masked value is stored in a single `u64`.
+/// The value must be a constant expression.
+#[macro_export]
+macro_rules! obfconst {
+	($e:expr) => {{
+		const _OBFCONST_KEY: u64 = $crate::random!(u64, "key", stringify!($e));
+		const _OBFCONST_SEED: u64 = $crate::random!(u64, "seed", stringify!($e));
+		$crate::xref::obfconst::<_, _OBFCONST_KEY, _OBFCONST_SEED>(const { $crate::xref::Masked::new::<_OBFCONST_KEY, _OBFCONST_SEED>($e) })
+	}};
+}
+
+/// Types supported by [`obfconst`].
+///
+/// # Safety
+///
+/// The type must be at most 8 bytes in size and without padding.
+/// Every value must be restored from its bits, see [`Masked::new`].
+pub unsafe trait Bits: Copy {}
+unsafe impl Bits for u8 {}
+unsafe impl Bits for u16 {}
+unsafe impl Bits for u32 {}
+unsafe impl Bits for u64 {}
+unsafe impl Bits for usize {}
+unsafe impl Bits for i8 {}
+unsafe impl Bits for i16 {}
+unsafe impl Bits for i32 {}
+unsafe impl Bits for i64 {}
+unsafe impl Bits for isize {}
+unsafe impl Bits for bool {}
+unsafe impl Bits for f32 {}
+unsafe impl Bits for f64 {}
+
+#[repr(C)]
+union Repr<T: Copy> {
+	value: T,
+	bits: u64,
+}
+
+/// Constant value masked at compiletime.
+#[derive(Copy, Clone)]
+pub struct Masked<T> {
+	bits: u64,
+	marker: PhantomData<T>,
+}
+
+impl<T: Bits> Masked<T> {
+	/// Masks the value with the pad derived from the key.
+	#[inline(always)]
+	pub const fn new<const KEY: u64, const SEED: u64>(value: T) -> Masked<T> {
+		const { assert!(mem::size_of::<T>() <= 8) };
+		// The bytes not covered by the value stay zero
+		let mut repr = Repr::<T> { bits: 0 };
+		repr.value = value;
+		let bits = unsafe { repr.bits };
+		Masked { bits: bits ^ pad(KEY, SEED), marker: PhantomData }
+	}
+}
+
+// Generates the 64-bit pad from the key with two chains of the xref operations picked by the seed.
+#[inline(always)]
+const fn pad(key: u64, seed: u64) -> u64 {
+	let lo = obfchain(key as u32, seed);
+	let hi = obfchain((key >> 32) as u32, crate::splitmix(seed));
+	return (hi as u64) << 32 | lo as u64;
+}
+
+/// Restores the constant masked by [`Masked::new`] with the same `KEY` and `SEED`.
+#[inline(always)]
+pub fn obfconst<T: Bits, const KEY: u64, const SEED: u64>(masked: Masked<T>) -> T {
+	// Launder the values through black_box to prevent LLVM from optimizing away the obfuscation
+	let bits = hint::black_box(masked.bits) ^ pad(hint::black_box(KEY), SEED);
+	unsafe { Repr::<T> { bits }.value }
+}
+
+// Adds the displacement of the next stage before removing the displacement of this stage.
+// The true address is never materialized between stages.
+#[inline(always)]
+fn stage<const SEED: u64>(addr: usize, next: usize, offset: u32) -> usize {
+	inner::<SEED>(hint::black_box(addr.wrapping_add(next)), hint::black_box(offset))
+}
+
+/// Obfuscates the xref to data reference in a chain of `DEPTH` stages.
+///
+/// Supports a depth of 1 to 4, the offsets and seeds of the stages beyond `DEPTH` are unused.
+#[inline(always)]
+pub fn xref_chain<T: ?Sized, const DEPTH: usize,
+	const OFFSET1: u32, const SEED1: u64, const OFFSET2: u32, const SEED2: u64,
+	const OFFSET3: u32, const SEED3: u64, const OFFSET4: u32, const SEED4: u64>(p: &'static T) -> &'static T
+{
+	const { assert!(DEPTH >= 1 && DEPTH <= 4, "xref depth must be in range 1..=4") };
+	if cfg!(feature = "no-xref") {
+		return hint::black_box(p);
+	}
+	let p: *const T = p;
+	#[cfg(debug_assertions)]
+	let original = p;
+	// Launder the values through black_box to prevent LLVM from optimizing away the obfuscation
+	let p = hint::black_box(p.wrapping_byte_add(obfuscate::<SEED1>(OFFSET1)));
+	let mut addr = p.addr();
+	if DEPTH > 1 {
+		addr = stage::<SEED1>(addr, obfuscate::<SEED2>(OFFSET2), OFFSET1);
+	}
+	if DEPTH > 2 {
+		addr = stage::<SEED2>(addr, obfuscate::<SEED3>(OFFSET3), OFFSET2);
 	}
+	if DEPTH > 3 {
+		addr = stage::<SEED3>(addr, obfuscate::<SEED4>(OFFSET4), OFFSET3);
+	}
+	// The last stage restores the true address
+	addr = match DEPTH {
+		1 => inner::<SEED1>(addr, hint::black_box(OFFSET1)),
+		2 => inner::<SEED2>(addr, hint::black_box(OFFSET2)),
+		3 => inner::<SEED3>(addr, hint::black_box(OFFSET3)),
+		_ => inner::<SEED4>(addr, hint::black_box(OFFSET4)),
+	};
+	#[cfg(debug_assertions)]
+	check::<T, SEED1>(original, p.with_addr(addr));
+	unsafe { &*p.with_addr(addr) }
+}
+
+/// Mangles the reference at compiletime.
+///
+/// The returned pointer is displaced and must be restored with [`unmangle`] using the same `OFFSET` and `SEED`.
+/// This allows storing obfuscated references in statics.
+#[inline(always)]
+pub const fn mangle<T, const OFFSET: u32, const SEED: u64>(p: &'static T) -> *const u8 {
+	if cfg!(feature = "no-xref") {
+		return p as *const T as *const u8;
+	}
+	(p as *const T as *const u8).wrapping_add(obfuscate::<SEED>(OFFSET))
+}
+
+/// Restores the reference mangled by [`mangle`].
+///
+/// # Safety
+///
+/// The pointer must have been returned by [`mangle`] with the same `T`, `OFFSET` and `SEED`.
+#[inline(always)]
+pub unsafe fn unmangle<T, const OFFSET: u32, const SEED: u64>(p: *const u8) -> &'static T {
+	if cfg!(feature = "no-xref") {
+		return &*(hint::black_box(p) as *const T);
+	}
+	// Launder the values through black_box to prevent LLVM from optimizing away the obfuscation
+	let p = hint::black_box(p);
+	&*(p.with_addr(inner::<SEED>(p.addr(), hint::black_box(OFFSET))) as *const T)
 }
 
 /// Obfuscates the xref to data reference.
@@ -90,6 +530,9 @@ pub fn xref<T: ?Sized, const OFFSET: u32, const SEED: u64>(p: &'static T) -> &'s
 /// // When looking at the disassembly the reference to `FOO` has been obfuscated.
 /// assert_eq!(foo as *mut _, unsafe { &mut FOO } as *mut _);
 /// ```
+///
+/// The returned `&'static mut` makes this hard to use soundly, every use on the same static creates another mutable reference which aliases the others.
+/// Prefer [`xref_mut_ptr!`] which obfuscates raw pointers instead.
 #[macro_export]
 macro_rules! xref_mut {
 	($e:expr) => {
@@ -99,22 +542,135 @@ macro_rules! xref_mut {
 	};
 }
 
-#[inline(never)]
-fn inner_mut<const SEED: u64>(p: *mut u8, offset: u32) -> *mut u8 {
-	p.wrapping_add(obfuscate::<SEED>(offset))
-}
-
 /// Obfuscates the xref to data reference.
+///
+/// See [`xref_mut!`] for the aliasing hazard, prefer [`xref_mut_ptr`].
 #[inline(always)]
 pub fn xref_mut<T: ?Sized, const OFFSET: u32, const SEED: u64>(p: &'static mut T) -> &'static mut T {
-	unsafe {
-		let mut p: *mut T = p;
-		// Launder the values through black_box to prevent LLVM from optimizing away the obfuscation
-		let val = inner_mut::<SEED>(hint::black_box((p as *mut u8).wrapping_sub(obfuscate::<SEED>(OFFSET))), hint::black_box(OFFSET));
-		// set_ptr_value
-		*(&mut p as *mut *mut T as *mut *mut u8) = val;
-		&mut *p
+	unsafe { &mut *xref_mut_ptr::<T, OFFSET, SEED>(p) }
+}
+
+/// Obfuscates the xref to a mutable data pointer.
+///
+/// No references are created, the pointer can be obtained any number of times without aliasing mutable references.
+///
+/// ```
+/// static mut COUNTER: i32 = 0;
+///
+/// let counter = obfstr::xref_mut_ptr!(core::ptr::addr_of_mut!(COUNTER));
+/// unsafe { *counter += 1 };
+///
+/// // When looking at the disassembly the reference to `COUNTER` has been obfuscated.
+/// assert_eq!(counter, core::ptr::addr_of_mut!(COUNTER));
+/// ```
+#[macro_export]
+macro_rules! xref_mut_ptr {
+	($e:expr) => {
+		$crate::xref::xref_mut_ptr::<_,
+			{$crate::random!(u32, stringify!($e), "OFFSET")},
+			{$crate::random!(u64, stringify!($e), "SEED")}>($e)
+	};
+}
+
+/// Obfuscates the xref to a mutable data pointer.
+#[inline(always)]
+pub fn xref_mut_ptr<T: ?Sized, const OFFSET: u32, const SEED: u64>(p: *mut T) -> *mut T {
+	if cfg!(feature = "no-xref") {
+		return hint::black_box(p);
 	}
+	#[cfg(debug_assertions)]
+	let original = p;
+	// Launder the values through black_box to prevent LLVM from optimizing away the obfuscation
+	let p = hint::black_box(p.wrapping_byte_add(obfuscate::<SEED>(OFFSET)));
+	let p = p.with_addr(inner::<SEED>(p.addr(), hint::black_box(OFFSET)));
+	#[cfg(debug_assertions)]
+	check::<T, SEED>(original, p);
+	return p;
+}
+
+/// Obfuscates the xref to a function.
+///
+/// The call becomes an indirect call through a computed pointer instead of a direct call to the function.
+/// Function items must be coerced to a function pointer, either by the expected type or with `as`:
+///
+/// ```
+/// fn add(a: i32, b: i32) -> i32 { a + b }
+///
+/// let f: fn(i32, i32) -> i32 = obfstr::xref_fn!(add);
+/// assert_eq!(f(2, 3), 5);
+/// assert_eq!(obfstr::xref_fn!(add as fn(i32, i32) -> i32)(4, 5), 9);
+/// ```
+///
+/// Supports `fn`, `unsafe fn`, `extern "C" fn` and `unsafe extern "C" fn` pointers with up to 8 arguments, see [`FnPtr`](crate::xref::FnPtr).
+#[macro_export]
+macro_rules! xref_fn {
+	($e:expr) => {
+		$crate::xref::xref_fn::<_,
+			{$crate::random!(u32, stringify!($e), "OFFSET")},
+			{$crate::random!(u64, stringify!($e), "SEED")}>($e)
+	};
+}
+
+/// Function pointer types supported by [`xref_fn`].
+///
+/// Higher-ranked signatures such as `fn(&str)` are not supported, name the lifetimes instead: `fn(&'static str)`.
+///
+/// # Safety
+///
+/// The type must be a function pointer.
+pub unsafe trait FnPtr: Copy {
+	/// Returns the address of the function.
+	fn to_ptr(self) -> *const ();
+	/// Restores the function pointer from its address.
+	///
+	/// # Safety
+	///
+	/// The address must have been returned by [`FnPtr::to_ptr`] of the same type.
+	unsafe fn from_ptr(p: *const ()) -> Self;
+}
+
+macro_rules! impl_fn_ptr {
+	($($arg:ident),*) => {
+		impl_fn_ptr!(@impl fn($($arg),*) -> R; $($arg),*);
+		impl_fn_ptr!(@impl unsafe fn($($arg),*) -> R; $($arg),*);
+		impl_fn_ptr!(@impl extern "C" fn($($arg),*) -> R; $($arg),*);
+		impl_fn_ptr!(@impl unsafe extern "C" fn($($arg),*) -> R; $($arg),*);
+	};
+	(@impl $ty:ty; $($arg:ident),*) => {
+		unsafe impl<R, $($arg),*> FnPtr for $ty {
+			#[inline(always)]
+			fn to_ptr(self) -> *const () {
+				self as *const ()
+			}
+			#[inline(always)]
+			unsafe fn from_ptr(p: *const ()) -> Self {
+				core::mem::transmute::<*const (), Self>(p)
+			}
+		}
+	};
+}
+impl_fn_ptr!();
+impl_fn_ptr!(A);
+impl_fn_ptr!(A, B);
+impl_fn_ptr!(A, B, C);
+impl_fn_ptr!(A, B, C, D);
+impl_fn_ptr!(A, B, C, D, E);
+impl_fn_ptr!(A, B, C, D, E, F);
+impl_fn_ptr!(A, B, C, D, E, F, G);
+impl_fn_ptr!(A, B, C, D, E, F, G, H);
+
+/// Obfuscates the xref to a function.
+#[inline(always)]
+pub fn xref_fn<F: FnPtr, const OFFSET: u32, const SEED: u64>(f: F) -> F {
+	if cfg!(feature = "no-xref") {
+		return hint::black_box(f);
+	}
+	// Launder the values through black_box to prevent LLVM from optimizing away the obfuscation
+	let p = hint::black_box(f.to_ptr().wrapping_byte_add(obfuscate::<SEED>(OFFSET)));
+	let p = p.with_addr(inner::<SEED>(p.addr(), hint::black_box(OFFSET)));
+	#[cfg(debug_assertions)]
+	check::<(), SEED>(f.to_ptr(), p);
+	unsafe { F::from_ptr(p) }
 }
 
 #[test]
@@ -126,9 +682,310 @@ fn test_xref_slice() {
 	assert_eq!(xref!(b"Byte array"), b"Byte array");
 }
 
+#[test]
+fn test_xref_slice_len() {
+	static BYTES: [u8; 11] = *b"byte string";
+	static WORDS: [u16; 3] = [1, 2, 3];
+	static EMPTY: [u32; 0] = [];
+	let bytes = xref_slice!(&BYTES[..]);
+	assert_eq!((bytes.as_ptr(), bytes.len()), (BYTES.as_ptr(), BYTES.len()));
+	let words = xref_slice!(&WORDS[1..]);
+	assert_eq!((words.as_ptr(), words.len()), (WORDS[1..].as_ptr(), 2));
+	assert_eq!(words, [2, 3]);
+	let empty = xref_slice!(&EMPTY[..]);
+	assert_eq!((empty.as_ptr(), empty.len()), (EMPTY.as_ptr(), 0));
+	const STRING: &str = "Hello 🌍";
+	let string = xref_slice!(STRING);
+	assert_eq!((string.as_ptr(), string.len()), (STRING.as_ptr(), STRING.len()));
+	assert_eq!(string, "Hello 🌍");
+}
+
+// Test fixture only, the symbol must not be exported from the library
+#[cfg(test)]
+#[no_mangle]
+static OBFSTR_TEST_EXTERN_TABLE: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+#[test]
+#[cfg_attr(miri, ignore = "extern statics are not supported")]
+fn test_xref_extern() {
+	// Stands in for a static defined in a C object file
+	mod ffi {
+		extern "C" {
+			pub static OBFSTR_TEST_EXTERN_TABLE: [u8; 8];
+		}
+	}
+	let table: &'static [u8; 8] = xref!(extern unsafe { &ffi::OBFSTR_TEST_EXTERN_TABLE });
+	assert_eq!(table as *const _, core::ptr::addr_of!(ffi::OBFSTR_TEST_EXTERN_TABLE));
+	assert_eq!(table as *const _, &OBFSTR_TEST_EXTERN_TABLE as *const _);
+	assert_eq!(table, &[1, 2, 3, 4, 5, 6, 7, 8]);
+}
+
+#[test]
+fn test_xref_chain() {
+	static FOO: [i32; 42] = [13; 42];
+	assert_eq!(xref!(1; &FOO) as *const _, &FOO as *const _);
+	assert_eq!(xref!(2; &FOO) as *const _, &FOO as *const _);
+	assert_eq!(xref!(3; &FOO) as *const _, &FOO as *const _);
+	assert_eq!(xref!(4; &FOO) as *const _, &FOO as *const _);
+	assert_eq!(xref!(4; &FOO[..]) as *const _, &FOO[..] as *const _);
+	assert_eq!(xref!(2; "Hello world!"), "Hello world!");
+	assert_eq!(xref!(3; b"Byte array"), b"Byte array");
+}
+
+#[test]
+fn test_xref_mut() {
+	static mut FOO: [i32; 4] = [1, 2, 3, 4];
+	let foo = xref_mut!(unsafe { &mut *core::ptr::addr_of_mut!(FOO) });
+	foo[1] = 5;
+	assert_eq!(foo as *mut [i32; 4], core::ptr::addr_of_mut!(FOO));
+	assert_eq!(unsafe { FOO }, [1, 5, 3, 4]);
+	let foo = xref_mut!(unsafe { &mut *(core::ptr::addr_of_mut!(FOO) as *mut [i32]) });
+	assert_eq!(foo.len(), 4);
+}
+
+#[test]
+fn test_xref_mut_ptr() {
+	static mut FOO: [i32; 4] = [1, 2, 3, 4];
+	// Obtain the pointer twice, write through one and read through the other
+	let a = xref_mut_ptr!(core::ptr::addr_of_mut!(FOO));
+	let b = xref_mut_ptr!(core::ptr::addr_of_mut!(FOO));
+	assert_eq!(a, core::ptr::addr_of_mut!(FOO));
+	unsafe { (*a)[1] = 5 };
+	assert_eq!(unsafe { *b }, [1, 5, 3, 4]);
+	let c = xref_mut_ptr!(core::ptr::addr_of_mut!(FOO) as *mut [i32]);
+	assert_eq!(c.len(), 4);
+}
+
+#[test]
+fn test_mangle() {
+	struct SyncPtr(*const u8);
+	unsafe impl Sync for SyncPtr {}
+	static FOO: [i32; 4] = [1, 2, 3, 4];
+	static MANGLED: SyncPtr = SyncPtr(mangle::<_, 0x1234, 0x5678>(&FOO));
+	let foo = unsafe { unmangle::<[i32; 4], 0x1234, 0x5678>(MANGLED.0) };
+	assert_eq!(foo as *const _, &FOO as *const _);
+}
+
+#[test]
+fn test_xref_fn() {
+	fn add(a: i32, b: i32) -> i32 { a.wrapping_add(b) }
+	fn answer() -> i32 { 42 }
+	unsafe fn deref(p: *const i32) -> i32 { *p }
+	extern "C" fn mul(a: u64, b: u64) -> u64 { a.wrapping_mul(b) }
+	unsafe extern "C" fn sum8(a: u8, b: u8, c: u8, d: u8, e: u8, f: u8, g: u8, h: u8) -> u32 {
+		[a, b, c, d, e, f, g, h].iter().map(|&x| x as u32).sum()
+	}
+
+	let f: fn(i32, i32) -> i32 = xref_fn!(add);
+	assert_eq!(f(2, 3), add(2, 3));
+	assert_eq!(xref_fn!(answer as fn() -> i32)(), answer());
+	assert_eq!(unsafe { xref_fn!(deref as unsafe fn(*const i32) -> i32)(&7) }, 7);
+	assert_eq!(xref_fn!(mul as extern "C" fn(u64, u64) -> u64)(6, 7), mul(6, 7));
+
+	// Table of FFI functions
+	static TABLE: [unsafe extern "C" fn(u8, u8, u8, u8, u8, u8, u8, u8) -> u32; 1] = [sum8];
+	let f = xref_fn!(TABLE[0]);
+	assert_eq!(unsafe { f(1, 2, 3, 4, 5, 6, 7, 8) }, 36);
+
+	// Named lifetimes
+	fn len(s: &'static str) -> usize { s.len() }
+	assert_eq!(xref_fn!(len as fn(&'static str) -> usize)("hello"), 5);
+}
+
+#[test]
+fn test_offset_bits() {
+	const fn seed_with_bits(bits: u32) -> u64 {
+		let mut seed = 0;
+		while offset_mask(seed) != (1 << bits) - 1 {
+			seed += 1;
+		}
+		return seed;
+	}
+	const SEED: u64 = seed_with_bits(24);
+	struct SyncPtr(*const u8);
+	unsafe impl Sync for SyncPtr {}
+	static FOO: [i32; 4] = [1, 2, 3, 4];
+	static MANGLED: SyncPtr = SyncPtr(mangle::<_, 0x1234, SEED>(&FOO));
+	assert_eq!(xref::<_, 0x1234, SEED>(&FOO) as *const _, &FOO as *const _);
+	assert_eq!(unsafe { unmangle::<[i32; 4], 0x1234, SEED>(MANGLED.0) } as *const _, &FOO as *const _);
+	// The displacement spans the full 24 bits
+	let max = (0..256).map(obfuscate::<SEED>).max().unwrap();
+	assert!(max > 0xffff && max <= 0xffffff, "{:#x}", max);
+	// Every width is picked for some seed
+	for bits in OFFSET_BITS_MIN..=OFFSET_BITS_MAX {
+		assert!((0..1000).any(|seed| offset_mask(seed) == (1 << bits) - 1), "{}", bits);
+	}
+}
+
 #[test]
 fn regression1() {
 	// Caused by `v = v ^ (v >> RNG)` when RNG is zero to always be zero
 	let v = obfuscate::<0xC4B3B4F3D986EFBEu64>(0x3C236765u32);
 	assert_ne!(v, 0);
+	// Likewise shifts and rotates by zero, adding or xoring zero and multiplying by one are the identity
+	for rand in [0u32, 1, 32, 0x20000000, 0xffffffe0, 0xffffffff] {
+		for op in 0..16u64 {
+			let seed = (rand as u64) << 32 | op;
+			assert!((0..32).any(|i| obfchoice(1 << i, seed) != 1 << i), "op {} is the identity for {:#x}", op, rand);
+		}
+	}
+}
+
+#[test]
+fn test_obfchoice() {
+	let mut seed = 0x1234u64;
+	for _ in 0..1000 {
+		seed = crate::splitmix(seed);
+		// No operation is the identity or a constant
+		let samples = [0u32, 1, 0x80000000, 0xdeadbeef, 0x12345678, seed as u32];
+		assert!(samples.iter().any(|&v| obfchoice(v, seed) != v), "{:#x}", seed);
+		assert!(samples.iter().any(|&v| obfchoice(v, seed) != obfchoice(0, seed)), "{:#x}", seed);
+		// The chain does not collapse to the identity or a constant
+		let mask = offset_mask(seed) as usize;
+		assert!(samples.iter().any(|&v| obfuscate_with(v, seed) != v as usize & mask), "{:#x}", seed);
+		assert!(samples.iter().any(|&v| obfuscate_with(v, seed) != obfuscate_with(0, seed)), "{:#x}", seed);
+		// The displacement round trips in integer space
+		let addr = seed as usize;
+		let offset = (seed >> 32) as u32;
+		let displaced = addr.wrapping_add(obfuscate_with(offset, seed));
+		assert_eq!(displaced.wrapping_sub(obfuscate_with(offset, seed)), addr);
+		assert!(obfuscate_with(offset, seed) <= mask);
+	}
+	// Every operation is picked
+	let mut seen = 0u16;
+	let mut seed = 0x5678u64;
+	for _ in 0..1000 {
+		seed = crate::splitmix(seed);
+		seen |= 1 << (seed & 15);
+	}
+	assert_eq!(seen, 0xffff);
+}
+
+#[test]
+fn test_obfindex() {
+	static TABLE: [u32; 5] = [10, 20, 30, 40, 50];
+	assert_eq!(obfindex!(TABLE, 0), 10);
+	assert_eq!(&obfindex!(TABLE, 4) as *const _, &TABLE[4] as *const _);
+	let slice: &[u32] = &TABLE[1..];
+	assert_eq!(&obfindex!(slice, 2) as *const _, &TABLE[3] as *const _);
+	assert_eq!(obfindex!(get TABLE, 3), Some(&TABLE[3]));
+	assert_eq!(obfindex!(get slice, 4), None);
+	assert_eq!(obfindex!(get slice, usize::MAX), None);
+	let mut buf = [0u8; 3];
+	obfindex!(buf, 1) = 7;
+	assert_eq!(buf, [0, 7, 0]);
+}
+
+#[test]
+#[should_panic(expected = "index out of bounds")]
+fn test_obfindex_out_of_bounds() {
+	let table: &[u8] = &[1, 2, 3];
+	let _ = obfindex!(table, 3);
+}
+
+#[test]
+fn test_round_trip() {
+	// The const generic path agrees with the runtime path used below
+	assert_eq!(obfuscate::<0x1234>(0x5678), obfuscate_with(0x5678, 0x1234));
+	assert_eq!(obfuscate::<{u64::MAX}>(u32::MAX), obfuscate_with(u32::MAX, u64::MAX));
+	let mut rng = 0x2545f4914f6cdd1d;
+	for _ in 0..4096 {
+		rng = crate::splitmix(rng);
+		let seed = rng;
+		rng = crate::splitmix(rng);
+		let offset = rng as u32;
+		let addr = (rng >> 32) as usize;
+		let disp = obfuscate_with(offset, seed);
+		assert!(disp <= offset_mask(seed) as usize, "{:#x} {:#x}", seed, offset);
+		assert!(disp < 1 << OFFSET_BITS_MAX, "{:#x} {:#x}", seed, offset);
+		// 64-bit pointer width
+		let addr64 = (addr as u64) << 16 | rng & 0xffff;
+		assert_eq!(addr64.wrapping_add(disp as u64).wrapping_sub(disp as u64), addr64);
+		assert_eq!(u64::MAX.wrapping_add(disp as u64).wrapping_sub(disp as u64), u64::MAX);
+		// 32-bit pointer width, the displacement must survive the truncation
+		assert_eq!(disp as u32 as usize, disp);
+		let addr32 = addr as u32;
+		assert_eq!(addr32.wrapping_add(disp as u32).wrapping_sub(disp as u32), addr32);
+		assert_eq!(u32::MAX.wrapping_add(disp as u32).wrapping_sub(disp as u32), u32::MAX);
+	}
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "did not round-trip")]
+fn test_check() {
+	static FOO: [i32; 2] = [1, 2];
+	check::<i32, 0x1234>(&FOO[0], &FOO[1]);
+}
+
+#[cfg(feature = "no-xref")]
+#[test]
+fn test_no_xref() {
+	static FOO: i32 = 42;
+	// The mangled pointer is the true pointer
+	assert_eq!(mangle::<_, 0x1234, 0x5678>(&FOO), &FOO as *const i32 as *const u8);
+	assert_eq!(xref!(&FOO) as *const _, &FOO as *const _);
+	assert_eq!(crate::obfstr!("still obfuscated"), "still obfuscated");
+}
+
+#[test]
+fn test_xref_projection() {
+	struct Inner {
+		tags: [&'static str; 2],
+	}
+	struct Config {
+		name: &'static str,
+		ports: [u16; 3],
+		inner: Inner,
+	}
+	mod statics {
+		pub static LUT: [u32; 4] = [1, 2, 3, 4];
+	}
+	static CONFIG: Config = Config { name: "config", ports: [80, 443, 8080], inner: Inner { tags: ["a", "b"] } };
+	static CONFIGS: [Config; 2] = [
+		Config { name: "first", ports: [1, 2, 3], inner: Inner { tags: ["c", "d"] } },
+		Config { name: "second", ports: [4, 5, 6], inner: Inner { tags: ["e", "f"] } },
+	];
+	static SLICE: &[Config] = &CONFIGS;
+	// Array element
+	assert!(core::ptr::eq(xref!(&statics::LUT[3]), &statics::LUT[3]));
+	assert!(core::ptr::eq(xref!(&statics::LUT[1..]), &statics::LUT[1..]));
+	assert!(core::ptr::eq(xref!(&crate::xref::OBFSTR_TEST_EXTERN_TABLE[2]), &OBFSTR_TEST_EXTERN_TABLE[2]));
+	// Struct field
+	assert!(core::ptr::eq(xref!(&CONFIG.name), &CONFIG.name));
+	assert!(core::ptr::eq(xref!(&CONFIG.inner), &CONFIG.inner));
+	// Nested projections
+	assert!(core::ptr::eq(xref!(&CONFIG.ports[1]), &CONFIG.ports[1]));
+	assert!(core::ptr::eq(xref!(&CONFIG.inner.tags[1]), &CONFIG.inner.tags[1]));
+	assert!(core::ptr::eq(xref!(&CONFIGS[1].inner.tags[0]), &CONFIGS[1].inner.tags[0]));
+	assert!(core::ptr::eq(xref!(&SLICE[1].ports), &CONFIGS[1].ports));
+	// Runtime index
+	let i = core::hint::black_box(1);
+	assert!(core::ptr::eq(xref!(&CONFIGS[i].name), &CONFIGS[1].name));
+	assert_eq!(*xref!(&CONFIGS[i].name), "second");
+}
+
+#[test]
+fn test_obfconst() {
+	macro_rules! check {
+		($($e:expr),*) => {$(
+			assert_eq!(obfconst!($e).to_ne_bytes(), $e.to_ne_bytes(), "{}", stringify!($e));
+		)*};
+	}
+	check!(u8::MIN, u8::MAX, i8::MIN, i8::MAX, 0x80u8, -1i8);
+	check!(u16::MIN, u16::MAX, i16::MIN, i16::MAX, 0xbeefu16);
+	check!(u32::MIN, u32::MAX, i32::MIN, i32::MAX, 0xdeadbeefu32);
+	check!(u64::MIN, u64::MAX, i64::MIN, i64::MAX, 0xdeadbeefcafebabeu64);
+	check!(usize::MIN, usize::MAX, isize::MIN, isize::MAX);
+	check!(f32::MIN, f32::MAX, f32::MIN_POSITIVE, f32::INFINITY, f32::NEG_INFINITY, f32::NAN, -0.0f32, 1.5f32);
+	check!(f64::MIN, f64::MAX, f64::MIN_POSITIVE, f64::INFINITY, f64::NEG_INFINITY, f64::NAN, -0.0f64, core::f64::consts::PI);
+	assert!(obfconst!(true));
+	assert!(!obfconst!(false));
+	// Unsuffixed literals fall back to i32
+	let v = obfconst!(42);
+	assert_eq!(v, 42i32);
+	// The stored bits are masked
+	let masked = const { Masked::new::<0x1234, 0x5678>(u64::MAX) };
+	assert_ne!(masked.bits, u64::MAX);
+	assert_eq!(obfconst::<u64, 0x1234, 0x5678>(masked), u64::MAX);
 }
@@ -0,0 +1,112 @@
+/*!
+xxHash32, a fast hash function for longer inputs.
+*/
+
+const PRIME1: u32 = 0x9e3779b1;
+const PRIME2: u32 = 0x85ebca77;
+const PRIME3: u32 = 0xc2b2ae3d;
+const PRIME4: u32 = 0x27d4eb2f;
+const PRIME5: u32 = 0x165667b1;
+
+/// Compiletime xxHash32 hash.
+///
+/// Accepts a string or a byte string, guarantees compiletime evaluation of the hash.
+///
+/// ```
+/// const HASH: u32 = obfstr::xxhash32!("abc", 0);
+/// assert_eq!(HASH, 0x32d153ff);
+/// assert_eq!(obfstr::xxhash32!(b"abc"), 0x32d153ff);
+/// ```
+#[macro_export]
+macro_rules! xxhash32 {
+	($s:expr, $seed:expr) => {{ const _XXHASH32_HASH: u32 = $crate::xxhash32($crate::AsBytes($s).bytes(), $seed); _XXHASH32_HASH }};
+	($s:expr) => {{ const _XXHASH32_HASH: u32 = $crate::xxhash32($crate::AsBytes($s).bytes(), 0); _XXHASH32_HASH }};
+}
+
+#[inline(always)]
+const fn read32(data: &[u8], i: usize) -> u32 {
+	u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]])
+}
+
+#[inline(always)]
+const fn round(acc: u32, input: u32) -> u32 {
+	acc.wrapping_add(input.wrapping_mul(PRIME2)).rotate_left(13).wrapping_mul(PRIME1)
+}
+
+/// xxHash32 keyed hash function.
+///
+/// Implements the reference algorithm, consumes the input in stripes of 16 bytes followed by the tail.
+/// Also usable in [`hash_eq!`](crate::hash_eq!) with the `xxhash32` form.
+///
+/// ```
+/// assert_eq!(obfstr::xxhash32(b"", 0), 0x02cc5d05);
+/// assert_eq!(obfstr::xxhash32(b"", 0x9e3779b1), 0x36b78ae7);
+/// assert_eq!(obfstr::xxhash32(b"Nobody inspects the spammish repetition", 0), 0xe2293b2f);
+/// ```
+pub const fn xxhash32(data: &[u8], seed: u32) -> u32 {
+	let len = data.len();
+	let mut i = 0;
+
+	let mut h = if len >= 16 {
+		let mut v1 = seed.wrapping_add(PRIME1).wrapping_add(PRIME2);
+		let mut v2 = seed.wrapping_add(PRIME2);
+		let mut v3 = seed;
+		let mut v4 = seed.wrapping_sub(PRIME1);
+		while i + 16 <= len {
+			v1 = round(v1, read32(data, i));
+			v2 = round(v2, read32(data, i + 4));
+			v3 = round(v3, read32(data, i + 8));
+			v4 = round(v4, read32(data, i + 12));
+			i += 16;
+		}
+		v1.rotate_left(1).wrapping_add(v2.rotate_left(7)).wrapping_add(v3.rotate_left(12)).wrapping_add(v4.rotate_left(18))
+	}
+	else {
+		seed.wrapping_add(PRIME5)
+	};
+
+	h = h.wrapping_add(len as u32);
+
+	while i + 4 <= len {
+		h = h.wrapping_add(read32(data, i).wrapping_mul(PRIME3)).rotate_left(17).wrapping_mul(PRIME4);
+		i += 4;
+	}
+	while i < len {
+		h = h.wrapping_add((data[i] as u32).wrapping_mul(PRIME5)).rotate_left(11).wrapping_mul(PRIME1);
+		i += 1;
+	}
+
+	h ^= h >> 15;
+	h = h.wrapping_mul(PRIME2);
+	h ^= h >> 13;
+	h = h.wrapping_mul(PRIME3);
+	h ^= h >> 16;
+	return h;
+}
+
+#[test]
+fn test_sanity() {
+	// Sanity buffer and test vectors from the reference xxhsum
+	let mut buffer = [0u8; 222];
+	let mut byte_gen = PRIME1 as u64;
+	for byte in buffer.iter_mut() {
+		*byte = (byte_gen >> 56) as u8;
+		byte_gen = byte_gen.wrapping_mul(0x9e3779b185ebca8d);
+	}
+	static TEST_VECTORS: [(usize, u32, u32); 8] = [
+		(0, 0, 0x02cc5d05),
+		(0, PRIME1, 0x36b78ae7),
+		(1, 0, 0xcf65b03e),
+		(1, PRIME1, 0xb4545aa4),
+		(14, 0, 0x1208e7e2),
+		(14, PRIME1, 0x6af1d1fe),
+		(222, 0, 0x5bd11dbd),
+		(222, PRIME1, 0x58803c5f),
+	];
+	for &(len, seed, expected) in TEST_VECTORS.iter() {
+		assert_eq!(xxhash32(&buffer[..len], seed), expected);
+	}
+	assert_eq!(xxhash32!(b"abc", 0), 0x32d153ff);
+	assert!(crate::hash_eq!(xxhash32 "xxHash32 is fast", "xxHash32 is fast"));
+	assert!(!crate::hash_eq!(xxhash32 "xxHash32 is fast", "xxHash32 is slow"));
+}